@@ -0,0 +1,557 @@
+//! Compiles the directive schema embedded in the binary.
+//!
+//! Directive names come from the per-section lists, generated by tools/doc-sync
+//! for units (`docs/directives/*.txt`) and maintained by hand for the other
+//! dialects (`docs/networkd/*/*.txt`, `docs/conf/*/*.txt`), and their
+//! documentation from the markdown next to them. `docs/schema/*.ini` adds
+//! the value type, allowed values, defaults and deprecations. The result is
+//! written to `$OUT_DIR/directive_schema.rs` and included by `src/schema.rs`.
+
+use std::collections::HashMap;
+use std::env;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Files of the non-unit dialects: `(UnitDialect variant, docs directory, schema file, man page)`
+const DIALECT_DIRECTORIES: &[(&str, &str, &str, &str)] = &[
+    (
+        "NetworkdNetwork",
+        "docs/networkd/network",
+        "network",
+        "systemd.network.5",
+    ),
+    (
+        "NetworkdNetDev",
+        "docs/networkd/netdev",
+        "netdev",
+        "systemd.netdev.5",
+    ),
+    (
+        "NetworkdLink",
+        "docs/networkd/link",
+        "link",
+        "systemd.link.5",
+    ),
+    (
+        "Journald",
+        "docs/conf/journald",
+        "journald",
+        "journald.conf.5",
+    ),
+    ("Logind", "docs/conf/logind", "logind", "logind.conf.5"),
+    (
+        "SystemConf",
+        "docs/conf/system",
+        "system",
+        "systemd-system.conf.5",
+    ),
+    (
+        "Timesyncd",
+        "docs/conf/timesyncd",
+        "timesyncd",
+        "timesyncd.conf.5",
+    ),
+    (
+        "Resolved",
+        "docs/conf/resolved",
+        "resolved",
+        "resolved.conf.5",
+    ),
+];
+
+const QUADLET_SECTIONS: &[&str] = &[
+    "Container",
+    "Pod",
+    "Volume",
+    "Network",
+    "Kube",
+    "Build",
+    "Image",
+];
+
+const VALUE_TYPES: &[(&str, &str)] = &[
+    ("string", "String"),
+    ("boolean", "Boolean"),
+    ("enum", "Enum"),
+    ("integer", "Integer"),
+    ("percent", "Percent"),
+    ("size", "Size"),
+    ("timespan", "TimeSpan"),
+    ("calendar", "Calendar"),
+    ("path", "Path"),
+    ("units", "Units"),
+    ("command", "Command"),
+    ("environment", "Environment"),
+    ("signal", "Signal"),
+    ("mode", "Mode"),
+    ("cpu-set", "CpuSet"),
+    ("exit-status", "ExitStatus"),
+    ("syscalls", "Syscalls"),
+    ("address-families", "AddressFamilies"),
+    ("capabilities", "Capabilities"),
+    ("socket-address", "SocketAddress"),
+    ("mount-options", "MountOptions"),
+];
+
+const BOOLEAN_VALUES: &[&str] = &["true", "false", "yes", "no", "1", "0"];
+
+const CONVERSIONS: &[(&str, &str)] = &[
+    ("keep", "Keep"),
+    ("cpu-shares-to-weight", "CpuSharesToWeight"),
+    ("block-io-to-io-weight", "BlockIoToIoWeight"),
+    (
+        "block-io-device-to-io-device-weight",
+        "BlockIoDeviceToIoDeviceWeight",
+    ),
+    ("isolate-to-job-mode", "IsolateToJobMode"),
+    ("privileged-exec-prefix", "PrivilegedExecPrefix"),
+];
+
+const KEYS: &[&str] = &[
+    "sections",
+    "type",
+    "list",
+    "values",
+    "since",
+    "default",
+    "deprecated",
+    "removed",
+    "replacement-section",
+    "conversion",
+    "hint",
+];
+
+/// One `[Directive]` block of a schema file
+#[derive(Debug, Default)]
+struct Block {
+    name: String,
+    line: usize,
+    keys: HashMap<String, String>,
+    used: bool,
+}
+
+impl Block {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.keys.get(key).map(String::as_str)
+    }
+
+    fn sections(&self) -> Option<Vec<&str>> {
+        self.get("sections")
+            .map(|sections| sections.split_whitespace().collect())
+    }
+
+    fn applies_to(&self, section: &str) -> bool {
+        self.sections()
+            .is_none_or(|sections| sections.contains(&section))
+    }
+
+    fn is_deprecated(&self) -> bool {
+        self.keys.contains_key("deprecated") || self.keys.contains_key("removed")
+    }
+}
+
+/// A section of a dialect with its directive names and the markdown documenting them
+struct Section {
+    name: String,
+    directives: Vec<String>,
+    /// `(man page, markdown)` in lookup order
+    docs: Vec<(String, String)>,
+}
+
+fn read(path: &Path) -> String {
+    println!("cargo:rerun-if-changed={}", path.display());
+    fs::read_to_string(path).unwrap_or_else(|err| panic!("{}: {}", path.display(), err))
+}
+
+fn directive_list(text: &str) -> Vec<String> {
+    text.lines()
+        .filter(|line| !line.starts_with('#') && !line.trim().is_empty())
+        .map(|line| line.trim().to_string())
+        .collect()
+}
+
+fn parse_schema(path: &Path) -> Vec<Block> {
+    let mut blocks: Vec<Block> = Vec::new();
+    for (index, line) in read(path).lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            blocks.push(Block {
+                name: name.to_string(),
+                line: index + 1,
+                ..Default::default()
+            });
+            continue;
+        }
+        let (Some(block), Some((key, value))) = (blocks.last_mut(), line.split_once('=')) else {
+            panic!(
+                "{}:{}: expected a [Directive] or key = value",
+                path.display(),
+                index + 1
+            );
+        };
+        let key = key.trim();
+        if !KEYS.contains(&key) {
+            panic!("{}:{}: unknown key '{}'", path.display(), index + 1, key);
+        }
+        block.keys.insert(key.to_string(), value.trim().to_string());
+    }
+    blocks
+}
+
+/// The text below `### Name=` up to the next header, without the reference footer
+fn markdown_block(markdown: &str, directive: &str) -> Option<String> {
+    let header = format!("### {}=", directive);
+    let mut lines = markdown.lines().skip_while(|line| *line != header);
+    lines.next()?;
+    let text = lines
+        .take_while(|line| !line.starts_with("### ") && !line.starts_with("## "))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let text = text.trim();
+    let text = match text.rfind("**Reference:**") {
+        Some(position) => text[..position].trim(),
+        None => text,
+    };
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// Version from the "Added in version N." line closing a directive's documentation
+fn added_version(documentation: &str) -> Option<u32> {
+    documentation
+        .lines()
+        .rev()
+        .find(|line| !line.trim().is_empty())?
+        .trim()
+        .strip_prefix("Added in version ")?
+        .strip_suffix('.')?
+        .parse()
+        .ok()
+}
+
+fn section_title(markdown: &str) -> Option<String> {
+    let title = markdown.lines().next()?.strip_prefix("# [")?;
+    Some(title.split_once(']')?.0.to_string())
+}
+
+fn unit_sections(root: &Path) -> Vec<Section> {
+    let shared = |name: &str| -> (String, String) {
+        (
+            format!("systemd.{}.5", name),
+            read(&root.join(format!("docs/{}.md", name))),
+        )
+    };
+
+    directive_list(&read(&root.join("docs/sections.txt")))
+        .into_iter()
+        .map(|name| {
+            let lower = name.to_lowercase();
+            let man_page = match name.as_str() {
+                "Unit" | "Install" => "systemd.unit.5".to_string(),
+                _ if QUADLET_SECTIONS.contains(&name.as_str()) => {
+                    "podman-systemd.unit.5".to_string()
+                }
+                _ => format!("systemd.{}.5", lower),
+            };
+            let mut docs = vec![(man_page, read(&root.join(format!("docs/{}.md", lower))))];
+            let list = root.join(format!("docs/directives/{}.txt", lower));
+            let directives = directive_list(&read(&list));
+            // Directives of systemd.exec(5), systemd.kill(5) and systemd.resource-control(5),
+            // which doc-sync merges into the lists of the sections accepting them
+            let shared_docs: &[&str] = match lower.as_str() {
+                "service" | "socket" | "mount" | "swap" => &["exec", "kill", "resource-control"],
+                "scope" => &["kill", "resource-control"],
+                "slice" => &["resource-control"],
+                _ => &[],
+            };
+            for doc in shared_docs {
+                let shared_list =
+                    root.join(format!("docs/directives/{}.txt", doc.replace('-', "")));
+                if let Some(missing) = directive_list(&read(&shared_list))
+                    .into_iter()
+                    .find(|directive| !directives.contains(directive))
+                {
+                    panic!(
+                        "{}: missing {}= from {}, regenerate the lists with `make gen-docs`",
+                        list.display(),
+                        missing,
+                        shared_list.display()
+                    );
+                }
+                docs.push(shared(doc));
+            }
+
+            Section {
+                directives,
+                name,
+                docs,
+            }
+        })
+        .collect()
+}
+
+fn dialect_sections(root: &Path, directory: &str) -> Vec<Section> {
+    let directory = root.join(directory);
+    println!("cargo:rerun-if-changed={}", directory.display());
+    let mut lists: Vec<PathBuf> = fs::read_dir(&directory)
+        .unwrap_or_else(|err| panic!("{}: {}", directory.display(), err))
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+        .collect();
+    lists.sort();
+
+    lists
+        .into_iter()
+        .map(|list| {
+            let markdown = read(&list.with_extension("md"));
+            Section {
+                name: section_title(&markdown)
+                    .unwrap_or_else(|| panic!("{}: missing '# [Section]' title", list.display())),
+                directives: directive_list(&read(&list)),
+                docs: vec![(String::new(), markdown)],
+            }
+        })
+        .collect()
+}
+
+fn rust_option(value: Option<&str>) -> String {
+    match value {
+        Some(value) => format!("Some({:?})", value),
+        None => "None".to_string(),
+    }
+}
+
+fn lookup<'a>(table: &[(&str, &'a str)], key: &str, what: &str, block: &Block) -> &'a str {
+    table
+        .iter()
+        .find(|(name, _)| *name == key)
+        .map(|(_, variant)| *variant)
+        .unwrap_or_else(|| {
+            panic!(
+                "[{}] line {}: unknown {} '{}'",
+                block.name, block.line, what, key
+            )
+        })
+}
+
+struct Generator {
+    output: String,
+    documents: HashMap<String, usize>,
+    constants: String,
+}
+
+impl Generator {
+    fn document(&mut self, text: &str) -> String {
+        let next = self.documents.len();
+        let index = *self.documents.entry(text.to_string()).or_insert_with(|| {
+            writeln!(self.constants, "const DOC_{}: &str = {:?};", next, text).unwrap();
+            next
+        });
+        format!("Some(DOC_{})", index)
+    }
+
+    fn entry(
+        &mut self,
+        dialect: &str,
+        section: &str,
+        name: &str,
+        block: Option<&Block>,
+        documentation: Option<(&str, String)>,
+    ) {
+        let get = |key: &str| block.and_then(|block| block.get(key));
+        let value_type = match (get("type"), block) {
+            (Some(value_type), Some(block)) => lookup(VALUE_TYPES, value_type, "type", block),
+            _ => "String",
+        };
+        let list = get("list").is_some_and(|list| list == "yes");
+        // Booleans offer the canonical spellings ahead of their extra keywords
+        let canonical: &[&str] = match value_type {
+            "Boolean" => BOOLEAN_VALUES,
+            _ => &[],
+        };
+        let values: Vec<String> = canonical
+            .iter()
+            .copied()
+            .chain(get("values").unwrap_or_default().split_whitespace())
+            .map(|value| format!("{:?}", value))
+            .collect();
+        let value_since: Vec<String> = get("since")
+            .map(|since| {
+                since
+                    .split_whitespace()
+                    .map(|item| {
+                        let (value, version) = item.rsplit_once('@').unwrap_or_else(|| {
+                            panic!("[{}]: since expects value@version, got '{}'", name, item)
+                        });
+                        let version: u32 = version.parse().unwrap_or_else(|_| {
+                            panic!("[{}]: invalid version in '{}'", name, item)
+                        });
+                        format!("({:?}, {})", value, version)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let deprecation = match block.filter(|block| block.is_deprecated()) {
+            Some(block) => {
+                let removed = block.keys.contains_key("removed");
+                let replacement = block
+                    .get("deprecated")
+                    .or(block.get("removed"))
+                    .filter(|replacement| *replacement != "yes");
+                let conversion = lookup(
+                    CONVERSIONS,
+                    block.get("conversion").unwrap_or("keep"),
+                    "conversion",
+                    block,
+                );
+                format!(
+                    "Some(Deprecation {{ replacement: {}, replacement_section: {}, conversion: ValueConversion::{}, removed: {}, hint: {} }})",
+                    rust_option(replacement),
+                    rust_option(block.get("replacement-section")),
+                    conversion,
+                    removed,
+                    rust_option(block.get("hint")),
+                )
+            }
+            None => "None".to_string(),
+        };
+
+        let (man_page, since, documentation) = match documentation {
+            Some((man_page, text)) => (man_page, added_version(&text), self.document(&text)),
+            None => ("", None, "None".to_string()),
+        };
+
+        writeln!(
+            self.output,
+            "    DirectiveSchema {{ dialect: UnitDialect::{}, section: {:?}, name: {:?}, value_type: ValueType::{}, list: {}, values: &[{}], value_since: &[{}], default: {}, since: {}, man_page: {:?}, documentation: {}, deprecation: {} }},",
+            dialect,
+            section,
+            name,
+            value_type,
+            list,
+            values.join(", "),
+            value_since.join(", "),
+            rust_option(get("default")),
+            since.map_or("None".to_string(), |since| format!("Some({})", since)),
+            man_page,
+            documentation,
+            deprecation,
+        )
+        .unwrap();
+    }
+
+    fn dialect(
+        &mut self,
+        dialect: &str,
+        dialect_man_page: &str,
+        sections: &[Section],
+        schema: &Path,
+    ) {
+        let mut blocks = parse_schema(schema);
+
+        for section in sections {
+            for name in &section.directives {
+                let index = blocks
+                    .iter()
+                    .position(|block| block.name == *name && block.applies_to(&section.name));
+                if let Some(index) = index {
+                    if blocks[index].is_deprecated() {
+                        panic!(
+                            "{}: [{}] is deprecated but still listed in [{}]",
+                            schema.display(),
+                            name,
+                            section.name
+                        );
+                    }
+                    blocks[index].used = true;
+                }
+                let documentation = section.docs.iter().find_map(|(man_page, markdown)| {
+                    let man_page = if man_page.is_empty() {
+                        dialect_man_page
+                    } else {
+                        man_page
+                    };
+                    markdown_block(markdown, name).map(|text| (man_page, text))
+                });
+                self.entry(
+                    dialect,
+                    &section.name,
+                    name,
+                    index.map(|index| &blocks[index]),
+                    documentation,
+                );
+            }
+        }
+
+        // Deprecated directives are no longer in the upstream lists
+        for block in blocks.iter_mut().filter(|block| block.is_deprecated()) {
+            let known = block.sections().is_some_and(|names| {
+                names
+                    .iter()
+                    .all(|name| sections.iter().any(|section| section.name == *name))
+            });
+            if !known {
+                panic!(
+                    "{}:{}: deprecated [{}] needs the sections it was accepted in",
+                    schema.display(),
+                    block.line,
+                    block.name
+                );
+            }
+            block.used = true;
+        }
+        for block in blocks.iter().filter(|block| block.is_deprecated()) {
+            for section in block.sections().unwrap_or_default() {
+                self.entry(dialect, section, &block.name, Some(block), None);
+            }
+        }
+
+        if let Some(block) = blocks.iter().find(|block| !block.used) {
+            panic!(
+                "{}:{}: [{}] does not match any listed directive",
+                schema.display(),
+                block.line,
+                block.name
+            );
+        }
+    }
+}
+
+fn main() {
+    let root = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    println!("cargo:rerun-if-changed=docs/schema");
+
+    let mut generator = Generator {
+        output: String::new(),
+        documents: HashMap::new(),
+        constants: String::new(),
+    };
+
+    generator.dialect(
+        "Systemd",
+        "",
+        &unit_sections(&root),
+        &root.join("docs/schema/unit.ini"),
+    );
+    let mut man_pages = String::new();
+    for (dialect, directory, schema, man_page) in DIALECT_DIRECTORIES {
+        writeln!(man_pages, "    (UnitDialect::{}, {:?}),", dialect, man_page).unwrap();
+        generator.dialect(
+            dialect,
+            man_page,
+            &dialect_sections(&root, directory),
+            &root.join(format!("docs/schema/{}.ini", schema)),
+        );
+    }
+
+    let code = format!(
+        "// Generated by build.rs from docs/schema/*.ini and the directive documentation\n\n{}\npub static DIRECTIVES: &[DirectiveSchema] = &[\n{}];\n\npub static DIALECT_MAN_PAGES: &[(UnitDialect, &str)] = &[\n{}];\n",
+        generator.constants, generator.output, man_pages
+    );
+    let out = PathBuf::from(env::var("OUT_DIR").unwrap()).join("directive_schema.rs");
+    fs::write(&out, code).unwrap_or_else(|err| panic!("{}: {}", out.display(), err));
+}
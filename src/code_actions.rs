@@ -0,0 +1,726 @@
+use crate::constants::SystemdConstants;
+use crate::diagnostics::{
+    is_service_file, DEPRECATED_DIRECTIVE_CODE, MISSING_ACTIVATED_UNIT_CODE, MOUNT_NAME_CODE,
+    SECURITY_EXPOSURE_CODE,
+};
+use crate::parser::{SystemdDirective, SystemdSection, SystemdUnit};
+use crate::schema::{Deprecation, ValueConversion};
+use crate::security::SecurityReport;
+use crate::templates::SystemdTemplates;
+use log::{debug, trace};
+use std::collections::HashMap;
+use tower_lsp_server::lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOrCommand, CreateFile, CreateFileOptions, Diagnostic,
+    DocumentChangeOperation, DocumentChanges, NumberOrString, OneOf,
+    OptionalVersionedTextDocumentIdentifier, Position, Range, RenameFile, RenameFileOptions,
+    ResourceOp, TextDocumentEdit, TextEdit, Uri, WorkspaceEdit,
+};
+use tower_lsp_server::UriExt;
+
+/// Commands `PermissionsStartOnly=` used to exempt from the sandboxing and user settings
+const AUXILIARY_EXEC_DIRECTIVES: &[&str] = &[
+    "ExecCondition",
+    "ExecStartPre",
+    "ExecStartPost",
+    "ExecReload",
+    "ExecStop",
+    "ExecStopPost",
+];
+
+#[derive(Debug)]
+pub struct SystemdCodeActions;
+
+impl Default for SystemdCodeActions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SystemdCodeActions {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Quick fixes migrating the deprecated directives on the lines covered by `range`
+    pub fn get_code_actions(
+        &self,
+        uri: &Uri,
+        unit: &SystemdUnit,
+        range: &Range,
+        diagnostics: &[Diagnostic],
+    ) -> Vec<CodeActionOrCommand> {
+        if !unit.dialect.is_unit() {
+            return Vec::new();
+        }
+
+        let mut actions = Vec::new();
+        for section in unit.sections.values() {
+            for directive in &section.directives {
+                if directive.end_line_number < range.start.line
+                    || directive.line_number > range.end.line
+                {
+                    continue;
+                }
+                let Some(deprecated) =
+                    SystemdConstants::deprecated_directive(&section.name, &directive.key)
+                        .and_then(|entry| entry.deprecation)
+                else {
+                    continue;
+                };
+
+                trace!(
+                    "Offering migration for {}= on line {}",
+                    directive.key,
+                    directive.line_number
+                );
+                let (title, edits) = Self::migration(unit, section, directive, &deprecated);
+                let fixed_diagnostics: Vec<Diagnostic> = diagnostics
+                    .iter()
+                    .filter(|diagnostic| {
+                        diagnostic.range.start.line == directive.line_number
+                            && diagnostic.code
+                                == Some(NumberOrString::String(
+                                    DEPRECATED_DIRECTIVE_CODE.to_string(),
+                                ))
+                    })
+                    .cloned()
+                    .collect();
+
+                let action = CodeAction {
+                    title,
+                    kind: Some(CodeActionKind::QUICKFIX),
+                    diagnostics: (!fixed_diagnostics.is_empty()).then_some(fixed_diagnostics),
+                    edit: Some(WorkspaceEdit {
+                        changes: Some(HashMap::from([(uri.clone(), edits)])),
+                        ..Default::default()
+                    }),
+                    is_preferred: Some(true),
+                    ..Default::default()
+                };
+                actions.push((directive.line_number, action));
+            }
+        }
+
+        if is_service_file(uri) {
+            actions.extend(Self::hardening(uri, unit, range, diagnostics));
+        }
+
+        actions.sort_by_key(|(line, _)| *line);
+        debug!("Generated {} code actions for {:?}", actions.len(), uri);
+        actions
+            .into_iter()
+            .map(|(_, action)| CodeActionOrCommand::CodeAction(action))
+            .collect()
+    }
+
+    /// Quick fixes creating the service a timer, path or socket unit activates next to it,
+    /// from the template the diagnostic reporting it missing names
+    pub fn missing_unit_actions(
+        &self,
+        uri: &Uri,
+        diagnostics: &[Diagnostic],
+        templates: &SystemdTemplates,
+    ) -> Vec<CodeActionOrCommand> {
+        diagnostics
+            .iter()
+            .filter(|diagnostic| {
+                diagnostic.code
+                    == Some(NumberOrString::String(
+                        MISSING_ACTIVATED_UNIT_CODE.to_string(),
+                    ))
+            })
+            .filter_map(|diagnostic| {
+                let data = diagnostic.data.as_ref()?;
+                let name = data.get("unit")?.as_str()?;
+                if !name.ends_with(".service") {
+                    return None;
+                }
+                let template = templates.get("service", data.get("template")?.as_str()?)?;
+                let created = Uri::from_file_path(uri.to_file_path()?.with_file_name(name))?;
+                let operations = vec![
+                    DocumentChangeOperation::Op(ResourceOp::Create(CreateFile {
+                        uri: created.clone(),
+                        options: Some(CreateFileOptions {
+                            overwrite: Some(false),
+                            ignore_if_exists: Some(true),
+                        }),
+                        annotation_id: None,
+                    })),
+                    DocumentChangeOperation::Edit(TextDocumentEdit {
+                        text_document: OptionalVersionedTextDocumentIdentifier {
+                            uri: created,
+                            version: None,
+                        },
+                        edits: vec![OneOf::Left(TextEdit::new(
+                            Range::default(),
+                            format!("{}\n", template.preview()),
+                        ))],
+                    }),
+                ];
+
+                Some(CodeActionOrCommand::CodeAction(CodeAction {
+                    title: format!("Create {} from the {} template", name, template.name),
+                    kind: Some(CodeActionKind::QUICKFIX),
+                    diagnostics: Some(vec![diagnostic.clone()]),
+                    edit: Some(WorkspaceEdit {
+                        document_changes: Some(DocumentChanges::Operations(operations)),
+                        ..Default::default()
+                    }),
+                    is_preferred: Some(true),
+                    ..Default::default()
+                }))
+            })
+            .collect()
+    }
+
+    /// Quick fixes for mount and automount units not named after their `Where=` path:
+    /// renaming the file, or else pointing `Where=` at the path the name stands for
+    pub fn mount_name_actions(
+        &self,
+        uri: &Uri,
+        diagnostics: &[Diagnostic],
+    ) -> Vec<CodeActionOrCommand> {
+        let mut actions = Vec::new();
+        for diagnostic in diagnostics.iter().filter(|diagnostic| {
+            diagnostic.code == Some(NumberOrString::String(MOUNT_NAME_CODE.to_string()))
+        }) {
+            let Some(data) = diagnostic.data.as_ref() else {
+                continue;
+            };
+            let name = data.get("name").and_then(|name| name.as_str());
+            let renamed = name.and_then(|name| {
+                Uri::from_file_path(uri.to_file_path()?.with_file_name(name)).zip(Some(name))
+            });
+            if let Some((renamed, name)) = renamed {
+                actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                    title: format!("Rename the file to {}", name),
+                    kind: Some(CodeActionKind::QUICKFIX),
+                    diagnostics: Some(vec![diagnostic.clone()]),
+                    edit: Some(WorkspaceEdit {
+                        document_changes: Some(DocumentChanges::Operations(vec![
+                            DocumentChangeOperation::Op(ResourceOp::Rename(RenameFile {
+                                old_uri: uri.clone(),
+                                new_uri: renamed,
+                                options: Some(RenameFileOptions {
+                                    overwrite: Some(false),
+                                    ignore_if_exists: Some(false),
+                                }),
+                                annotation_id: None,
+                            })),
+                        ])),
+                        ..Default::default()
+                    }),
+                    is_preferred: Some(true),
+                    ..Default::default()
+                }));
+            }
+            if let Some(path) = data.get("where").and_then(|path| path.as_str()) {
+                actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                    title: format!("Change Where= to {}", path),
+                    kind: Some(CodeActionKind::QUICKFIX),
+                    diagnostics: Some(vec![diagnostic.clone()]),
+                    edit: Some(WorkspaceEdit {
+                        changes: Some(HashMap::from([(
+                            uri.clone(),
+                            vec![TextEdit::new(diagnostic.range, path.to_string())],
+                        )])),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }));
+            }
+        }
+        actions
+    }
+
+    /// Quick fixes applying the recommendation of each failed security check, offered on
+    /// the `[Service]` header that carries the exposure diagnostic
+    fn hardening(
+        uri: &Uri,
+        unit: &SystemdUnit,
+        range: &Range,
+        diagnostics: &[Diagnostic],
+    ) -> Vec<(u32, CodeAction)> {
+        let (Some(section), Some(report)) =
+            (unit.sections.get("Service"), SecurityReport::for_unit(unit))
+        else {
+            return Vec::new();
+        };
+        let header = section.line_range.0;
+        if header < range.start.line || header > range.end.line {
+            return Vec::new();
+        }
+
+        let exposure_diagnostics: Vec<Diagnostic> = diagnostics
+            .iter()
+            .filter(|diagnostic| {
+                diagnostic.code == Some(NumberOrString::String(SECURITY_EXPOSURE_CODE.to_string()))
+            })
+            .cloned()
+            .collect();
+
+        report
+            .failed()
+            .into_iter()
+            .map(|finding| {
+                let check = finding.check;
+                let edit = match section
+                    .directives
+                    .iter()
+                    .rev()
+                    .find(|directive| directive.key == check.recommended_directive())
+                {
+                    Some(directive) => TextEdit::new(
+                        Self::directive_lines(directive),
+                        format!("{}\n", check.recommendation),
+                    ),
+                    None => TextEdit::new(
+                        Range::new(
+                            Self::insertion_point(section),
+                            Self::insertion_point(section),
+                        ),
+                        format!("{}\n", check.recommendation),
+                    ),
+                };
+                let setting = if check.recommendation.len() > 40 {
+                    format!("{}=…", check.recommended_directive())
+                } else {
+                    check.recommendation.to_string()
+                };
+
+                let action = CodeAction {
+                    title: format!(
+                        "Harden with {} (exposure {:.1} → {:.1})",
+                        setting,
+                        report.exposure(),
+                        report.exposure_with(check)
+                    ),
+                    kind: Some(CodeActionKind::QUICKFIX),
+                    diagnostics: (!exposure_diagnostics.is_empty())
+                        .then(|| exposure_diagnostics.clone()),
+                    edit: Some(WorkspaceEdit {
+                        changes: Some(HashMap::from([(uri.clone(), vec![edit])])),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                };
+                (header, action)
+            })
+            .collect()
+    }
+
+    /// Title and edits replacing a deprecated directive with its successor
+    fn migration(
+        unit: &SystemdUnit,
+        section: &SystemdSection,
+        directive: &SystemdDirective,
+        deprecated: &Deprecation,
+    ) -> (String, Vec<TextEdit>) {
+        let Some(replacement) = deprecated.replacement else {
+            return Self::removal(section, directive, deprecated);
+        };
+
+        let value = Self::convert_value(deprecated.conversion, &directive.value);
+        let new_line = format!("{}={}", replacement, value);
+        let target_name = deprecated
+            .replacement_section
+            .unwrap_or(section.name.as_str());
+        let target = unit.sections.get(target_name);
+
+        let already_set = target.is_some_and(|target| {
+            target
+                .directives
+                .iter()
+                .any(|existing| existing.key == replacement)
+        });
+        if already_set {
+            return (
+                format!("Remove {}=, {}= is already set", directive.key, replacement),
+                vec![TextEdit::new(
+                    Self::directive_lines(directive),
+                    String::new(),
+                )],
+            );
+        }
+
+        if deprecated.replacement_section.is_none() {
+            let end = directive
+                .value_spans
+                .last()
+                .map(|span| Position::new(span.line, span.end))
+                .unwrap_or(Position::new(
+                    directive.line_number,
+                    directive.column_range.1,
+                ));
+            let start = Position::new(directive.line_number, directive.column_range.0);
+            return (
+                format!("Replace {}= with {}=", directive.key, replacement),
+                vec![TextEdit::new(Range::new(start, end), new_line)],
+            );
+        }
+
+        let insertion = match target {
+            Some(target) => TextEdit::new(
+                Range::new(Self::insertion_point(target), Self::insertion_point(target)),
+                format!("{}\n", new_line),
+            ),
+            None => TextEdit::new(
+                Range::new(Position::new(0, 0), Position::new(0, 0)),
+                format!("[{}]\n{}\n\n", target_name, new_line),
+            ),
+        };
+
+        (
+            format!(
+                "Move {}= to {}= in [{}]",
+                directive.key, replacement, target_name
+            ),
+            vec![
+                insertion,
+                TextEdit::new(Self::directive_lines(directive), String::new()),
+            ],
+        )
+    }
+
+    fn removal(
+        section: &SystemdSection,
+        directive: &SystemdDirective,
+        deprecated: &Deprecation,
+    ) -> (String, Vec<TextEdit>) {
+        let mut edits = vec![TextEdit::new(
+            Self::directive_lines(directive),
+            String::new(),
+        )];
+
+        if deprecated.conversion != ValueConversion::PrivilegedExecPrefix
+            || !Self::is_enabled(&directive.value)
+        {
+            return (format!("Remove {}=", directive.key), edits);
+        }
+
+        // The auxiliary commands keep running with full privileges through the '+' prefix
+        for command in section
+            .directives
+            .iter()
+            .filter(|other| AUXILIARY_EXEC_DIRECTIVES.contains(&other.key.as_str()))
+        {
+            let prefixes: String = command
+                .value
+                .chars()
+                .take_while(|c| matches!(c, '@' | '-' | ':' | '+' | '!'))
+                .collect();
+            if prefixes.contains(['+', '!']) || command.value.is_empty() {
+                continue;
+            }
+            if let Some(span) = command.value_spans.first() {
+                let position = Position::new(span.line, span.start);
+                edits.push(TextEdit::new(
+                    Range::new(position, position),
+                    "+".to_string(),
+                ));
+            }
+        }
+
+        (
+            format!("Replace {}= with '+' command prefixes", directive.key),
+            edits,
+        )
+    }
+
+    fn convert_value(conversion: ValueConversion, value: &str) -> String {
+        match conversion {
+            ValueConversion::Keep | ValueConversion::PrivilegedExecPrefix => value.to_string(),
+            ValueConversion::CpuSharesToWeight => Self::convert_weight(value, 1024),
+            ValueConversion::BlockIoToIoWeight => Self::convert_weight(value, 500),
+            ValueConversion::BlockIoDeviceToIoDeviceWeight => {
+                match value.trim_end().rsplit_once(char::is_whitespace) {
+                    Some((device, weight)) => {
+                        format!(
+                            "{} {}",
+                            device.trim_end(),
+                            Self::convert_weight(weight, 500)
+                        )
+                    }
+                    None => value.to_string(),
+                }
+            }
+            ValueConversion::IsolateToJobMode => {
+                if Self::is_enabled(value) {
+                    "isolate".to_string()
+                } else {
+                    "replace".to_string()
+                }
+            }
+        }
+    }
+
+    /// Scale a legacy weight onto the cgroup v2 range (1…10000, default 100) like systemd does
+    fn convert_weight(value: &str, legacy_default: u64) -> String {
+        match value.trim().parse::<u64>() {
+            Ok(weight) => (weight.saturating_mul(100) / legacy_default)
+                .clamp(1, 10000)
+                .to_string(),
+            Err(_) => value.to_string(),
+        }
+    }
+
+    fn is_enabled(value: &str) -> bool {
+        matches!(
+            value.trim().to_ascii_lowercase().as_str(),
+            "yes" | "true" | "1" | "on"
+        )
+    }
+
+    /// The full lines of a directive, including continuation lines
+    fn directive_lines(directive: &SystemdDirective) -> Range {
+        Range::new(
+            Position::new(directive.line_number, 0),
+            Position::new(directive.end_line_number + 1, 0),
+        )
+    }
+
+    /// Start of the line following the last directive of a section
+    fn insertion_point(section: &SystemdSection) -> Position {
+        let line = section
+            .directives
+            .iter()
+            .map(|directive| directive.end_line_number + 1)
+            .max()
+            .unwrap_or(section.line_range.0 + 1);
+        Position::new(line, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::SystemdParser;
+
+    fn apply(text: &str, mut edits: Vec<TextEdit>) -> String {
+        let mut lines: Vec<String> = text.split('\n').map(str::to_string).collect();
+        // Apply from the bottom up so earlier positions stay valid
+        edits.sort_by_key(|edit| (edit.range.start.line, edit.range.start.character));
+        for edit in edits.into_iter().rev() {
+            let offset = |position: Position, lines: &[String]| -> usize {
+                lines[..position.line as usize]
+                    .iter()
+                    .map(|line| line.len() + 1)
+                    .sum::<usize>()
+                    + position.character as usize
+            };
+            let mut joined = lines.join("\n");
+            let start = offset(edit.range.start, &lines);
+            let end = offset(edit.range.end, &lines).min(joined.len());
+            joined.replace_range(start..end, &edit.new_text);
+            lines = joined.split('\n').map(str::to_string).collect();
+        }
+        lines.join("\n")
+    }
+
+    fn migrate(text: &str, line: u32) -> (String, String) {
+        let parser = SystemdParser::new();
+        let uri = "file:///legacy.service".parse::<Uri>().unwrap();
+        let unit = parser.parse(text);
+        let range = Range::new(Position::new(line, 0), Position::new(line, 0));
+
+        let actions = SystemdCodeActions::new().get_code_actions(&uri, &unit, &range, &[]);
+        assert_eq!(actions.len(), 1, "{:?}", actions);
+        let CodeActionOrCommand::CodeAction(action) = &actions[0] else {
+            panic!("expected a code action");
+        };
+        let edits = action.edit.as_ref().unwrap().changes.as_ref().unwrap()[&uri].clone();
+        (action.title.clone(), apply(text, edits))
+    }
+
+    #[test]
+    fn test_cpu_shares_are_converted_to_weight() {
+        let text = "[Service]\nExecStart=/bin/true\nCPUShares=512\n";
+        let (title, migrated) = migrate(text, 2);
+        assert_eq!(title, "Replace CPUShares= with CPUWeight=");
+        assert_eq!(migrated, "[Service]\nExecStart=/bin/true\nCPUWeight=50\n");
+    }
+
+    #[test]
+    fn test_block_io_weights_are_converted() {
+        assert_eq!(
+            SystemdCodeActions::convert_value(ValueConversion::BlockIoToIoWeight, "1000"),
+            "200"
+        );
+        assert_eq!(
+            SystemdCodeActions::convert_value(
+                ValueConversion::BlockIoDeviceToIoDeviceWeight,
+                "/dev/sda 10"
+            ),
+            "/dev/sda 2"
+        );
+        assert_eq!(
+            SystemdCodeActions::convert_value(ValueConversion::CpuSharesToWeight, "262144"),
+            "10000"
+        );
+    }
+
+    #[test]
+    fn test_start_limit_interval_moves_to_unit_section() {
+        let text = "[Unit]\nDescription=Legacy\n\n[Service]\nExecStart=/bin/true\nStartLimitInterval=30s\n";
+        let (title, migrated) = migrate(text, 5);
+        assert_eq!(
+            title,
+            "Move StartLimitInterval= to StartLimitIntervalSec= in [Unit]"
+        );
+        assert_eq!(
+            migrated,
+            "[Unit]\nDescription=Legacy\nStartLimitIntervalSec=30s\n\n[Service]\nExecStart=/bin/true\n"
+        );
+    }
+
+    #[test]
+    fn test_permissions_start_only_prefixes_commands() {
+        let text = "[Service]\nPermissionsStartOnly=true\nExecStartPre=/usr/bin/mkdir -p /run/app\nExecStartPre=-+/usr/bin/chown app /run/app\nExecStart=/usr/bin/app\n";
+        let (title, migrated) = migrate(text, 1);
+        assert_eq!(
+            title,
+            "Replace PermissionsStartOnly= with '+' command prefixes"
+        );
+        assert_eq!(
+            migrated,
+            "[Service]\nExecStartPre=+/usr/bin/mkdir -p /run/app\nExecStartPre=-+/usr/bin/chown app /run/app\nExecStart=/usr/bin/app\n"
+        );
+    }
+
+    #[test]
+    fn test_hardening_actions_on_service_header() {
+        let text = "[Service]\nExecStart=/usr/bin/app\nProtectSystem=no\n";
+        let parser = SystemdParser::new();
+        let uri = "file:///app.service".parse::<Uri>().unwrap();
+        let unit = parser.parse(text);
+        let header = Range::new(Position::new(0, 0), Position::new(0, 0));
+        let actions = SystemdCodeActions::new().get_code_actions(&uri, &unit, &header, &[]);
+
+        let find = |prefix: &str| {
+            actions
+                .iter()
+                .find_map(|action| match action {
+                    CodeActionOrCommand::CodeAction(action) if action.title.starts_with(prefix) => {
+                        Some(action.clone())
+                    }
+                    _ => None,
+                })
+                .unwrap_or_else(|| panic!("no action starting with {}: {:?}", prefix, actions))
+        };
+        let edits = |action: &CodeAction| {
+            action.edit.as_ref().unwrap().changes.as_ref().unwrap()[&uri].clone()
+        };
+
+        let private_tmp = find("Harden with PrivateTmp=yes (exposure ");
+        assert_eq!(
+            apply(text, edits(&private_tmp)),
+            "[Service]\nExecStart=/usr/bin/app\nProtectSystem=no\nPrivateTmp=yes\n"
+        );
+        // A directive set to a weaker value is replaced
+        let protect_system = find("Harden with ProtectSystem=strict");
+        assert_eq!(
+            apply(text, edits(&protect_system)),
+            "[Service]\nExecStart=/usr/bin/app\nProtectSystem=strict\n"
+        );
+        find("Harden with CapabilityBoundingSet=…");
+
+        // Only on the header, and only for complete service units
+        let body = Range::new(Position::new(1, 0), Position::new(1, 0));
+        assert!(SystemdCodeActions::new()
+            .get_code_actions(&uri, &unit, &body, &[])
+            .is_empty());
+        let drop_in = "file:///etc/systemd/system/app.service.d/override.conf"
+            .parse::<Uri>()
+            .unwrap();
+        assert!(SystemdCodeActions::new()
+            .get_code_actions(&drop_in, &unit, &header, &[])
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_missing_service_is_created_from_template() {
+        let diagnostics = crate::diagnostics::SystemdDiagnostics::new();
+        let uri = "file:///srv/units/nightly-report.timer"
+            .parse::<Uri>()
+            .unwrap();
+        diagnostics
+            .update(
+                &uri,
+                SystemdParser::new().parse("[Timer]\nOnCalendar=daily\n"),
+            )
+            .await;
+        let found = diagnostics.get_diagnostics(&uri).await;
+
+        let actions =
+            SystemdCodeActions::new().missing_unit_actions(&uri, &found, &SystemdTemplates::new());
+        assert_eq!(actions.len(), 1, "{:?}", found);
+        let CodeActionOrCommand::CodeAction(action) = &actions[0] else {
+            panic!("expected a code action");
+        };
+        assert_eq!(
+            action.title,
+            "Create nightly-report.service from the oneshot-service template"
+        );
+        let Some(DocumentChanges::Operations(operations)) =
+            &action.edit.as_ref().unwrap().document_changes
+        else {
+            panic!("expected resource operations");
+        };
+        let created = "file:///srv/units/nightly-report.service"
+            .parse::<Uri>()
+            .unwrap();
+        assert!(matches!(
+            &operations[0],
+            DocumentChangeOperation::Op(ResourceOp::Create(create)) if create.uri == created
+        ));
+        let DocumentChangeOperation::Edit(edit) = &operations[1] else {
+            panic!("expected the content of the service");
+        };
+        let OneOf::Left(text) = &edit.edits[0] else {
+            panic!("expected a plain text edit");
+        };
+        assert!(text.new_text.contains("Type=oneshot"), "{}", text.new_text);
+        assert!(!text.new_text.contains("${"), "{}", text.new_text);
+    }
+
+    #[tokio::test]
+    async fn test_mount_is_renamed_after_its_path() {
+        let diagnostics = crate::diagnostics::SystemdDiagnostics::new();
+        let uri = "file:///srv/units/data.mount".parse::<Uri>().unwrap();
+        diagnostics
+            .update(
+                &uri,
+                SystemdParser::new().parse("[Mount]\nWhat=/dev/sdb1\nWhere=/var/lib/data\n"),
+            )
+            .await;
+        let found = diagnostics.get_diagnostics(&uri).await;
+
+        let actions = SystemdCodeActions::new().mount_name_actions(&uri, &found);
+        let [CodeActionOrCommand::CodeAction(rename), CodeActionOrCommand::CodeAction(change)] =
+            actions.as_slice()
+        else {
+            panic!("expected two code actions, got {:?}", actions);
+        };
+        assert_eq!(rename.title, "Rename the file to var-lib-data.mount");
+        let Some(DocumentChanges::Operations(operations)) =
+            &rename.edit.as_ref().unwrap().document_changes
+        else {
+            panic!("expected resource operations");
+        };
+        assert!(matches!(
+            &operations[0],
+            DocumentChangeOperation::Op(ResourceOp::Rename(rename))
+                if rename.new_uri == "file:///srv/units/var-lib-data.mount".parse::<Uri>().unwrap()
+        ));
+
+        assert_eq!(change.title, "Change Where= to /data");
+        let edits = &change.edit.as_ref().unwrap().changes.as_ref().unwrap()[&uri];
+        assert_eq!(
+            edits[0],
+            TextEdit::new(
+                Range::new(Position::new(2, 6), Position::new(2, 19)),
+                "/data".to_string()
+            )
+        );
+    }
+}
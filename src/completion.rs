@@ -1,16 +1,63 @@
 use crate::constants::SystemdConstants;
+use crate::dialect::UnitDialect;
 use crate::parser::SystemdParser;
-use log::{debug, trace};
+use crate::schema::{self, DirectiveSchema, ValueType};
+use crate::templates::SystemdTemplates;
+use crate::value_providers::{self, ValueProvider};
+use log::{debug, info, trace};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::Path;
+use std::sync::RwLock;
 use tower_lsp_server::lsp_types::{
-    CompletionItem, CompletionItemKind, CompletionResponse, Documentation, MarkupContent,
-    MarkupKind, Position, Uri,
+    CompletionItem, CompletionItemKind, CompletionItemLabelDetails, CompletionItemTag,
+    CompletionResponse, CompletionTextEdit, Documentation, InsertTextFormat, MarkupContent,
+    MarkupKind, Position, Range, TextEdit, Uri,
 };
 
 #[derive(Debug)]
 pub struct SystemdCompletion {
     section_completions: Vec<CompletionItem>,
     directive_completions: HashMap<String, Vec<CompletionItem>>,
+    dialect_completions: HashMap<UnitDialect, DialectCompletions>,
+    directive_versions: HashMap<UnitDialect, HashMap<(&'static str, &'static str), u32>>,
+    target_version: RwLock<Option<u32>>,
+    templates: SystemdTemplates,
+    /// Names of the unit files found in the workspace folders
+    workspace_units: RwLock<Vec<String>>,
+}
+
+/// Section and directive completions for the file types other than units
+#[derive(Debug)]
+struct DialectCompletions {
+    section_completions: Vec<CompletionItem>,
+    directive_completions: HashMap<String, Vec<CompletionItem>>,
+}
+
+/// Stored in an item's `data` so `completionItem/resolve` can add the documentation
+/// the completion list leaves out
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+enum CompletionData {
+    Section {
+        dialect: UnitDialect,
+        section: String,
+    },
+    Directive {
+        dialect: UnitDialect,
+        section: String,
+        directive: String,
+    },
+    Value {
+        dialect: UnitDialect,
+        section: String,
+        directive: String,
+        value: String,
+    },
+    Template {
+        unit_type: String,
+        name: String,
+    },
 }
 
 #[derive(Debug, Clone)]
@@ -21,47 +68,119 @@ enum CompletionContext {
     Global,
 }
 
+impl Default for SystemdCompletion {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl SystemdCompletion {
     pub fn new() -> Self {
+        let dialect_completions = UnitDialect::ALL
+            .into_iter()
+            .filter(|dialect| !dialect.is_unit())
+            .map(|dialect| {
+                (
+                    dialect,
+                    DialectCompletions {
+                        section_completions: Self::build_section_completions(dialect),
+                        directive_completions: Self::build_directive_completions(dialect),
+                    },
+                )
+            })
+            .collect();
+
+        let directive_versions = UnitDialect::ALL
+            .into_iter()
+            .map(|dialect| (dialect, SystemdConstants::directive_versions(dialect)))
+            .collect();
+
+        Self {
+            section_completions: Self::build_section_completions(UnitDialect::Systemd),
+            directive_completions: Self::build_directive_completions(UnitDialect::Systemd),
+            dialect_completions,
+            directive_versions,
+            target_version: RwLock::new(None),
+            templates: SystemdTemplates::new(),
+            workspace_units: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// Set the oldest systemd version units have to support; newer items are marked deprecated
+    pub fn set_target_version(&self, version: Option<u32>) {
+        *self.target_version.write().unwrap() = version;
+    }
+
+    /// Unit names completed for dependency directives besides the standard units and open files
+    pub fn set_workspace_units(&self, names: Vec<String>) {
+        *self.workspace_units.write().unwrap() = names;
+    }
+
+    /// The built-in and user templates
+    pub fn templates(&self) -> &SystemdTemplates {
+        &self.templates
+    }
+
+    /// Load user templates from `directory`, or from [`SystemdTemplates::default_directory`]
+    pub fn set_templates_directory(&self, directory: Option<&Path>) {
+        let directory = directory
+            .map(Path::to_path_buf)
+            .or_else(SystemdTemplates::default_directory);
+        let count = self.templates.load_directory(directory.as_deref());
+        info!("Loaded {} user template(s) from {:?}", count, directory);
+    }
+
+    fn build_section_completions(dialect: UnitDialect) -> Vec<CompletionItem> {
         let mut section_completions = Vec::new();
-        for (name, description) in SystemdConstants::section_documentation() {
-            let documentation = Self::create_documentation(
-                &format!("[{}] Section", name),
-                description,
-                &format!("systemd.{}.5", name.to_lowercase()),
-            );
+        for name in SystemdConstants::dialect_section_documentation(dialect).keys() {
+            let detail = if !dialect.is_unit() {
+                format!("{} [{}] section", dialect.description(), name)
+            } else {
+                format!("systemd {} section", name.to_lowercase())
+            };
 
-            section_completions.push(Self::create_completion_item(
+            let mut item = Self::create_completion_item(
                 format!("[{}]", name),
                 CompletionItemKind::MODULE,
-                format!("systemd {} section", name.to_lowercase()),
-                documentation,
+                detail,
                 Some(format!("[{}]", name)),
-            ));
+            );
+            item.data = Self::completion_data(CompletionData::Section {
+                dialect,
+                section: name.to_string(),
+            });
+            section_completions.push(item);
         }
+        section_completions
+    }
 
+    fn build_directive_completions(dialect: UnitDialect) -> HashMap<String, Vec<CompletionItem>> {
         let mut directive_completions = HashMap::new();
-        let directive_descriptions = SystemdConstants::directive_descriptions();
+        let versions = SystemdConstants::directive_versions(dialect);
 
-        for (section, directives) in SystemdConstants::section_directives() {
+        for (section, directives) in SystemdConstants::dialect_section_directives(dialect) {
             let mut completion_items = Vec::new();
             for directive in directives {
-                let description = directive_descriptions
-                    .get(&(section, directive))
-                    .unwrap_or(&"systemd directive")
-                    .to_string();
-                completion_items.push(Self::create_directive_completion(
-                    section,
-                    directive,
-                    &description,
-                ));
+                let mut item = Self::create_directive_completion(dialect, section, directive);
+                item.label_details =
+                    Self::since_label(versions.get(&(section, directive)).copied());
+                completion_items.push(item);
             }
             directive_completions.insert(section.to_string(), completion_items);
         }
+        directive_completions
+    }
 
-        Self {
-            section_completions,
-            directive_completions,
+    fn completions_for(
+        &self,
+        dialect: UnitDialect,
+    ) -> (&Vec<CompletionItem>, &HashMap<String, Vec<CompletionItem>>) {
+        match self.dialect_completions.get(&dialect) {
+            Some(completions) => (
+                &completions.section_completions,
+                &completions.directive_completions,
+            ),
+            None => (&self.section_completions, &self.directive_completions),
         }
     }
 
@@ -87,11 +206,13 @@ impl SystemdCompletion {
 
         debug!("Completion context: {:?}", completion_context);
 
+        let (section_completions, directive_completions) = self.completions_for(unit.dialect);
+
         match completion_context {
             CompletionContext::SectionHeader => {
                 // Only show section completions
                 debug!("Providing section completions");
-                Some(CompletionResponse::Array(self.section_completions.clone()))
+                Some(CompletionResponse::Array(section_completions.clone()))
             }
             CompletionContext::Directive(section_name) => {
                 // Only show directives for the current section
@@ -99,8 +220,13 @@ impl SystemdCompletion {
                     "Providing directive completions for section: {}",
                     section_name
                 );
-                if let Some(directives) = self.directive_completions.get(&section_name) {
-                    Some(CompletionResponse::Array(directives.clone()))
+                if let Some(directives) = directive_completions.get(&section_name) {
+                    let mut items = directives.clone();
+                    let versions = self.directive_versions.get(&unit.dialect);
+                    self.mark_unavailable(&mut items, |label| {
+                        versions?.get(&(section_name.as_str(), label)).copied()
+                    });
+                    Some(CompletionResponse::Array(items))
                 } else {
                     debug!("No directives found for section: {}", section_name);
                     Some(CompletionResponse::Array(Vec::new()))
@@ -114,25 +240,188 @@ impl SystemdCompletion {
                     "Providing value completions for {}.{}",
                     section_name, directive
                 );
-                match self.get_value_completions(section_name.as_str(), directive.as_str()) {
-                    Some(items) if !items.is_empty() => Some(CompletionResponse::Array(items)),
-                    _ => {
-                        debug!(
-                            "No value completions available for {}.{}",
-                            section_name, directive
-                        );
-                        None
-                    }
+                let mut items = self
+                    .get_value_completions(unit.dialect, section_name.as_str(), directive.as_str())
+                    .unwrap_or_default();
+                items.extend(self.provided_value_completions(
+                    parser,
+                    uri,
+                    unit.dialect,
+                    (&section_name, &directive),
+                    &document_text,
+                    position,
+                ));
+
+                if items.is_empty() {
+                    debug!(
+                        "No value completions available for {}.{}",
+                        section_name, directive
+                    );
+                    return None;
                 }
+                self.mark_unavailable(&mut items, |label| {
+                    SystemdConstants::value_version(&section_name, &directive, label)
+                });
+                Some(CompletionResponse::Array(items))
             }
             CompletionContext::Global => {
-                // Show section completions if we're not inside any section
+                // Show section completions if we're not inside any section, and
+                // whole-file templates while the file is still empty
                 debug!("Providing global completions (sections)");
-                Some(CompletionResponse::Array(self.section_completions.clone()))
+                let mut items = Vec::new();
+                if document_text.trim().is_empty() {
+                    items.extend(self.template_completions(uri));
+                }
+                items.extend(section_completions.iter().cloned());
+                Some(CompletionResponse::Array(items))
             }
         }
     }
 
+    /// Values of directives naming units, users, groups, paths, signals, system calls,
+    /// address families or capabilities. They replace the word before the cursor, as
+    /// paths and unit names contain `/`, `-` and `.`, and system calls `@`, `~` and `:`.
+    fn provided_value_completions(
+        &self,
+        parser: &SystemdParser,
+        uri: &Uri,
+        dialect: UnitDialect,
+        (section, directive): (&str, &str),
+        document_text: &str,
+        position: &Position,
+    ) -> Vec<CompletionItem> {
+        let Some(provider) = ValueProvider::for_directive(dialect, section, directive) else {
+            return Vec::new();
+        };
+        let line = document_text
+            .lines()
+            .nth(position.line as usize)
+            .unwrap_or("");
+        let (start, typed) = Self::value_word_before(line, position.character);
+
+        let values = match provider {
+            ValueProvider::Units => self.unit_names(parser, uri),
+            ValueProvider::Users => value_providers::system_users(),
+            ValueProvider::Groups => value_providers::system_groups(),
+            ValueProvider::Paths => value_providers::path_completions(&typed),
+            ValueProvider::Signals => value_providers::signal_names(),
+            ValueProvider::Syscalls => value_providers::syscall_completions(&typed),
+            ValueProvider::AddressFamilies => value_providers::address_family_completions(&typed),
+            ValueProvider::Capabilities => value_providers::capability_completions(&typed),
+        };
+
+        let range = Range::new(Position::new(position.line, start), *position);
+        values
+            .into_iter()
+            .map(|value| {
+                let mut item = Self::create_completion_item(
+                    value.clone(),
+                    CompletionItemKind::VALUE,
+                    provider.detail().to_string(),
+                    None,
+                );
+                item.filter_text = Some(value.clone());
+                item.text_edit = Some(CompletionTextEdit::Edit(TextEdit::new(range, value)));
+                item
+            })
+            .collect()
+    }
+
+    /// Start column and text of the value word the cursor is at, e.g. `network-on` in
+    /// `After=basic.target network-on`
+    fn value_word_before(line: &str, character: u32) -> (u32, String) {
+        let before: Vec<char> = line.chars().take(character as usize).collect();
+        let value_start = before
+            .iter()
+            .position(|c| *c == '=')
+            .map_or(0, |index| index + 1);
+        let start = before[value_start..]
+            .iter()
+            .rposition(|c| c.is_whitespace())
+            .map_or(value_start, |index| value_start + index + 1);
+        (start as u32, before[start..].iter().collect())
+    }
+
+    /// Standard units, unit files in the workspace and open unit files other than `uri`
+    fn unit_names(&self, parser: &SystemdParser, uri: &Uri) -> Vec<String> {
+        let own_name = Self::file_name(uri);
+        let mut names: Vec<String> = value_providers::STANDARD_UNITS
+            .iter()
+            .map(|name| name.to_string())
+            .chain(self.workspace_units.read().unwrap().iter().cloned())
+            .chain(parser.document_uris().iter().filter_map(Self::file_name))
+            .filter(|name| value_providers::is_unit_name(name) && Some(name) != own_name.as_ref())
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    fn file_name(uri: &Uri) -> Option<String> {
+        let path = uri.path();
+        Path::new(path.as_str())
+            .file_name()
+            .and_then(|name| name.to_str())
+            .map(str::to_string)
+    }
+
+    /// Snippets for the templates registered for the file's extension
+    fn template_completions(&self, uri: &Uri) -> Vec<CompletionItem> {
+        let path = uri.path();
+        let Some(unit_type) = Path::new(path.as_str())
+            .extension()
+            .and_then(|extension| extension.to_str())
+        else {
+            return Vec::new();
+        };
+
+        self.templates
+            .for_unit_type(unit_type)
+            .into_iter()
+            .map(|template| {
+                let mut item = Self::create_completion_item(
+                    template.name.clone(),
+                    CompletionItemKind::SNIPPET,
+                    template.description,
+                    Some(template.snippet),
+                );
+                item.insert_text_format = Some(InsertTextFormat::SNIPPET);
+                item.data = Self::completion_data(CompletionData::Template {
+                    unit_type: template.unit_type,
+                    name: template.name,
+                });
+                item
+            })
+            .collect()
+    }
+
+    /// Tag items that need a newer systemd than the target version as deprecated and
+    /// sort them after the available ones
+    fn mark_unavailable(
+        &self,
+        items: &mut [CompletionItem],
+        version_of: impl Fn(&str) -> Option<u32>,
+    ) {
+        let Some(target) = *self.target_version.read().unwrap() else {
+            return;
+        };
+
+        for item in items.iter_mut() {
+            if version_of(&item.label).is_some_and(|version| version > target) {
+                item.tags = Some(vec![CompletionItemTag::DEPRECATED]);
+                item.sort_text = Some(format!("~{}", item.label));
+            }
+        }
+    }
+
+    /// "since vNNN" shown next to the label of items added in a known systemd version
+    fn since_label(version: Option<u32>) -> Option<CompletionItemLabelDetails> {
+        version.map(|version| CompletionItemLabelDetails {
+            detail: None,
+            description: Some(format!("since v{}", version)),
+        })
+    }
+
     fn determine_context(
         &self,
         parser: &SystemdParser,
@@ -210,10 +499,11 @@ impl SystemdCompletion {
 
     fn get_value_completions(
         &self,
+        dialect: UnitDialect,
         section_name: &str,
         directive_name: &str,
     ) -> Option<Vec<CompletionItem>> {
-        let section_map = SystemdConstants::section_directives();
+        let section_map = SystemdConstants::dialect_section_directives(dialect);
         let canonical_section = section_map
             .keys()
             .find(|name| name.eq_ignore_ascii_case(section_name))
@@ -229,6 +519,9 @@ impl SystemdCompletion {
                     .copied()
             })
             .or_else(|| {
+                if !dialect.is_unit() {
+                    return None;
+                }
                 let global_values = SystemdConstants::valid_values();
                 global_values
                     .keys()
@@ -237,8 +530,11 @@ impl SystemdCompletion {
             })
             .unwrap_or(directive_name);
 
-        let values =
-            SystemdConstants::valid_values_for_section(canonical_section, canonical_directive)?;
+        let values = SystemdConstants::valid_values_for_dialect(
+            dialect,
+            canonical_section,
+            canonical_directive,
+        )?;
 
         if values.is_empty() {
             return None;
@@ -247,7 +543,12 @@ impl SystemdCompletion {
         let items = values
             .iter()
             .map(|value| {
-                Self::create_value_completion(canonical_section, canonical_directive, value)
+                Self::create_value_completion(
+                    dialect,
+                    canonical_section,
+                    canonical_directive,
+                    value,
+                )
             })
             .collect::<Vec<_>>();
 
@@ -264,11 +565,11 @@ impl SystemdCompletion {
         })
     }
 
+    /// Items leave out their documentation, [`Self::resolve`] adds it on request
     fn create_completion_item(
         label: String,
         kind: CompletionItemKind,
         detail: String,
-        documentation: Documentation,
         insert_text: Option<String>,
     ) -> CompletionItem {
         CompletionItem {
@@ -276,7 +577,7 @@ impl SystemdCompletion {
             label_details: None,
             kind: Some(kind),
             detail: Some(detail),
-            documentation: Some(documentation),
+            documentation: None,
             deprecated: None,
             preselect: None,
             sort_text: None,
@@ -294,105 +595,148 @@ impl SystemdCompletion {
     }
 
     /*
-     * We Want to extract directive documentation from markdown files. To allow for
-     * deduplication and maintainability, each section has a comprehensive markdown file
-     * we require a strict format to adhere to discoverability of the documenation.
-     * Most notably, directive headers must be prefixed with "### " and suffixed with "=".
+     * Directive documentation comes from the schema build.rs compiles out of the markdown
+     * files. Each section has a comprehensive markdown file and directive headers must be
+     * prefixed with "### " and suffixed with "=" to be picked up. The schema adds a summary
+     * line with the value type, default and manual page ahead of the text.
      */
-    fn extract_directive_from_markdown(section_name: &str, directive_name: &str) -> Option<String> {
-        // Helper function to search for a directive in a markdown string
-        fn search_in_markdown(markdown_content: &str, directive_name: &str) -> Option<String> {
-            let directive_header = format!("### {}=", directive_name);
-            let directive_header_lower = directive_header.to_lowercase();
-
-            let lines = markdown_content.lines();
-            let mut found_header = false;
-            let mut doc_lines = Vec::new();
-
-            for line in lines {
-                if line.to_lowercase() == directive_header_lower {
-                    found_header = true;
-                    continue;
-                }
-
-                if found_header {
-                    // Stop at the next directive header (###) or section header (##)
-                    if line.starts_with("### ") || line.starts_with("## ") {
-                        break;
-                    }
-                    doc_lines.push(line);
-                }
-            }
-
-            if doc_lines.is_empty() {
-                return None;
-            }
+    fn directive_documentation(
+        dialect: UnitDialect,
+        section_name: &str,
+        directive_name: &str,
+    ) -> Option<String> {
+        let entry = schema::find(dialect, section_name, directive_name)?;
+        let Some(documentation) = entry.documentation else {
+            return entry.deprecation_message();
+        };
+        Some(format!(
+            "{}\n\n{}",
+            Self::schema_summary(entry),
+            documentation
+        ))
+    }
 
-            // Join the lines and trim
-            let documentation = doc_lines.join("\n").trim().to_string();
+    /// e.g. "*time span* · default `90s` · [systemd.service(5)](https://…#TimeoutSec=)"
+    fn schema_summary(entry: &DirectiveSchema) -> String {
+        let mut parts = Vec::new();
+        if entry.value_type != ValueType::String {
+            parts.push(format!("*{}*", entry.value_type.description()));
+        }
+        if entry.list {
+            parts.push("*list*".to_string());
+        }
+        if let Some(default) = entry.default {
+            parts.push(format!("default `{}`", default));
+        }
+        if let (Some((page, section)), Some(url)) =
+            (entry.man_page.rsplit_once('.'), entry.man_page_url())
+        {
+            parts.push(format!("[{}({})]({})", page, section, url));
+        }
+        parts.join(" · ")
+    }
 
-            // Remove the trailing "**Reference:**" line if present
-            let documentation = if let Some(last_ref_pos) = documentation.rfind("**Reference:**") {
-                documentation[..last_ref_pos].trim().to_string()
-            } else {
-                documentation
-            };
+    fn completion_data(data: CompletionData) -> Option<serde_json::Value> {
+        serde_json::to_value(data).ok()
+    }
 
-            Some(documentation)
+    /// Fill in the documentation of an item returned by [`Self::get_completions`]
+    pub fn resolve(&self, mut item: CompletionItem) -> CompletionItem {
+        if item.documentation.is_some() {
+            return item;
         }
-
-        // First, try to find the directive in the section's own markdown file
-        let section_docs = SystemdConstants::section_documentation();
-        if let Some(section_key) = section_docs.keys().find(|k| k.eq_ignore_ascii_case(section_name)) {
-            if let Some(markdown_content) = section_docs.get(section_key) {
-                if let Some(result) = search_in_markdown(markdown_content, directive_name) {
-                    return Some(result);
-                }
+        let Some(data) = item
+            .data
+            .clone()
+            .and_then(|data| serde_json::from_value::<CompletionData>(data).ok())
+        else {
+            return item;
+        };
+
+        item.documentation = match data {
+            CompletionData::Section { dialect, section } => {
+                Self::section_item_documentation(dialect, &section)
             }
-        }
-
-        // If not found in the section's own docs, search in shared documentation
-        // (exec, kill, resource-control) based on which section we're in
-        let shared_docs_keys = SystemdConstants::section_shared_docs(section_name);
-        if !shared_docs_keys.is_empty() {
-            let shared_docs = SystemdConstants::shared_documentation();
-            for shared_key in shared_docs_keys {
-                if let Some(shared_content) = shared_docs.get(shared_key) {
-                    if let Some(result) = search_in_markdown(shared_content, directive_name) {
-                        return Some(result);
-                    }
-                }
+            CompletionData::Directive {
+                dialect,
+                section,
+                directive,
+            } => Some(Self::directive_item_documentation(
+                dialect, &section, &directive,
+            )),
+            CompletionData::Value {
+                dialect,
+                section,
+                directive,
+                value,
+            } => Some(Self::value_item_documentation(
+                dialect, &section, &directive, &value,
+            )),
+            CompletionData::Template { unit_type, name } => {
+                self.templates.get(&unit_type, &name).map(|template| {
+                    Documentation::MarkupContent(MarkupContent {
+                        kind: MarkupKind::Markdown,
+                        value: format!("```ini\n{}\n```", template.preview()),
+                    })
+                })
             }
-        }
+        };
+        item
+    }
 
-        None
+    fn section_item_documentation(dialect: UnitDialect, section: &str) -> Option<Documentation> {
+        let description = *SystemdConstants::dialect_section_documentation(dialect).get(section)?;
+        let reference = dialect
+            .man_page()
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("systemd.{}.5", section.to_lowercase()));
+        Some(Self::create_documentation(
+            &format!("[{}] Section", section),
+            description,
+            &reference,
+        ))
     }
 
     // We also leverage the directive completion for auto complete and hover.
     fn create_directive_completion(
+        dialect: UnitDialect,
         section: &str,
         key: &str,
-        short_description: &str,
     ) -> CompletionItem {
-        // Try to get comprehensive markdown documentation
-        let documentation =
-            if let Some(markdown_doc) = Self::extract_directive_from_markdown(section, key) {
-                Documentation::MarkupContent(MarkupContent {
-                    kind: MarkupKind::Markdown,
-                    value: format!("**{}**\n\n{}", key, markdown_doc),
-                })
-            } else {
-                // Fall back to short description
-                Self::create_documentation(key, short_description, "systemd documentation")
-            };
-
-        Self::create_completion_item(
+        let mut item = Self::create_completion_item(
             key.to_string(),
             CompletionItemKind::PROPERTY,
             "systemd directive".to_string(),
-            documentation,
             Some(format!("{}=", key)),
-        )
+        );
+        item.data = Self::completion_data(CompletionData::Directive {
+            dialect,
+            section: section.to_string(),
+            directive: key.to_string(),
+        });
+        item
+    }
+
+    fn directive_item_documentation(
+        dialect: UnitDialect,
+        section: &str,
+        key: &str,
+    ) -> Documentation {
+        // Try to get comprehensive markdown documentation
+        if let Some(markdown_doc) = Self::directive_documentation(dialect, section, key) {
+            return Documentation::MarkupContent(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: format!("**{}**\n\n{}", key, markdown_doc),
+            });
+        }
+
+        // Fall back to short description
+        let short_description = SystemdConstants::directive_descriptions()
+            .get(&(section, key))
+            .filter(|_| dialect == UnitDialect::Systemd)
+            .copied()
+            .unwrap_or("systemd directive");
+        Self::create_documentation(key, short_description, "systemd documentation")
     }
 
     /* Extract value-specific documentation from the directive markdown. Similarly to directive
@@ -401,12 +745,13 @@ impl SystemdCompletion {
      * description".
      */
     fn extract_value_documentation(
+        dialect: UnitDialect,
         section_name: &str,
         directive_name: &str,
         value: &str,
     ) -> Option<String> {
         // Get the full directive documentation
-        let directive_doc = Self::extract_directive_from_markdown(section_name, directive_name)?;
+        let directive_doc = Self::directive_documentation(dialect, section_name, directive_name)?;
 
         // Look for the value in bullet list format: - **value**: description
         // or: - **value** (default): description
@@ -444,32 +789,80 @@ impl SystemdCompletion {
             }
         }
 
-        None
+        Self::value_sentence(&directive_doc, value)
     }
 
-    // we want this for hover and autocomplete of values
-    fn create_value_completion(section: &str, directive: &str, value: &str) -> CompletionItem {
-        // Try to get value-specific documentation from markdown
-        let documentation =
-            if let Some(value_doc) = Self::extract_value_documentation(section, directive, value) {
-                Documentation::MarkupContent(MarkupContent {
-                    kind: MarkupKind::Markdown,
-                    value: format!("**{}**\n\n{}", value, value_doc),
-                })
-            } else {
-                Documentation::MarkupContent(MarkupContent {
-                    kind: MarkupKind::Markdown,
-                    value: format!("Valid `{}` option for `{}`", value, directive),
+    /// The sentence of the directive prose introducing a value, such as
+    /// "If set to `on-failure`, the service will be restarted when …"
+    fn value_sentence(directive_doc: &str, value: &str) -> Option<String> {
+        let text = directive_doc
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        let marker = format!("`{}`", value);
+
+        text.match_indices(&marker).find_map(|(index, _)| {
+            let before = &text[..index];
+            if !["set to ", "If ", "When ", "with "]
+                .iter()
+                .any(|lead| before.ends_with(lead))
+            {
+                return None;
+            }
+
+            let start = before.rfind(". ").map_or(0, |position| position + 2);
+            let after = index + marker.len();
+            let end = text[after..]
+                .match_indices(". ")
+                .find(|(position, _)| {
+                    text[after + position + 2..].starts_with(|c: char| c.is_uppercase())
                 })
-            };
+                .map_or(text.len(), |(position, _)| after + position + 1);
+            Some(text[start..end].to_string())
+        })
+    }
 
-        Self::create_completion_item(
+    // we want this for hover and autocomplete of values
+    fn create_value_completion(
+        dialect: UnitDialect,
+        section: &str,
+        directive: &str,
+        value: &str,
+    ) -> CompletionItem {
+        let mut item = Self::create_completion_item(
             value.to_string(),
             CompletionItemKind::VALUE,
             format!("{} value", directive),
-            documentation,
             Some(value.to_string()),
-        )
+        );
+        item.data = Self::completion_data(CompletionData::Value {
+            dialect,
+            section: section.to_string(),
+            directive: directive.to_string(),
+            value: value.to_string(),
+        });
+        if dialect.is_unit() {
+            item.label_details =
+                Self::since_label(SystemdConstants::value_version(section, directive, value));
+        }
+        item
+    }
+
+    fn value_item_documentation(
+        dialect: UnitDialect,
+        section: &str,
+        directive: &str,
+        value: &str,
+    ) -> Documentation {
+        // Try to get value-specific documentation from markdown
+        let value = match Self::extract_value_documentation(dialect, section, directive, value) {
+            Some(value_doc) => format!("**{}**\n\n{}", value, value_doc),
+            None => format!("Valid `{}` option for `{}`", value, directive),
+        };
+        Documentation::MarkupContent(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value,
+        })
     }
 
     pub fn get_section_documentation(&self, section_name: &str) -> Option<String> {
@@ -490,7 +883,49 @@ impl SystemdCompletion {
         directive_name: &str,
         section_name: &str,
     ) -> Option<String> {
-        Self::extract_directive_from_markdown(section_name, directive_name)
+        Self::directive_documentation(UnitDialect::Systemd, section_name, directive_name)
+    }
+
+    /// Documentation of one value of a directive, e.g. `on-failure` of `Restart=`
+    pub fn get_value_documentation(
+        &self,
+        dialect: UnitDialect,
+        section_name: &str,
+        directive_name: &str,
+        value: &str,
+    ) -> Option<String> {
+        Self::extract_value_documentation(dialect, section_name, directive_name, value)
+    }
+
+    pub fn get_dialect_section_documentation(
+        &self,
+        dialect: UnitDialect,
+        section_name: &str,
+    ) -> Option<String> {
+        let Some(man_page) = dialect.man_page() else {
+            return self.get_section_documentation(section_name);
+        };
+
+        SystemdConstants::dialect_section_documentation(dialect)
+            .get(section_name)
+            .map(|description| {
+                format!(
+                    "**[{}] Section**\n\n{}\n\n**Reference:** {}",
+                    section_name, description, man_page
+                )
+            })
+    }
+
+    pub fn get_dialect_directive_documentation(
+        &self,
+        dialect: UnitDialect,
+        directive_name: &str,
+        section_name: &str,
+    ) -> Option<String> {
+        match dialect {
+            UnitDialect::Systemd => self.get_directive_documentation(directive_name, section_name),
+            _ => Self::directive_documentation(dialect, section_name, directive_name),
+        }
     }
 }
 
@@ -553,7 +988,11 @@ mod tests {
             let section_item = items.iter().find(|item| item.label == "[Unit]").unwrap();
             assert_eq!(section_item.kind, Some(CompletionItemKind::MODULE));
             assert!(section_item.detail.is_some());
-            assert!(section_item.documentation.is_some());
+            assert!(section_item.documentation.is_none());
+            assert!(completion
+                .resolve(section_item.clone())
+                .documentation
+                .is_some());
         }
     }
 
@@ -578,13 +1017,180 @@ mod tests {
     #[test]
     fn test_create_directive_completion() {
         let completion =
-            SystemdCompletion::create_directive_completion("Service", "Type", "Test description");
+            SystemdCompletion::create_directive_completion(UnitDialect::Systemd, "Service", "Type");
 
         assert_eq!(completion.label, "Type");
         assert_eq!(completion.kind, Some(CompletionItemKind::PROPERTY));
         assert_eq!(completion.detail, Some("systemd directive".to_string()));
         assert_eq!(completion.insert_text, Some("Type=".to_string()));
-        assert!(completion.documentation.is_some());
+        assert!(completion.documentation.is_none());
+        assert!(completion.data.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_provided_value_completions() {
+        let completion = SystemdCompletion::new();
+        let parser = SystemdParser::new();
+        completion.set_workspace_units(vec!["backend.service".to_string()]);
+        let uri = "file:///srv/units/web.service".parse::<Uri>().unwrap();
+        let other = "file:///srv/units/web.socket".parse::<Uri>().unwrap();
+        parser.update_document(&other, "[Socket]\nListenStream=80\n");
+        parser.update_document(
+            &uri,
+            "[Unit]\nAfter=basic.target network-on\n\n[Service]\nKillSignal=\nEnvironmentFile=-/\n",
+        );
+
+        let Some(CompletionResponse::Array(units)) = completion
+            .get_completions(&parser, &uri, &Position::new(1, 30))
+            .await
+        else {
+            panic!("Expected unit name completions");
+        };
+        let labels: Vec<&str> = units.iter().map(|item| item.label.as_str()).collect();
+        for name in ["network-online.target", "backend.service", "web.socket"] {
+            assert!(labels.contains(&name), "{}", name);
+        }
+        assert!(!labels.contains(&"web.service"));
+        let Some(CompletionTextEdit::Edit(edit)) = &units[0].text_edit else {
+            panic!("Expected a text edit");
+        };
+        assert_eq!(
+            edit.range,
+            Range::new(Position::new(1, 19), Position::new(1, 30))
+        );
+
+        let Some(CompletionResponse::Array(signals)) = completion
+            .get_completions(&parser, &uri, &Position::new(4, 11))
+            .await
+        else {
+            panic!("Expected signal completions");
+        };
+        assert!(signals.iter().any(|item| item.label == "SIGTERM"));
+
+        let Some(CompletionResponse::Array(paths)) = completion
+            .get_completions(&parser, &uri, &Position::new(5, 18))
+            .await
+        else {
+            panic!("Expected path completions");
+        };
+        assert!(paths.iter().all(|item| item.label.starts_with("-/")));
+    }
+
+    #[tokio::test]
+    async fn test_template_completions_in_empty_files() {
+        let completion = SystemdCompletion::new();
+        let parser = SystemdParser::new();
+        let uri = "file:///backup.timer".parse::<Uri>().unwrap();
+        parser.update_document(&uri, "");
+
+        let Some(CompletionResponse::Array(items)) = completion
+            .get_completions(&parser, &uri, &Position::new(0, 0))
+            .await
+        else {
+            panic!("Expected global completions");
+        };
+        let timer = items.iter().find(|item| item.label == "timer").unwrap();
+        assert_eq!(timer.kind, Some(CompletionItemKind::SNIPPET));
+        assert_eq!(timer.insert_text_format, Some(InsertTextFormat::SNIPPET));
+        assert!(timer
+            .insert_text
+            .as_ref()
+            .unwrap()
+            .contains("OnCalendar=${2:daily}"));
+        assert!(!items.iter().any(|item| item.label == "hardened-service"));
+        assert!(items.iter().any(|item| item.label == "[Timer]"));
+
+        let Some(Documentation::MarkupContent(content)) =
+            completion.resolve(timer.clone()).documentation
+        else {
+            panic!("Expected a template preview");
+        };
+        assert!(content.value.contains("OnCalendar=daily"));
+
+        // Once the file has content only sections are offered
+        parser.update_document(&uri, "# backup\n");
+        let Some(CompletionResponse::Array(items)) = completion
+            .get_completions(&parser, &uri, &Position::new(1, 0))
+            .await
+        else {
+            panic!("Expected global completions");
+        };
+        assert!(!items
+            .iter()
+            .any(|item| item.kind == Some(CompletionItemKind::SNIPPET)));
+    }
+
+    #[tokio::test]
+    async fn test_shared_directive_completions() {
+        let completion = SystemdCompletion::new();
+        let parser = SystemdParser::new();
+        let uri = "file:///app.slice".parse::<Uri>().unwrap();
+        parser.update_document(&uri, "[Slice]\n\n");
+
+        let Some(CompletionResponse::Array(items)) = completion
+            .get_completions(&parser, &uri, &Position::new(1, 0))
+            .await
+        else {
+            panic!("Expected Slice directive completions");
+        };
+        assert!(items.iter().any(|item| item.label == "MemoryMax"));
+        assert!(items.iter().any(|item| item.label == "ConcurrencyHardMax"));
+        assert!(!items.iter().any(|item| item.label == "User"));
+        assert!(!items.iter().any(|item| item.label == "KillMode"));
+
+        let socket = &completion.directive_completions["Socket"];
+        for directive in ["ListenStream", "User", "KillSignal", "CPUWeight"] {
+            assert!(
+                socket.iter().any(|item| item.label == directive),
+                "{}",
+                directive
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resolve_adds_documentation() {
+        let completion = SystemdCompletion::new();
+        let parser = SystemdParser::new();
+        let uri = "file:///app.service".parse::<Uri>().unwrap();
+        parser.update_document(&uri, "[Service]\nRestart=\n\n");
+
+        let Some(CompletionResponse::Array(directives)) = completion
+            .get_completions(&parser, &uri, &Position::new(2, 0))
+            .await
+        else {
+            panic!("Expected Service directive completions");
+        };
+        assert!(directives.iter().all(|item| item.documentation.is_none()));
+        let restart_sec = directives
+            .iter()
+            .find(|item| item.label == "RestartSec")
+            .unwrap();
+        let Some(Documentation::MarkupContent(content)) =
+            completion.resolve(restart_sec.clone()).documentation
+        else {
+            panic!("Expected RestartSec documentation");
+        };
+        assert!(content.value.starts_with("**RestartSec**"));
+        assert!(content.value.contains("*time span*"));
+
+        let Some(CompletionResponse::Array(values)) = completion
+            .get_completions(&parser, &uri, &Position::new(1, 8))
+            .await
+        else {
+            panic!("Expected Restart value completions");
+        };
+        let on_failure = values
+            .iter()
+            .find(|item| item.label == "on-failure")
+            .unwrap();
+        let resolved = completion.resolve(on_failure.clone());
+        assert_eq!(resolved.label, "on-failure");
+        assert!(resolved.documentation.is_some());
+
+        // Items from other sources pass through untouched
+        let foreign = CompletionItem::new_simple("x".to_string(), "y".to_string());
+        assert!(completion.resolve(foreign).documentation.is_none());
     }
 
     #[test]
@@ -772,4 +1378,133 @@ mod tests {
             "Expected no completions for freeform directive value"
         );
     }
+
+    #[tokio::test]
+    async fn test_networkd_completions_follow_dialect() {
+        let completion = SystemdCompletion::new();
+        let parser = SystemdParser::new();
+        let uri = "file:///etc/systemd/network/lan.network"
+            .parse::<Uri>()
+            .unwrap();
+
+        let document_text = "[Match]\nName=eth0\n\n[Network]\nDHCP=\n";
+        parser.update_document(&uri, document_text);
+
+        let sections = completion
+            .get_completions(&parser, &uri, &Position::new(0, 0))
+            .await;
+        if let Some(CompletionResponse::Array(items)) = sections {
+            assert!(items.iter().any(|item| item.label == "[DHCPv4]"));
+            assert!(!items.iter().any(|item| item.label == "[Service]"));
+        } else {
+            panic!("Expected networkd section completions");
+        }
+
+        let directives = completion
+            .get_completions(&parser, &uri, &Position::new(4, 0))
+            .await;
+        if let Some(CompletionResponse::Array(items)) = directives {
+            assert!(items.iter().any(|item| item.label == "Address"));
+            assert!(!items.iter().any(|item| item.label == "NetworkName"));
+        } else {
+            panic!("Expected networkd directive completions");
+        }
+
+        let values = completion
+            .get_completions(&parser, &uri, &Position::new(4, 5))
+            .await;
+        if let Some(CompletionResponse::Array(items)) = values {
+            assert!(items.iter().any(|item| item.label == "ipv4"));
+            let yes = items.iter().find(|item| item.label == "yes").unwrap();
+            let Some(Documentation::MarkupContent(content)) =
+                completion.resolve(yes.clone()).documentation
+            else {
+                panic!("Expected DHCP=yes documentation");
+            };
+            assert!(content.value.contains("DHCPv4 and DHCPv6"));
+        } else {
+            panic!("Expected DHCP value completions");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_completions_follow_target_version() {
+        let completion = SystemdCompletion::new();
+        let parser = SystemdParser::new();
+        let uri = "file:///app.service".parse::<Uri>().unwrap();
+        parser.update_document(&uri, "[Service]\nType=\n\n");
+        completion.set_target_version(Some(250));
+
+        let Some(CompletionResponse::Array(values)) = completion
+            .get_completions(&parser, &uri, &Position::new(1, 5))
+            .await
+        else {
+            panic!("Expected Type value completions");
+        };
+        let notify_reload = values
+            .iter()
+            .find(|item| item.label == "notify-reload")
+            .unwrap();
+        assert_eq!(
+            notify_reload.label_details.as_ref().unwrap().description,
+            Some("since v253".to_string())
+        );
+        assert_eq!(
+            notify_reload.tags,
+            Some(vec![CompletionItemTag::DEPRECATED])
+        );
+        let exec = values.iter().find(|item| item.label == "exec").unwrap();
+        assert_eq!(exec.tags, None);
+
+        let Some(CompletionResponse::Array(directives)) = completion
+            .get_completions(&parser, &uri, &Position::new(2, 0))
+            .await
+        else {
+            panic!("Expected Service directive completions");
+        };
+        let exec_condition = directives
+            .iter()
+            .find(|item| item.label == "ExecCondition")
+            .unwrap();
+        assert_eq!(exec_condition.tags, None);
+        let open_file = directives
+            .iter()
+            .find(|item| item.label == "OpenFile")
+            .unwrap();
+        assert_eq!(open_file.tags, Some(vec![CompletionItemTag::DEPRECATED]));
+    }
+
+    #[test]
+    fn test_value_documentation_from_prose() {
+        let completion = SystemdCompletion::new();
+
+        let on_failure = completion
+            .get_value_documentation(UnitDialect::Systemd, "Service", "Restart", "on-failure")
+            .unwrap();
+        assert!(on_failure.starts_with("If set to `on-failure`, the service will be restarted"));
+        assert!(on_failure.ends_with('.'));
+        assert!(!on_failure.contains("on-abnormal"));
+
+        assert!(completion
+            .get_value_documentation(UnitDialect::Systemd, "Service", "Restart", "sometimes")
+            .is_none());
+    }
+
+    #[test]
+    fn test_dialect_directive_documentation() {
+        let completion = SystemdCompletion::new();
+
+        let networkd = completion
+            .get_dialect_directive_documentation(UnitDialect::NetworkdNetwork, "DHCP", "Network")
+            .unwrap();
+        assert!(networkd.contains("DHCPv4"));
+
+        assert!(completion
+            .get_dialect_directive_documentation(UnitDialect::Systemd, "DHCP", "Network")
+            .is_none());
+        assert!(completion
+            .get_dialect_section_documentation(UnitDialect::NetworkdLink, "Link")
+            .unwrap()
+            .contains("systemd.link.5"));
+    }
 }
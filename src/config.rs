@@ -0,0 +1,144 @@
+use log::warn;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::path::PathBuf;
+
+/// Which dialect `.network` files are treated as.
+///
+/// Podman Quadlet and systemd-networkd both use the `.network` extension with a
+/// `[Network]` section, so the language server has to pick one of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NetworkDialect {
+    /// Decide from the file location first and the file content second
+    #[default]
+    Auto,
+    /// Always treat `.network` files as Quadlet network units
+    Quadlet,
+    /// Always treat `.network` files as systemd-networkd configuration
+    Networkd,
+}
+
+/// User configuration received through `initializationOptions` or
+/// `workspace/didChangeConfiguration`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SystemdLspConfig {
+    pub network_dialect: NetworkDialect,
+    /// Oldest systemd version the units have to run on, e.g. `239`
+    #[serde(alias = "target-version")]
+    pub target_version: Option<u32>,
+    /// Directory with user templates, by default `~/.config/systemd-lsp/templates`
+    #[serde(alias = "templates-directory")]
+    pub templates_directory: Option<PathBuf>,
+    /// Whether services get the sandboxing exposure summary, on unless set to `false`
+    #[serde(alias = "security-exposure")]
+    pub security_exposure: Option<bool>,
+}
+
+impl SystemdLspConfig {
+    /// Settings may be sent bare or nested under a `systemd-lsp` key, depending on the client.
+    /// An invalid setting is logged and left at its default instead of discarding the
+    /// others; `None` when the settings are not an object at all.
+    pub fn from_value(value: &Value) -> Option<Self> {
+        let settings = ["systemd-lsp", "systemdLsp", "systemd_lsp"]
+            .iter()
+            .find_map(|key| value.get(key))
+            .unwrap_or(value)
+            .as_object()?;
+
+        let valid: Map<String, Value> = settings
+            .iter()
+            .filter(|(key, value)| {
+                let single = Map::from_iter([((*key).clone(), (*value).clone())]);
+                match serde_json::from_value::<Self>(Value::Object(single)) {
+                    Ok(_) => true,
+                    Err(err) => {
+                        warn!("Ignoring invalid setting {}: {}", key, err);
+                        false
+                    }
+                }
+            })
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+
+        serde_json::from_value(Value::Object(valid)).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_default_config_is_auto() {
+        let config = SystemdLspConfig::from_value(&json!({})).unwrap();
+        assert_eq!(config.network_dialect, NetworkDialect::Auto);
+    }
+
+    #[test]
+    fn test_bare_and_nested_settings() {
+        let bare = SystemdLspConfig::from_value(&json!({ "networkDialect": "networkd" })).unwrap();
+        assert_eq!(bare.network_dialect, NetworkDialect::Networkd);
+
+        let nested = SystemdLspConfig::from_value(
+            &json!({ "systemd-lsp": { "networkDialect": "quadlet" } }),
+        )
+        .unwrap();
+        assert_eq!(nested.network_dialect, NetworkDialect::Quadlet);
+    }
+
+    #[test]
+    fn test_target_version() {
+        let config = SystemdLspConfig::from_value(&json!({ "targetVersion": 250 })).unwrap();
+        assert_eq!(config.target_version, Some(250));
+
+        let kebab = SystemdLspConfig::from_value(&json!({ "target-version": 239 })).unwrap();
+        assert_eq!(kebab.target_version, Some(239));
+        assert_eq!(SystemdLspConfig::default().target_version, None);
+    }
+
+    #[test]
+    fn test_templates_directory() {
+        let config =
+            SystemdLspConfig::from_value(&json!({ "templatesDirectory": "/srv/templates" }))
+                .unwrap();
+        assert_eq!(
+            config.templates_directory,
+            Some(PathBuf::from("/srv/templates"))
+        );
+        assert_eq!(SystemdLspConfig::default().templates_directory, None);
+    }
+
+    #[test]
+    fn test_security_exposure() {
+        let config = SystemdLspConfig::from_value(&json!({ "securityExposure": false })).unwrap();
+        assert_eq!(config.security_exposure, Some(false));
+
+        let kebab = SystemdLspConfig::from_value(&json!({ "security-exposure": true })).unwrap();
+        assert_eq!(kebab.security_exposure, Some(true));
+        assert_eq!(SystemdLspConfig::default().security_exposure, None);
+    }
+
+    #[test]
+    fn test_invalid_settings_fall_back_one_by_one() {
+        let config = SystemdLspConfig::from_value(&json!({
+            "networkDialect": "bogus",
+            "targetVersion": 250,
+            "templatesDirectory": "/srv/templates",
+            "securityExposure": "sometimes"
+        }))
+        .unwrap();
+        assert_eq!(config.network_dialect, NetworkDialect::Auto);
+        assert_eq!(config.target_version, Some(250));
+        assert_eq!(
+            config.templates_directory,
+            Some(PathBuf::from("/srv/templates"))
+        );
+        assert_eq!(config.security_exposure, None);
+
+        assert!(SystemdLspConfig::from_value(&json!(null)).is_none());
+        assert!(SystemdLspConfig::from_value(&json!({ "systemd-lsp": 42 })).is_none());
+    }
+}
@@ -1,3 +1,5 @@
+use crate::dialect::UnitDialect;
+use crate::schema::{self, DirectiveSchema};
 use std::collections::HashMap;
 
 pub struct SystemdConstants;
@@ -7,134 +9,16 @@ impl SystemdConstants {
         include_str!("../docs/sections.txt").lines().collect()
     }
 
-    /*
-    // Lists containing valid directives for each section
-    // this is stored in docs and _should_ be generated from
-    // from the parent documentation to keep it up to date
-    // and not prone to human error.
-    */
+    /// Directives per section, from the schema compiled out of `docs/directives/*.txt`
     pub fn section_directives() -> HashMap<&'static str, Vec<&'static str>> {
-        let mut map = HashMap::new();
-
-        map.insert(
-            "Unit",
-            include_str!("../docs/directives/unit.txt")
-                .lines()
-                .collect(),
-        );
-        map.insert(
-            "Service",
-            include_str!("../docs/directives/service.txt")
-                .lines()
-                .collect(),
-        );
-        map.insert(
-            "Install",
-            include_str!("../docs/directives/install.txt")
-                .lines()
-                .collect(),
-        );
-        map.insert(
-            "Timer",
-            include_str!("../docs/directives/timer.txt")
-                .lines()
-                .collect(),
-        );
-        map.insert(
-            "Socket",
-            include_str!("../docs/directives/socket.txt")
-                .lines()
-                .collect(),
-        );
-        map.insert(
-            "Mount",
-            include_str!("../docs/directives/mount.txt")
-                .lines()
-                .collect(),
-        );
-        map.insert(
-            "Path",
-            include_str!("../docs/directives/path.txt")
-                .lines()
-                .collect(),
-        );
-        map.insert(
-            "Swap",
-            include_str!("../docs/directives/swap.txt")
-                .lines()
-                .collect(),
-        );
-        map.insert(
-            "Automount",
-            include_str!("../docs/directives/automount.txt")
-                .lines()
-                .filter(|line| !line.starts_with('#') && !line.trim().is_empty())
-                .collect(),
-        );
-        map.insert(
-            "Slice",
-            include_str!("../docs/directives/slice.txt")
-                .lines()
-                .filter(|line| !line.starts_with('#') && !line.trim().is_empty())
-                .collect(),
-        );
-        map.insert(
-            "Scope",
-            include_str!("../docs/directives/scope.txt")
-                .lines()
-                .filter(|line| !line.starts_with('#') && !line.trim().is_empty())
-                .collect(),
-        );
-        map.insert(
-            "Container",
-            include_str!("../docs/directives/container.txt")
-                .lines()
-                .filter(|line| !line.starts_with('#') && !line.trim().is_empty())
-                .collect(),
-        );
-        map.insert(
-            "Pod",
-            include_str!("../docs/directives/pod.txt")
-                .lines()
-                .filter(|line| !line.starts_with('#') && !line.trim().is_empty())
-                .collect(),
-        );
-        map.insert(
-            "Volume",
-            include_str!("../docs/directives/volume.txt")
-                .lines()
-                .filter(|line| !line.starts_with('#') && !line.trim().is_empty())
-                .collect(),
-        );
-        map.insert(
-            "Network",
-            include_str!("../docs/directives/network.txt")
-                .lines()
-                .filter(|line| !line.starts_with('#') && !line.trim().is_empty())
-                .collect(),
-        );
-        map.insert(
-            "Kube",
-            include_str!("../docs/directives/kube.txt")
-                .lines()
-                .filter(|line| !line.starts_with('#') && !line.trim().is_empty())
-                .collect(),
-        );
-        map.insert(
-            "Build",
-            include_str!("../docs/directives/build.txt")
-                .lines()
-                .filter(|line| !line.starts_with('#') && !line.trim().is_empty())
-                .collect(),
-        );
-        map.insert(
-            "Image",
-            include_str!("../docs/directives/image.txt")
-                .lines()
-                .filter(|line| !line.starts_with('#') && !line.trim().is_empty())
-                .collect(),
-        );
+        Self::schema_section_directives(UnitDialect::Systemd)
+    }
 
+    fn schema_section_directives(dialect: UnitDialect) -> HashMap<&'static str, Vec<&'static str>> {
+        let mut map: HashMap<&'static str, Vec<&'static str>> = HashMap::new();
+        for entry in schema::directives(dialect).filter(|entry| entry.deprecation.is_none()) {
+            map.entry(entry.section).or_default().push(entry.name);
+        }
         map
     }
 
@@ -170,116 +54,15 @@ impl SystemdConstants {
         map
     }
 
+    /// Allowed values by directive name, regardless of the section
     pub fn valid_values() -> HashMap<&'static str, &'static [&'static str]> {
         let mut map = HashMap::new();
-
-        // Note: Type= is now handled context-sensitively in valid_values_for_section()
-        map.insert(
-            "Restart",
-            &[
-                "no",
-                "on-success",
-                "on-failure",
-                "on-abnormal",
-                "on-watchdog",
-                "on-abort",
-                "always",
-            ] as &[&str],
-        );
-        map.insert(
-            "ProtectSystem",
-            &["true", "false", "strict", "full", "yes", "no"] as &[&str],
-        );
-        map.insert(
-            "ProtectHome",
-            &["true", "false", "read-only", "tmpfs", "yes", "no"] as &[&str],
-        );
-
-        // Boolean values for security directives
-        let boolean_values = &["true", "false", "yes", "no", "1", "0"] as &[&str];
-        map.insert("NoNewPrivileges", boolean_values);
-        map.insert("PrivateDevices", boolean_values);
-        map.insert("PrivateNetwork", boolean_values);
-        map.insert("PrivateUsers", boolean_values);
-        map.insert("PrivateMounts", boolean_values);
-        map.insert("ProtectKernelTunables", boolean_values);
-        map.insert("ProtectKernelModules", boolean_values);
-        map.insert("ProtectKernelLogs", boolean_values);
-        map.insert("ProtectControlGroups", boolean_values);
-        map.insert("RestrictRealtime", boolean_values);
-        map.insert("RestrictSUIDSGID", boolean_values);
-        map.insert("RemoveIPC", boolean_values);
-        map.insert("DynamicUser", boolean_values);
-        map.insert("MountAPIVFS", boolean_values);
-        map.insert("LockPersonality", boolean_values);
-        map.insert("MemoryDenyWriteExecute", boolean_values);
-        map.insert("ProtectHostname", boolean_values);
-        map.insert("ProtectClock", boolean_values);
-        map.insert("PrivatePIDs", boolean_values);
-
-        // PrivateTmp with additional 'disconnected' value
-        map.insert(
-            "PrivateTmp",
-            &["true", "false", "yes", "no", "1", "0", "disconnected"] as &[&str],
-        );
-
-        // DevicePolicy values
-        map.insert("DevicePolicy", &["auto", "closed", "strict"] as &[&str]);
-
-        // ProtectProc values
-        map.insert(
-            "ProtectProc",
-            &["noaccess", "invisible", "ptraceable", "default"] as &[&str],
-        );
-
-        // NotifyAccess values
-        map.insert("NotifyAccess", &["none", "main", "exec", "all"] as &[&str]);
-
-        let standard_io_values = &[
-            "inherit",
-            "null",
-            "tty",
-            "journal",
-            "kmsg",
-            "journal+console",
-            "kmsg+console",
-            "file:",
-            "append:",
-            "truncate:",
-            "socket",
-        ] as &[&str];
-        map.insert("StandardOutput", standard_io_values);
-        map.insert("StandardError", standard_io_values);
-
-        map
-    }
-
-    pub fn valid_values_for_section(
-        section: &str,
-        directive: &str,
-    ) -> Option<&'static [&'static str]> {
-        match (section, directive) {
-            ("Service", "Type") => Some(&[
-                "simple",
-                "exec",
-                "forking",
-                "oneshot",
-                "dbus",
-                "notify",
-                "notify-reload",
-                "idle",
-            ]),
-            ("Mount", "Type") => Some(&[
-                "ext4", "ext3", "ext2", "xfs", "btrfs", "vfat", "ntfs", "exfat", "iso9660",
-                "tmpfs", "proc", "sysfs", "devpts", "nfs", "nfs4", "cifs", "sshfs", "bind",
-                "overlay", "squashfs", "fuse", "none", "auto",
-            ]),
-            _ => {
-                // Fall back to global valid_values for other directives
-                let global_values = Self::valid_values();
-                global_values.get(directive).copied()
+        for entry in schema::directives(UnitDialect::Systemd) {
+            if let Some(values) = entry.allowed_values() {
+                map.entry(entry.name).or_insert(values);
             }
         }
+        map
     }
 
     pub fn section_documentation() -> HashMap<&'static str, &'static str> {
@@ -309,31 +92,136 @@ impl SystemdConstants {
         map
     }
 
-    /// Get shared documentation files (exec, kill, resource-control)
-    /// These contain directives that are shared across multiple section types
-    pub fn shared_documentation() -> HashMap<&'static str, &'static str> {
+    /// Valid sections for a dialect; systemd units use `docs/sections.txt`
+    pub fn dialect_valid_sections(dialect: UnitDialect) -> Vec<&'static str> {
+        match dialect {
+            UnitDialect::Systemd => Self::valid_sections(),
+            _ => Self::dialect_section_directives(dialect)
+                .into_keys()
+                .collect(),
+        }
+    }
+
+    /// Directives per section for a dialect. The systemd-networkd lists live in
+    /// `docs/networkd/<file type>/<section>.txt`, the daemon configuration files
+    /// in `docs/conf/<daemon>/<section>.txt`.
+    pub fn dialect_section_directives(
+        dialect: UnitDialect,
+    ) -> HashMap<&'static str, Vec<&'static str>> {
+        Self::schema_section_directives(dialect)
+    }
+
+    pub fn dialect_section_documentation(
+        dialect: UnitDialect,
+    ) -> HashMap<&'static str, &'static str> {
         let mut map = HashMap::new();
-        map.insert("exec", include_str!("../docs/exec.md"));
-        map.insert("kill", include_str!("../docs/kill.md"));
-        map.insert("resource-control", include_str!("../docs/resource-control.md"));
+
+        match dialect {
+            UnitDialect::Systemd => return Self::section_documentation(),
+            UnitDialect::NetworkdNetwork => {
+                map.insert("Match", include_str!("../docs/networkd/network/match.md"));
+                map.insert("Link", include_str!("../docs/networkd/network/link.md"));
+                map.insert(
+                    "Network",
+                    include_str!("../docs/networkd/network/network.md"),
+                );
+                map.insert(
+                    "Address",
+                    include_str!("../docs/networkd/network/address.md"),
+                );
+                map.insert("Route", include_str!("../docs/networkd/network/route.md"));
+                map.insert("DHCPv4", include_str!("../docs/networkd/network/dhcpv4.md"));
+            }
+            UnitDialect::NetworkdNetDev => {
+                map.insert("Match", include_str!("../docs/networkd/netdev/match.md"));
+                map.insert("NetDev", include_str!("../docs/networkd/netdev/netdev.md"));
+                map.insert("VLAN", include_str!("../docs/networkd/netdev/vlan.md"));
+                map.insert(
+                    "MACVLAN",
+                    include_str!("../docs/networkd/netdev/macvlan.md"),
+                );
+                map.insert("Bridge", include_str!("../docs/networkd/netdev/bridge.md"));
+                map.insert("Bond", include_str!("../docs/networkd/netdev/bond.md"));
+                map.insert(
+                    "WireGuard",
+                    include_str!("../docs/networkd/netdev/wireguard.md"),
+                );
+                map.insert(
+                    "WireGuardPeer",
+                    include_str!("../docs/networkd/netdev/wireguardpeer.md"),
+                );
+                map.insert("VXLAN", include_str!("../docs/networkd/netdev/vxlan.md"));
+                map.insert("Tun", include_str!("../docs/networkd/netdev/tun.md"));
+                map.insert("Tap", include_str!("../docs/networkd/netdev/tap.md"));
+            }
+            UnitDialect::NetworkdLink => {
+                map.insert("Match", include_str!("../docs/networkd/link/match.md"));
+                map.insert("Link", include_str!("../docs/networkd/link/link.md"));
+            }
+            UnitDialect::Journald => {
+                map.insert("Journal", include_str!("../docs/conf/journald/journal.md"));
+            }
+            UnitDialect::Logind => {
+                map.insert("Login", include_str!("../docs/conf/logind/login.md"));
+            }
+            UnitDialect::SystemConf => {
+                map.insert("Manager", include_str!("../docs/conf/system/manager.md"));
+            }
+            UnitDialect::Timesyncd => {
+                map.insert("Time", include_str!("../docs/conf/timesyncd/time.md"));
+            }
+            UnitDialect::Resolved => {
+                map.insert("Resolve", include_str!("../docs/conf/resolved/resolve.md"));
+            }
+        }
+
         map
     }
 
-    /// Returns the list of shared documentation keys that apply to a given section
-    /// This mapping determines which additional directive sets are available in each section
-    pub fn section_shared_docs(section: &str) -> Vec<&'static str> {
-        match section.to_lowercase().as_str() {
-            "service" => vec!["exec", "kill", "resource-control"],
-            "socket" => vec!["exec", "kill", "resource-control"],
-            "mount" => vec!["exec", "kill", "resource-control"],
-            "swap" => vec!["exec", "kill", "resource-control"],
-            "scope" => vec!["kill", "resource-control"],
-            "slice" => vec!["resource-control"],
-            _ => vec![],
-        }
+    pub fn valid_values_for_dialect(
+        dialect: UnitDialect,
+        section: &str,
+        directive: &str,
+    ) -> Option<&'static [&'static str]> {
+        schema::lookup(dialect, section, directive)?.allowed_values()
+    }
+
+    /// Minimum systemd version of each `(section, directive)` of a dialect, taken from the
+    /// "Added in version N." line closing the directive's documentation. Directives that
+    /// predate these annotations have no entry.
+    pub fn directive_versions(dialect: UnitDialect) -> HashMap<(&'static str, &'static str), u32> {
+        schema::directives(dialect)
+            .filter_map(|entry| Some(((entry.section, entry.name), entry.since?)))
+            .collect()
+    }
+
+    /// Minimum systemd version of a value, when it is newer than the directive accepting it
+    pub fn value_version(section: &str, directive: &str, value: &str) -> Option<u32> {
+        schema::lookup(UnitDialect::Systemd, section, directive)?.value_version(value)
+    }
+
+    pub fn deprecated_directives() -> impl Iterator<Item = &'static DirectiveSchema> {
+        schema::directives(UnitDialect::Systemd).filter(|entry| entry.deprecation.is_some())
+    }
+
+    /// Look up a deprecated or removed directive as it appears in the given section
+    pub fn deprecated_directive(
+        section: &str,
+        directive: &str,
+    ) -> Option<&'static DirectiveSchema> {
+        Self::deprecated_directives()
+            .find(|entry| entry.section == section && entry.name == directive)
     }
 
     pub const APP_NAME: &'static str = "systemdls";
+
+    /// Signal names without their `SIG` prefix, as `signal_from_string()` accepts them
+    pub const SIGNALS: &'static [&'static str] = &[
+        "HUP", "INT", "QUIT", "ILL", "TRAP", "ABRT", "IOT", "BUS", "FPE", "KILL", "USR1", "SEGV",
+        "USR2", "PIPE", "ALRM", "TERM", "STKFLT", "CHLD", "CONT", "STOP", "TSTP", "TTIN", "TTOU",
+        "URG", "XCPU", "XFSZ", "VTALRM", "PROF", "WINCH", "IO", "POLL", "PWR", "SYS", "RTMIN",
+        "RTMAX",
+    ];
 }
 
 #[cfg(test)]
@@ -410,7 +298,8 @@ mod tests {
     fn test_valid_values_for_type_directive() {
         // Test Service Type directive
         let service_type_values =
-            SystemdConstants::valid_values_for_section("Service", "Type").unwrap();
+            SystemdConstants::valid_values_for_dialect(UnitDialect::Systemd, "Service", "Type")
+                .unwrap();
         assert!(service_type_values.contains(&"simple"));
         assert!(service_type_values.contains(&"exec"));
         assert!(service_type_values.contains(&"forking"));
@@ -421,7 +310,8 @@ mod tests {
 
         // Test Mount Type directive
         let mount_type_values =
-            SystemdConstants::valid_values_for_section("Mount", "Type").unwrap();
+            SystemdConstants::valid_values_for_dialect(UnitDialect::Systemd, "Mount", "Type")
+                .unwrap();
         assert!(mount_type_values.contains(&"ext4"));
         assert!(mount_type_values.contains(&"exfat"));
         assert!(mount_type_values.contains(&"ntfs"));
@@ -529,4 +419,165 @@ mod tests {
         assert!(protect_home.contains(&"yes"));
         assert!(protect_home.contains(&"no"));
     }
+
+    #[test]
+    fn test_networkd_dialect_sections() {
+        let network = SystemdConstants::dialect_section_directives(UnitDialect::NetworkdNetwork);
+        for section in ["Match", "Link", "Network", "Address", "Route", "DHCPv4"] {
+            assert!(network.contains_key(section), "missing [{}]", section);
+        }
+        assert!(network["Network"].contains(&"DHCP"));
+        assert!(!network["Network"].contains(&"NetworkName"));
+
+        let netdev = SystemdConstants::dialect_section_directives(UnitDialect::NetworkdNetDev);
+        assert!(netdev["NetDev"].contains(&"Kind"));
+        assert!(netdev.contains_key("WireGuardPeer"));
+
+        let link = SystemdConstants::dialect_section_directives(UnitDialect::NetworkdLink);
+        assert!(link["Link"].contains(&"NamePolicy"));
+    }
+
+    #[test]
+    fn test_dialect_documentation_matches_directives() {
+        for dialect in UnitDialect::ALL {
+            let docs = SystemdConstants::dialect_section_documentation(dialect);
+            for section in SystemdConstants::dialect_section_directives(dialect).keys() {
+                assert!(
+                    docs.contains_key(section),
+                    "{:?} [{}] has no docs",
+                    dialect,
+                    section
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_valid_values_for_dialect() {
+        let dhcp = SystemdConstants::valid_values_for_dialect(
+            UnitDialect::NetworkdNetwork,
+            "Network",
+            "DHCP",
+        )
+        .unwrap();
+        assert!(dhcp.contains(&"ipv4"));
+
+        // Unit-file values must not leak into networkd files
+        assert!(SystemdConstants::valid_values_for_dialect(
+            UnitDialect::NetworkdNetwork,
+            "Network",
+            "Restart"
+        )
+        .is_none());
+        assert!(SystemdConstants::valid_values_for_dialect(
+            UnitDialect::Systemd,
+            "Service",
+            "Restart"
+        )
+        .is_some());
+    }
+
+    #[test]
+    fn test_config_file_dialects() {
+        let sections = [
+            (UnitDialect::Journald, "Journal", "Storage"),
+            (UnitDialect::Logind, "Login", "HandleLidSwitch"),
+            (UnitDialect::SystemConf, "Manager", "DefaultLimitNOFILE"),
+            (UnitDialect::Timesyncd, "Time", "FallbackNTP"),
+            (UnitDialect::Resolved, "Resolve", "DNSOverTLS"),
+        ];
+        for (dialect, section, directive) in sections {
+            let directives = SystemdConstants::dialect_section_directives(dialect);
+            assert_eq!(directives.len(), 1, "{:?}", dialect);
+            assert!(directives[section].contains(&directive));
+        }
+
+        let lid = SystemdConstants::valid_values_for_dialect(
+            UnitDialect::Logind,
+            "Login",
+            "HandleLidSwitchDocked",
+        )
+        .unwrap();
+        assert!(lid.contains(&"suspend-then-hibernate"));
+        let max_level = SystemdConstants::valid_values_for_dialect(
+            UnitDialect::Journald,
+            "Journal",
+            "MaxLevelConsole",
+        )
+        .unwrap();
+        assert!(max_level.contains(&"warning"));
+    }
+
+    #[test]
+    fn test_directive_versions() {
+        let versions = SystemdConstants::directive_versions(UnitDialect::Systemd);
+        assert_eq!(versions.get(&("Service", "ExecCondition")), Some(&243));
+        // Shared exec documentation applies to every section that includes it
+        assert_eq!(versions.get(&("Service", "PrivatePIDs")), Some(&257));
+        assert_eq!(versions.get(&("Socket", "PrivatePIDs")), Some(&257));
+        // RestartMode= documents versions per value; the last line is the directive's own
+        assert_eq!(versions.get(&("Service", "RestartMode")), Some(&254));
+        // Directives older than the annotations have no version
+        assert!(!versions.contains_key(&("Service", "Type")));
+
+        let journald = SystemdConstants::directive_versions(UnitDialect::Journald);
+        assert_eq!(journald.get(&("Journal", "ForwardToSocket")), Some(&256));
+    }
+
+    #[test]
+    fn test_value_versions() {
+        assert_eq!(
+            SystemdConstants::value_version("Service", "Type", "notify-reload"),
+            Some(253)
+        );
+        assert_eq!(
+            SystemdConstants::value_version("Service", "StandardOutput", "append:/var/log/app.log"),
+            Some(240)
+        );
+        assert_eq!(
+            SystemdConstants::value_version("Service", "ProtectControlGroups", "private"),
+            Some(257)
+        );
+        assert_eq!(
+            SystemdConstants::value_version("Service", "Type", "simple"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_deprecated_directives() {
+        let cpu_shares = SystemdConstants::deprecated_directive("Service", "CPUShares").unwrap();
+        assert_eq!(
+            cpu_shares.deprecation.unwrap().replacement,
+            Some("CPUWeight")
+        );
+        assert_eq!(
+            cpu_shares.deprecation_message().unwrap(),
+            "CPUShares= is deprecated, use CPUWeight= instead"
+        );
+
+        let interval =
+            SystemdConstants::deprecated_directive("Service", "StartLimitInterval").unwrap();
+        assert_eq!(
+            interval.deprecation_message().unwrap(),
+            "StartLimitInterval= in [Service] is deprecated, use StartLimitIntervalSec= in [Unit] instead"
+        );
+
+        // Valid in [Unit], only deprecated in [Service]
+        assert!(SystemdConstants::deprecated_directive("Unit", "StartLimitBurst").is_none());
+        assert!(SystemdConstants::deprecated_directive("Service", "MemoryMax").is_none());
+
+        // Deprecated directives must not also be listed as valid ones
+        let directives = SystemdConstants::section_directives();
+        for entry in SystemdConstants::deprecated_directives() {
+            assert!(
+                !directives
+                    .get(entry.section)
+                    .is_some_and(|keys| keys.contains(&entry.name)),
+                "{}= is still listed in [{}]",
+                entry.name,
+                entry.section
+            );
+        }
+    }
 }
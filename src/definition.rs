@@ -1,43 +1,48 @@
 use log::debug;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tower_lsp_server::lsp_types::{GotoDefinitionResponse, Location, Position, Range, Uri};
 use tower_lsp_server::UriExt;
 
 use crate::constants::SystemdConstants;
-use crate::parser::SystemdParser;
-
-#[derive(Debug)]
-// this is the shared file for loading the embedded documentation
-pub struct SystemdDefinitionProvider {
-    shared_temp_file: Option<PathBuf>,
-}
+use crate::dialect::UnitDialect;
+use crate::environment::{self, DefinitionSource, SectionEnvironment};
+use crate::parser::{SystemdParser, SystemdSection, SystemdUnit};
+use crate::schema::{self, ValueType};
+use crate::unit_name;
+use crate::value_providers::ValueProvider;
+
+/// URI scheme of the documentation pages, e.g. `systemd-doc://service#Restart`. Clients
+/// fetch their markdown with the `systemd-lsp/virtualDocument` request.
+pub const DOCUMENTATION_SCHEME: &str = "systemd-doc";
+
+/// Manual pages documenting directives that several unit sections accept, as
+/// (page name, manual page, markdown)
+const SHARED_DOCUMENTATION: &[(&str, &str, &str)] = &[
+    ("exec", "systemd.exec.5", include_str!("../docs/exec.md")),
+    ("kill", "systemd.kill.5", include_str!("../docs/kill.md")),
+    (
+        "resource-control",
+        "systemd.resource-control.5",
+        include_str!("../docs/resource-control.md"),
+    ),
+];
+
+/// Directories systemd searches for executables named without a path
+const EXECUTABLE_SEARCH_PATH: &[&str] =
+    &["/usr/local/sbin", "/usr/local/bin", "/usr/sbin", "/usr/bin"];
+
+/// Prefixes of `Exec*=` commands such as `-` in `ExecStartPre=-/usr/bin/true`
+const EXEC_PREFIXES: &[char] = &['@', '-', ':', '+', '!', '|'];
+
+/// Serves the embedded section documentation as `systemd-doc://` pages. Units name
+/// the page after the section (`systemd-doc://service`), other dialects prefix it
+/// with their manual page (`systemd-doc://journald.conf/journal`).
+#[derive(Debug, Default)]
+pub struct SystemdDefinitionProvider;
 
 impl SystemdDefinitionProvider {
     pub fn new() -> Self {
-        // Create a single shared temp file for all documentation
-        let shared_temp_file = if let Ok(temp_dir) = std::env::temp_dir().canonicalize() {
-            let temp_file = temp_dir.join("systemdls-documentation.md");
-
-            // Create the file with initial content if it doesn't exist
-            if !temp_file.exists() {
-                let initial_content = "# systemd Documentation\n\nSelect a section header and use goto definition to view documentation.\n";
-                if std::fs::write(&temp_file, initial_content).is_ok() {
-                    debug!("Created shared temp file for documentation");
-                    Some(temp_file)
-                } else {
-                    debug!("Failed to create shared temp file");
-                    None
-                }
-            } else {
-                debug!("Reusing existing shared temp file");
-                Some(temp_file)
-            }
-        } else {
-            debug!("Failed to get temp directory");
-            None
-        };
-
-        Self { shared_temp_file }
+        Self
     }
 
     pub async fn get_definition(
@@ -53,76 +58,279 @@ impl SystemdDefinitionProvider {
 
         let parsed = parser.get_parsed_document(uri)?;
 
-        debug!(
-            "Found parsed document with {} sections",
-            parsed.sections.len()
-        );
-        for (name, section) in &parsed.sections {
-            debug!(
-                "Section '{}' at lines {}-{}",
-                name, section.line_range.0, section.line_range.1
-            );
-        }
-
-        // Only handle section headers for go-to definition
         if let Some(section_name) = parser.get_section_header_at_position(&parsed, position) {
             debug!("Found section header '{}' at position", section_name);
-            return self.get_section_man_page_definition(&section_name).await;
-        } else {
+            return self.get_section_definition(parsed.dialect, &section_name);
+        }
+
+        let section = parser.get_section_at_line(&parsed, position.line)?;
+        let directive = section.directives.iter().find(|directive| {
+            directive.line_number == position.line
+                && directive.column_range.0 <= position.character
+                && position.character <= directive.column_range.1
+        });
+        if let Some(directive) = directive {
             debug!(
-                "No section header found at position {}:{}",
-                position.line, position.character
+                "Found directive '{}' in section '{}' at position",
+                directive.key, section.name
             );
+            return self.get_directive_definition(parsed.dialect, &section.name, &directive.key);
         }
 
-        None
+        self.get_value_definition(parser, uri, &parsed, section, position)
     }
 
-    async fn get_section_man_page_definition(
+    /// The file a path or command value refers to, the unit file of a unit name, or
+    /// the definitions of the `$VARIABLE` under the cursor in a command line
+    fn get_value_definition(
         &self,
-        section_name: &str,
+        parser: &SystemdParser,
+        uri: &Uri,
+        unit: &SystemdUnit,
+        section: &SystemdSection,
+        position: &Position,
     ) -> Option<GotoDefinitionResponse> {
-        let docs = SystemdConstants::section_documentation();
+        let (directive, span) = section.directives.iter().find_map(|directive| {
+            directive
+                .value_spans
+                .iter()
+                .find(|span| {
+                    span.line == position.line
+                        && span.start <= position.character
+                        && position.character <= span.end
+                })
+                .map(|span| (directive, span))
+        })?;
+        let entry = schema::find(unit.dialect, &section.name, &directive.key)?;
+
+        let chars: Vec<char> = unit
+            .raw_text
+            .lines()
+            .nth(span.line as usize)?
+            .chars()
+            .collect();
+        let span_end = (span.end as usize).min(chars.len());
+        let span_start = (span.start as usize).min(span_end);
+        let cursor = (position.character as usize).clamp(span_start, span_end);
+
+        if entry.value_type == ValueType::Command {
+            let text: String = chars[span_start..span_end].iter().collect();
+            if let Some(reference) = environment::references(&text)
+                .into_iter()
+                .find(|reference| {
+                    span_start + reference.start <= cursor && cursor <= span_start + reference.end
+                })
+            {
+                debug!("Looking up the definition of ${}", reference.name);
+                return self.get_variable_definition(uri, unit, section, &reference.name);
+            }
+        }
+
+        // The whitespace-separated word of the value the cursor is on
+        let start = (span_start..cursor)
+            .rev()
+            .find(|&index| chars[index].is_whitespace())
+            .map_or(span_start, |index| index + 1);
+        let end = (cursor..span_end)
+            .find(|&index| chars[index].is_whitespace())
+            .unwrap_or(span_end);
+        let word: String = chars[start..end].iter().collect();
+
+        if entry.value_type == ValueType::Units {
+            // Instances such as `getty@tty1.service` are defined by their template
+            if let Some(open) = parser.find_unit_document(&word) {
+                return Some(Self::location(open, 0, 0));
+            }
+            let near = uri
+                .to_file_path()
+                .and_then(|path| path.parent().map(Path::to_path_buf));
+            let path = unit_name::find_unit_file(&word, near.as_deref())?;
+            debug!("Unit {} is defined by {}", word, path.display());
+            return Some(Self::location(Uri::from_file_path(path)?, 0, 0));
+        }
 
-        // Try to find documentation (case-insensitive)
-        let content = docs.iter().find_map(|(key, value)| {
-            if key.eq_ignore_ascii_case(section_name) {
-                Some(*value)
+        let path = if entry.value_type == ValueType::Command {
+            let command = word.trim_start_matches(EXEC_PREFIXES);
+            let is_executable = span.line == directive.line_number
+                && chars[span_start..start].iter().all(|c| c.is_whitespace());
+            if is_executable {
+                Self::resolve_executable(command)
             } else {
-                None
+                Self::existing_path(Path::new(command))
             }
-        });
+        } else if ValueProvider::for_directive(unit.dialect, &section.name, &directive.key)
+            == Some(ValueProvider::Paths)
+        {
+            let path = Path::new(word.trim_start_matches(['-', '+', '!', ':']));
+            if path.is_absolute() {
+                Self::existing_path(path)
+            } else if entry.name == "PIDFile" {
+                // Relative PIDFile= paths are placed below /run/
+                Self::existing_path(&Path::new("/run").join(path))
+            } else {
+                // Quadlet resolves relative paths against the unit file
+                Self::existing_path(&uri.to_file_path()?.parent()?.join(path))
+            }
+        } else {
+            None
+        }?;
+
+        debug!("Value refers to {}", path.display());
+        Some(Self::location(Uri::from_file_path(path)?, 0, 0))
+    }
 
-        // Update the shared temp file with the requested section's documentation
-        if let Some(temp_file) = &self.shared_temp_file {
-            if let Some(content) = content {
-                if std::fs::write(temp_file, content).is_ok() {
-                    debug!(
-                        "Updated shared temp file with {} documentation",
-                        section_name
-                    );
-                    if let Some(uri) = Uri::from_file_path(temp_file) {
-                        let location = Location {
-                            uri,
-                            range: Range {
-                                start: Position {
-                                    line: 0,
-                                    character: 0,
-                                },
-                                end: Position {
-                                    line: 0,
-                                    character: 0,
-                                },
-                            },
-                        };
-                        return Some(GotoDefinitionResponse::Scalar(location));
-                    }
-                }
+    /// The `Environment=` assignments and environment file lines of the section that
+    /// define `name`
+    fn get_variable_definition(
+        &self,
+        uri: &Uri,
+        unit: &SystemdUnit,
+        section: &SystemdSection,
+        name: &str,
+    ) -> Option<GotoDefinitionResponse> {
+        let unit_path = uri.to_file_path();
+        let environment = SectionEnvironment::new(unit, section, unit_path.as_deref());
+        let mut locations: Vec<Location> = environment
+            .definitions_of(name)
+            .filter_map(|definition| match &definition.source {
+                DefinitionSource::Directive { line, column } => Some(Location {
+                    uri: uri.clone(),
+                    range: Self::range(*line, *column),
+                }),
+                DefinitionSource::File { path, line } => Some(Location {
+                    uri: Uri::from_file_path(path)?,
+                    range: Self::range(*line, 0),
+                }),
+            })
+            .collect();
+
+        match locations.len() {
+            0 => None,
+            1 => locations.pop().map(GotoDefinitionResponse::Scalar),
+            _ => Some(GotoDefinitionResponse::Array(locations)),
+        }
+    }
+
+    /// An executable given by path, or by name from the default search path
+    fn resolve_executable(command: &str) -> Option<PathBuf> {
+        if command.contains('/') {
+            return Self::existing_path(Path::new(command));
+        }
+        EXECUTABLE_SEARCH_PATH
+            .iter()
+            .find_map(|directory| Self::existing_path(&Path::new(directory).join(command)))
+    }
+
+    fn existing_path(path: &Path) -> Option<PathBuf> {
+        (path.is_absolute() && path.exists()).then(|| path.to_path_buf())
+    }
+
+    /// The top of the section's documentation page
+    fn get_section_definition(
+        &self,
+        dialect: UnitDialect,
+        section_name: &str,
+    ) -> Option<GotoDefinitionResponse> {
+        let (section, _) = Self::section_documentation(dialect, section_name)?;
+        let uri = Self::documentation_uri(dialect, &section.to_lowercase(), None)?;
+        Some(Self::location(uri, 0, 0))
+    }
+
+    /// The `### Directive=` heading of the page documenting the directive; shared
+    /// directives such as `User=` live on the systemd.exec(5) page
+    fn get_directive_definition(
+        &self,
+        dialect: UnitDialect,
+        section_name: &str,
+        key: &str,
+    ) -> Option<GotoDefinitionResponse> {
+        let entry = schema::find(dialect, section_name, key)?;
+        let shared = SHARED_DOCUMENTATION
+            .iter()
+            .find(|(_, man_page, _)| dialect.is_unit() && *man_page == entry.man_page);
+        let (page, content) = match shared {
+            Some((page, _, content)) => (page.to_string(), *content),
+            None => {
+                let (section, content) = Self::section_documentation(dialect, entry.section)?;
+                (section.to_lowercase(), content)
             }
+        };
+
+        let heading = format!("### {}=", entry.name);
+        let line = content
+            .lines()
+            .position(|line| line.starts_with(&heading))
+            .unwrap_or(0);
+        let uri = Self::documentation_uri(dialect, &page, Some(entry.name))?;
+        Some(Self::location(uri, line as u32, 0))
+    }
+
+    fn range(line: u32, character: u32) -> Range {
+        let position = Position { line, character };
+        Range {
+            start: position,
+            end: position,
         }
+    }
+
+    fn location(uri: Uri, line: u32, character: u32) -> GotoDefinitionResponse {
+        GotoDefinitionResponse::Scalar(Location {
+            uri,
+            range: Self::range(line, character),
+        })
+    }
 
-        debug!("No documentation available for section: {}", section_name);
-        None
+    fn section_documentation(
+        dialect: UnitDialect,
+        section_name: &str,
+    ) -> Option<(&'static str, &'static str)> {
+        SystemdConstants::dialect_section_documentation(dialect)
+            .into_iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(section_name))
+    }
+
+    /// Name of a dialect's pages, e.g. `journald.conf` for `journald.conf.5`
+    fn dialect_page_prefix(dialect: UnitDialect) -> Option<&'static str> {
+        dialect
+            .man_page()
+            .and_then(|man_page| man_page.rsplit_once('.'))
+            .map(|(name, _)| name)
+    }
+
+    fn documentation_uri(dialect: UnitDialect, page: &str, directive: Option<&str>) -> Option<Uri> {
+        let mut uri = match Self::dialect_page_prefix(dialect) {
+            Some(prefix) => format!("{}://{}/{}", DOCUMENTATION_SCHEME, prefix, page),
+            None => format!("{}://{}", DOCUMENTATION_SCHEME, page),
+        };
+        if let Some(directive) = directive {
+            uri.push('#');
+            uri.push_str(directive);
+        }
+        uri.parse().ok()
+    }
+
+    /// Markdown of a `systemd-doc://` page; the `#Directive` fragment is ignored
+    pub fn documentation_content(&self, uri: &Uri) -> Option<String> {
+        let page = uri
+            .as_str()
+            .strip_prefix(DOCUMENTATION_SCHEME)?
+            .strip_prefix("://")?;
+        let page = page.split('#').next()?.trim_end_matches('/');
+
+        let content = match page.split_once('/') {
+            Some((prefix, section)) => {
+                let dialect = UnitDialect::ALL
+                    .into_iter()
+                    .find(|dialect| Self::dialect_page_prefix(*dialect) == Some(prefix))?;
+                Self::section_documentation(dialect, section)?.1
+            }
+            None => SHARED_DOCUMENTATION
+                .iter()
+                .find(|(name, _, _)| *name == page)
+                .map(|(_, _, content)| *content)
+                .or_else(|| Some(Self::section_documentation(UnitDialect::Systemd, page)?.1))?,
+        };
+        Some(content.to_string())
     }
 
     /// Get embedded documentation for a section
@@ -137,17 +345,24 @@ impl SystemdDefinitionProvider {
         })
     }
 
-    /// Clean up temporary documentation files
-    pub fn cleanup_temp_files(&self) {
-        if let Some(temp_file) = &self.shared_temp_file {
-            if temp_file.exists() {
-                if let Err(e) = std::fs::remove_file(temp_file) {
-                    debug!("Failed to remove shared temp file: {}", e);
-                } else {
-                    debug!("Cleaned up shared temp file");
-                }
-            }
+    /// Get embedded documentation for a section of the given dialect
+    pub fn get_dialect_embedded_documentation(
+        &self,
+        dialect: UnitDialect,
+        section_key: &str,
+    ) -> Option<String> {
+        if dialect == UnitDialect::Systemd {
+            return self.get_embedded_documentation(section_key);
         }
+
+        let docs = SystemdConstants::dialect_section_documentation(dialect);
+        docs.iter().find_map(|(key, value)| {
+            if key.eq_ignore_ascii_case(section_key) {
+                Some(value.to_string())
+            } else {
+                None
+            }
+        })
     }
 }
 
@@ -155,8 +370,23 @@ impl SystemdDefinitionProvider {
 mod tests {
     use super::*;
     use crate::parser::SystemdParser;
+    use std::fs;
     use tower_lsp_server::lsp_types::{Position, Uri};
 
+    async fn definition_location(
+        provider: &SystemdDefinitionProvider,
+        parser: &SystemdParser,
+        uri: &Uri,
+        line: u32,
+        character: u32,
+    ) -> Option<Location> {
+        let position = Position { line, character };
+        match provider.get_definition(parser, uri, &position).await? {
+            GotoDefinitionResponse::Scalar(location) => Some(location),
+            _ => None,
+        }
+    }
+
     #[test]
     fn test_embedded_documentation_exists() {
         let docs = SystemdConstants::section_documentation();
@@ -195,12 +425,201 @@ mod tests {
         let result = provider.get_definition(&parser, &uri, &position).await;
         assert!(result.is_some());
 
-        if let Some(GotoDefinitionResponse::Scalar(location)) = result {
-            assert!(location
-                .uri
-                .to_string()
-                .contains("systemdls-documentation.md"));
-        }
+        let Some(GotoDefinitionResponse::Scalar(location)) = result else {
+            panic!("expected a single location");
+        };
+        assert_eq!(location.uri.as_str(), "systemd-doc://unit");
+        assert_eq!(location.range.start.line, 0);
+
+        let content = provider.documentation_content(&location.uri).unwrap();
+        assert!(content.contains("[Unit]"));
+    }
+
+    #[tokio::test]
+    async fn test_get_definition_for_directive() {
+        let provider = SystemdDefinitionProvider::new();
+        let parser = SystemdParser::new();
+
+        let content = "[Service]\nRestart=always\nUser=app\n";
+        let uri = "file:///test.service".parse::<Uri>().unwrap();
+        parser.update_document(&uri, content);
+
+        let restart = definition_location(&provider, &parser, &uri, 1, 3)
+            .await
+            .unwrap();
+        assert_eq!(restart.uri.as_str(), "systemd-doc://service#Restart");
+        let page = provider.documentation_content(&restart.uri).unwrap();
+        assert!(page
+            .lines()
+            .nth(restart.range.start.line as usize)
+            .unwrap()
+            .starts_with("### Restart="));
+
+        // Shared directives jump into the systemd.exec(5) page
+        let user = definition_location(&provider, &parser, &uri, 2, 0)
+            .await
+            .unwrap();
+        assert_eq!(user.uri.as_str(), "systemd-doc://exec#User");
+        let page = provider.documentation_content(&user.uri).unwrap();
+        assert!(page
+            .lines()
+            .nth(user.range.start.line as usize)
+            .unwrap()
+            .starts_with("### User="));
+
+        // Values are not directives
+        assert!(definition_location(&provider, &parser, &uri, 1, 10)
+            .await
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_get_definition_for_paths_and_variables() {
+        let provider = SystemdDefinitionProvider::new();
+        let parser = SystemdParser::new();
+
+        let temp = tempfile::tempdir().unwrap();
+        let directory = temp.path().to_path_buf();
+        fs::write(directory.join("app"), "#!/bin/sh\n").unwrap();
+        fs::write(
+            directory.join("app.env"),
+            "# defaults\nPORT=8080\nHOST=::\n",
+        )
+        .unwrap();
+        let base = directory.display().to_string();
+
+        let content = format!(
+            "[Service]\nEnvironment=\"NAME=app\" PORT=80\nEnvironmentFile=-{base}/app.env\nExecStart={base}/app --name=${{NAME}} $PORT $MISSING\nWorkingDirectory={base}\nExecStartPost=sh -c true\n"
+        );
+        let uri = "file:///test.service".parse::<Uri>().unwrap();
+        parser.update_document(&uri, &content);
+        let exec_line = content.lines().nth(3).unwrap();
+        let column = |needle: &str| exec_line.find(needle).unwrap() as u32 + 1;
+
+        // The executable and the environment file open the files themselves
+        let executable = definition_location(&provider, &parser, &uri, 3, column("/app "))
+            .await
+            .unwrap();
+        assert!(executable.uri.as_str().ends_with("/app"));
+        let environment_file = definition_location(&provider, &parser, &uri, 2, 20)
+            .await
+            .unwrap();
+        assert!(environment_file.uri.as_str().ends_with("/app.env"));
+        let working_directory = definition_location(&provider, &parser, &uri, 4, 20)
+            .await
+            .unwrap();
+        assert_eq!(
+            working_directory.uri,
+            Uri::from_file_path(&directory).unwrap()
+        );
+
+        // Executables named without a path are looked up in the search path
+        let shell = definition_location(&provider, &parser, &uri, 5, 15).await;
+        assert_eq!(shell.is_some(), Path::new("/usr/bin/sh").exists());
+
+        // ${NAME} is assigned by Environment=
+        let name = definition_location(&provider, &parser, &uri, 3, column("{NAME}"))
+            .await
+            .unwrap();
+        assert_eq!(name.uri, uri);
+        assert_eq!(name.range.start, Position::new(1, 13));
+
+        // $PORT is assigned by both Environment= and the environment file
+        let position = Position::new(3, column("$PORT"));
+        let Some(GotoDefinitionResponse::Array(port)) =
+            provider.get_definition(&parser, &uri, &position).await
+        else {
+            panic!("expected both definitions of $PORT");
+        };
+        assert_eq!(port[0].range.start, Position::new(1, 23));
+        assert!(port[1].uri.as_str().ends_with("/app.env"));
+        assert_eq!(port[1].range.start.line, 1);
+
+        assert!(
+            definition_location(&provider, &parser, &uri, 3, column("MISSING"))
+                .await
+                .is_none()
+        );
+
+    }
+
+    #[tokio::test]
+    async fn test_get_definition_for_instances() {
+        let provider = SystemdDefinitionProvider::new();
+        let parser = SystemdParser::new();
+
+        let temp = tempfile::tempdir().unwrap();
+        let directory = temp.path().to_path_buf();
+        fs::write(directory.join("worker@.service"), "[Service]\n").unwrap();
+        let uri = Uri::from_file_path(directory.join("app.target")).unwrap();
+        parser.update_document(
+            &uri,
+            "[Unit]\nWants=worker@1.service db.service\nAfter=no-such-unit-here.service\n",
+        );
+
+        // The instance opens its template
+        let worker = definition_location(&provider, &parser, &uri, 1, 8)
+            .await
+            .unwrap();
+        assert_eq!(
+            worker.uri,
+            Uri::from_file_path(directory.join("worker@.service")).unwrap()
+        );
+        assert!(definition_location(&provider, &parser, &uri, 2, 10)
+            .await
+            .is_none());
+
+        // An open template is preferred over the file on disk
+        let open = "file:///srv/units/worker@.service".parse::<Uri>().unwrap();
+        parser.update_document(&open, "[Service]\n");
+        let worker = definition_location(&provider, &parser, &uri, 1, 8)
+            .await
+            .unwrap();
+        assert_eq!(worker.uri, open);
+
+    }
+
+    #[tokio::test]
+    async fn test_get_definition_in_daemon_configuration() {
+        let provider = SystemdDefinitionProvider::new();
+        let parser = SystemdParser::new();
+
+        let content = "[Journal]\nStorage=persistent\n";
+        let uri = "file:///etc/systemd/journald.conf".parse::<Uri>().unwrap();
+        parser.update_document(&uri, content);
+
+        let position = Position {
+            line: 1,
+            character: 2,
+        };
+        let Some(GotoDefinitionResponse::Scalar(location)) =
+            provider.get_definition(&parser, &uri, &position).await
+        else {
+            panic!("expected a single location");
+        };
+        assert_eq!(
+            location.uri.as_str(),
+            "systemd-doc://journald.conf/journal#Storage"
+        );
+        assert!(provider.documentation_content(&location.uri).is_some());
+    }
+
+    #[test]
+    fn test_documentation_content() {
+        let provider = SystemdDefinitionProvider::new();
+        let content = |uri: &str| provider.documentation_content(&uri.parse::<Uri>().unwrap());
+
+        assert!(content("systemd-doc://service")
+            .unwrap()
+            .contains("[Service]"));
+        assert_eq!(
+            content("systemd-doc://service#Restart"),
+            content("systemd-doc://service")
+        );
+        assert!(content("systemd-doc://resource-control").is_some());
+        assert!(content("systemd-doc://systemd.network/match").is_some());
+        assert!(content("systemd-doc://unknown").is_none());
+        assert!(content("file:///tmp/service.md").is_none());
     }
 
     #[tokio::test]
@@ -212,10 +631,10 @@ mod tests {
         let uri = "file:///test.service".parse::<Uri>().unwrap();
         parser.update_document(&uri, content);
 
-        // Test position not on a section header (line 1)
+        // Test position on a value rather than a section header or directive (line 1)
         let position = Position {
             line: 1,
-            character: 0,
+            character: 14,
         };
         let result = provider.get_definition(&parser, &uri, &position).await;
         assert!(result.is_none());
@@ -1,45 +1,216 @@
 use crate::constants::SystemdConstants;
-use crate::parser::{DirectiveValueSpan, SystemdSection, SystemdUnit};
+use crate::dialect::UnitDialect;
+use crate::environment::{self, SectionEnvironment};
+use crate::graph::{self, OrderingDeclaration, OrderingIndex, ReferenceIndex};
+use crate::install;
+use crate::parser::{DirectiveValueSpan, SystemdDirective, SystemdSection, SystemdUnit};
+use crate::schema::{self, DirectiveSchema, ValueType};
+use crate::security::SecurityReport;
+use crate::unit_name;
+use crate::validators;
+use crate::value_providers;
 use dashmap::DashMap;
 use log::{debug, trace};
-use std::collections::HashSet;
-use tower_lsp_server::lsp_types::{Diagnostic, DiagnosticSeverity, Position, Range, Uri};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::RwLock;
+use tower_lsp_server::lsp_types::{
+    Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity, DiagnosticTag, Location,
+    NumberOrString, Position, Range, Uri,
+};
+use tower_lsp_server::UriExt;
+
+/// Diagnostic code of deprecated directives, used to attach the migration code action
+pub const DEPRECATED_DIRECTIVE_CODE: &str = "deprecated-directive";
+
+/// Diagnostic code of directives and values newer than the configured target version
+pub const REQUIRES_NEWER_SYSTEMD_CODE: &str = "requires-newer-systemd";
+
+/// Diagnostic code of the security exposure summary, used to attach the hardening code actions
+pub const SECURITY_EXPOSURE_CODE: &str = "security-exposure";
+
+/// Diagnostic code of timers, paths and sockets whose unit is missing, used to attach the
+/// code action creating it. The data holds the missing unit's name and the template to
+/// create it from.
+pub const MISSING_ACTIVATED_UNIT_CODE: &str = "missing-activated-unit";
+
+/// Diagnostic code of mount and automount units not named after their `Where=` path,
+/// used to attach the rename quick fix. The data holds the name the unit should have and
+/// the path its current name stands for.
+pub const MOUNT_NAME_CODE: &str = "mount-name-mismatch";
+
+/// `Type=` of mounts whose `What=` is a block device or an image file
+const BLOCK_FILE_SYSTEMS: &[&str] = &[
+    "ext4", "ext3", "ext2", "xfs", "btrfs", "vfat", "ntfs", "exfat", "iso9660", "squashfs",
+];
+
+/// Mount flags that undo each other, of which the last one given wins
+const OPPOSITE_MOUNT_FLAGS: &[(&str, &str)] = &[
+    ("ro", "rw"),
+    ("auto", "noauto"),
+    ("exec", "noexec"),
+    ("suid", "nosuid"),
+    ("dev", "nodev"),
+    ("sync", "async"),
+    ("atime", "noatime"),
+    ("relatime", "norelatime"),
+];
+
+/// Settings of `[Timer]` that make a timer elapse
+const TIMER_TRIGGERS: &[&str] = &[
+    "OnActiveSec",
+    "OnBootSec",
+    "OnStartupSec",
+    "OnUnitActiveSec",
+    "OnUnitInactiveSec",
+    "OnCalendar",
+    "OnClockChange",
+    "OnTimezoneChange",
+];
+
+/// Settings of `[Path]` naming the paths a path unit watches
+const PATH_CONDITIONS: &[&str] = &[
+    "PathExists",
+    "PathExistsGlob",
+    "PathChanged",
+    "PathModified",
+    "DirectoryNotEmpty",
+];
+
+/// Settings of `[Socket]` naming what a socket listens on
+const LISTEN_SETTINGS: &[&str] = &[
+    "ListenStream",
+    "ListenDatagram",
+    "ListenSequentialPacket",
+    "ListenFIFO",
+    "ListenSpecial",
+    "ListenNetlink",
+    "ListenMessageQueue",
+    "ListenUSBFunction",
+];
+
+/// Unit types that only start when something pulls them in or they are enabled
+const STARTABLE_UNIT_TYPES: &[&str] = &[
+    "service",
+    "socket",
+    "timer",
+    "path",
+    "target",
+    "mount",
+    "automount",
+    "swap",
+];
+
+/// `[Unit]` directives that pull the units they name in, contradicted by `Conflicts=`
+const PULLING_DEPENDENCIES: &[&str] = &["Requires", "Requisite", "BindsTo", "Wants", "Upholds"];
 
 #[derive(Debug)]
 pub struct SystemdDiagnostics {
     diagnostics: DashMap<Uri, Vec<Diagnostic>>,
-    valid_sections: HashSet<&'static str>,
-    section_directives: DashMap<&'static str, HashSet<&'static str>>,
+    valid_sections: HashMap<UnitDialect, HashSet<&'static str>>,
+    section_directives: HashMap<UnitDialect, HashMap<&'static str, HashSet<&'static str>>>,
+    directive_versions: HashMap<UnitDialect, HashMap<(&'static str, &'static str), u32>>,
+    target_version: RwLock<Option<u32>>,
+    security_exposure: RwLock<bool>,
+    ordering: OrderingIndex,
+    references: ReferenceIndex,
+    workspace_folders: RwLock<Vec<PathBuf>>,
 }
 
 impl SystemdDiagnostics {
     pub fn new() -> Self {
-        let valid_sections: HashSet<&'static str> =
-            SystemdConstants::valid_sections().iter().cloned().collect();
+        let mut valid_sections = HashMap::new();
+        let mut section_directives = HashMap::new();
+        let mut directive_versions = HashMap::new();
 
-        let section_directives = DashMap::new();
-        for (section, directives) in SystemdConstants::section_directives() {
-            let directive_set: HashSet<&'static str> = directives.iter().cloned().collect();
-            section_directives.insert(section, directive_set);
+        for dialect in UnitDialect::ALL {
+            valid_sections.insert(
+                dialect,
+                SystemdConstants::dialect_valid_sections(dialect)
+                    .into_iter()
+                    .collect::<HashSet<_>>(),
+            );
+
+            let mut dialect_directives = HashMap::new();
+            for (section, directives) in SystemdConstants::dialect_section_directives(dialect) {
+                let directive_set: HashSet<&'static str> = directives.iter().cloned().collect();
+                dialect_directives.insert(section, directive_set);
+            }
+            section_directives.insert(dialect, dialect_directives);
+            directive_versions.insert(dialect, SystemdConstants::directive_versions(dialect));
         }
 
         Self {
             diagnostics: DashMap::new(),
             valid_sections,
             section_directives,
+            directive_versions,
+            target_version: RwLock::new(None),
+            security_exposure: RwLock::new(false),
+            ordering: OrderingIndex::new(),
+            references: ReferenceIndex::new(),
+            workspace_folders: RwLock::new(Vec::new()),
         }
     }
 
-    pub async fn update(&self, uri: &Uri, unit: SystemdUnit) {
+    /// Set the oldest systemd version units have to support. Returns whether it changed.
+    pub fn set_target_version(&self, version: Option<u32>) -> bool {
+        let mut target = self.target_version.write().unwrap();
+        let changed = *target != version;
+        *target = version;
+        changed
+    }
+
+    /// Summarize the sandboxing of services in an informational diagnostic. Returns
+    /// whether the setting changed.
+    pub fn set_security_exposure(&self, enabled: bool) -> bool {
+        let mut exposure = self.security_exposure.write().unwrap();
+        let changed = *exposure != enabled;
+        *exposure = enabled;
+        changed
+    }
+
+    /// Set the folders whose units are warned about when nothing in them starts the unit
+    pub fn set_workspace_folders(&self, folders: Vec<PathBuf>) {
+        *self.workspace_folders.write().unwrap() = folders;
+    }
+
+    /// Record the dependencies of a unit file that is not open, so that cycles through
+    /// it are found in the open ones and the units it names count as referenced
+    pub fn index_unit(&self, uri: &Uri, unit: &SystemdUnit) {
+        self.ordering.update(uri, unit);
+        self.references.update(uri, unit);
+    }
+
+    /// Validate a document. Returns whether its dependencies changed, which may add or
+    /// remove ordering cycles and unreferenced units in other documents.
+    pub async fn update(&self, uri: &Uri, unit: SystemdUnit) -> bool {
         trace!("Updating diagnostics for {:?}", uri);
         let mut diagnostics = Vec::new();
+        let ordering_changed = self.ordering.update(uri, &unit);
+        let references_changed = self.references.update(uri, &unit);
 
         for section in unit.sections.values() {
-            self.validate_section(section, &mut diagnostics);
+            self.validate_section(unit.dialect, section, &mut diagnostics);
+        }
+        if unit.dialect.is_unit() {
+            Self::validate_variables(uri, &unit, &mut diagnostics);
+            Self::validate_contradictions(uri, &unit, &mut diagnostics);
+            Self::validate_install(uri, &unit, &mut diagnostics);
+            Self::validate_instance_specifiers(uri, &unit, &mut diagnostics);
+            self.validate_activation(uri, &unit, &mut diagnostics);
+            Self::validate_mount(uri, &unit, &mut diagnostics);
+            Self::validate_file_name(uri, &unit, &mut diagnostics);
+            diagnostics.extend(self.ordering_cycles(uri));
+            diagnostics.extend(self.unreferenced_unit(uri, &unit));
+            if *self.security_exposure.read().unwrap() && is_service_file(uri) {
+                diagnostics.extend(Self::security_exposure(&unit));
+            }
         }
 
         debug!("Generated {} diagnostics for {:?}", diagnostics.len(), uri);
         self.diagnostics.insert(uri.clone(), diagnostics);
+        ordering_changed || references_changed
     }
 
     pub async fn get_diagnostics(&self, uri: &Uri) -> Vec<Diagnostic> {
@@ -49,20 +220,51 @@ impl SystemdDiagnostics {
             .unwrap_or_default()
     }
 
-    fn validate_section(&self, section: &SystemdSection, diagnostics: &mut Vec<Diagnostic>) {
-        if !self.valid_sections.contains(section.name.as_str()) {
+    fn validate_section(
+        &self,
+        dialect: UnitDialect,
+        section: &SystemdSection,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) {
+        let is_valid_section = self
+            .valid_sections
+            .get(&dialect)
+            .is_some_and(|sections| sections.contains(section.name.as_str()));
+
+        if !is_valid_section {
+            let message = if !dialect.is_unit() {
+                format!(
+                    "Unknown section: [{}] in {}",
+                    section.name,
+                    dialect.description()
+                )
+            } else {
+                format!("Unknown section: [{}]", section.name)
+            };
             diagnostics.push(Diagnostic::new_simple(
                 Range::new(
                     Position::new(section.line_range.0, 0),
                     Position::new(section.line_range.0, section.name.len() as u32 + 2),
                 ),
-                format!("Unknown section: [{}]", section.name),
+                message,
             ));
             return;
         }
 
-        if let Some(valid_directives) = self.section_directives.get(section.name.as_str()) {
+        if let Some(valid_directives) = self
+            .section_directives
+            .get(&dialect)
+            .and_then(|sections| sections.get(section.name.as_str()))
+        {
             for directive in &section.directives {
+                if let Some(deprecated) =
+                    SystemdConstants::deprecated_directive(&section.name, &directive.key)
+                        .filter(|_| dialect.is_unit())
+                {
+                    diagnostics.push(Self::create_deprecation_diagnostic(directive, deprecated));
+                    continue;
+                }
+
                 if !valid_directives.contains(directive.key.as_str()) {
                     diagnostics.push(Diagnostic {
                         range: Range::new(
@@ -83,46 +285,880 @@ impl SystemdDiagnostics {
                     });
                 }
 
-                self.validate_directive_value(section, directive, diagnostics);
+                self.validate_directive_value(dialect, section, directive, diagnostics);
+                self.validate_directive_version(dialect, section, directive, diagnostics);
+            }
+        }
+    }
+
+    /// The overall exposure of a service, on its `[Service]` header
+    fn security_exposure(unit: &SystemdUnit) -> Option<Diagnostic> {
+        let section = unit.sections.get("Service")?;
+        let report = SecurityReport::for_unit(unit)?;
+        let failed = report.failed();
+        let most_exposed: Vec<String> = failed
+            .iter()
+            .take(3)
+            .map(|finding| format!("{}=", finding.check.directive))
+            .collect();
+        let mut message = format!(
+            "Security exposure {:.1} {}: {} of {} hardening checks fail",
+            report.exposure(),
+            report.rating(),
+            failed.len(),
+            report.findings.len()
+        );
+        if !most_exposed.is_empty() {
+            message.push_str(&format!(", most of all {}", most_exposed.join(", ")));
+        }
+
+        Some(Self::create_diagnostic(
+            Range::new(
+                Position::new(section.line_range.0, 0),
+                Position::new(section.line_range.0, section.name.len() as u32 + 2),
+            ),
+            DiagnosticSeverity::INFORMATION,
+            message,
+            Some(SECURITY_EXPOSURE_CODE),
+        ))
+    }
+
+    /// A warning on every `After=` and `Before=` entry of `uri` that closes an ordering
+    /// cycle, linking the entries of the other units in the cycle
+    fn ordering_cycles(&self, uri: &Uri) -> Vec<Diagnostic> {
+        self.ordering
+            .cycles_through(uri)
+            .into_iter()
+            .map(|cycle| {
+                let (first, _) = cycle[0].later_and_earlier();
+                let mut message = format!("Ordering cycle: {}", first);
+                for declaration in &cycle {
+                    let (_, earlier) = declaration.later_and_earlier();
+                    message.push_str(&format!(" starts after {}", earlier));
+                    if earlier != first {
+                        message.push_str(", which");
+                    }
+                }
+                message.push_str(". systemd breaks the cycle at boot by deleting one of the jobs");
+
+                let related: Vec<DiagnosticRelatedInformation> = cycle[1..]
+                    .iter()
+                    .map(|declaration| DiagnosticRelatedInformation {
+                        location: Location::new(declaration.uri.clone(), declaration.range),
+                        message: Self::declaration_label(declaration),
+                    })
+                    .collect();
+
+                Diagnostic {
+                    related_information: (!related.is_empty()).then_some(related),
+                    ..Self::create_diagnostic(
+                        cycle[0].range,
+                        DiagnosticSeverity::WARNING,
+                        message,
+                        None,
+                    )
+                }
+            })
+            .collect()
+    }
+
+    /// E.g. `db.service: Before=web.service`
+    fn declaration_label(declaration: &OrderingDeclaration) -> String {
+        format!(
+            "{}: {}={}",
+            declaration.unit, declaration.directive, declaration.target
+        )
+    }
+
+    /// Flag units that both pull in and conflict with a unit, or order themselves both
+    /// before and after it. The later entry is flagged, linking the earlier one.
+    fn validate_contradictions(uri: &Uri, unit: &SystemdUnit, diagnostics: &mut Vec<Diagnostic>) {
+        let Some(section) = unit.sections.get("Unit") else {
+            return;
+        };
+
+        let mut entries: Vec<(&str, String, Range)> = Vec::new();
+        for directive in &section.directives {
+            let words = graph::value_words(unit, directive);
+            if words.is_empty() {
+                entries.retain(|(key, _, _)| *key != directive.key);
+                continue;
+            }
+            for (name, range) in words {
+                let contradicted = entries.iter().find(|(key, other, _)| {
+                    *other == name
+                        && match directive.key.as_str() {
+                            "Conflicts" => PULLING_DEPENDENCIES.contains(key),
+                            "After" => *key == "Before",
+                            "Before" => *key == "After",
+                            pulling if PULLING_DEPENDENCIES.contains(&pulling) => {
+                                *key == "Conflicts"
+                            }
+                            _ => false,
+                        }
+                });
+                if let Some((key, _, earlier)) = contradicted {
+                    let consequence = if matches!(directive.key.as_str(), "After" | "Before") {
+                        format!("the unit cannot start both before and after {}", name)
+                    } else {
+                        format!("starting the unit both pulls in and stops {}", name)
+                    };
+                    diagnostics.push(Diagnostic {
+                        related_information: Some(vec![DiagnosticRelatedInformation {
+                            location: Location::new(uri.clone(), *earlier),
+                            message: format!("{}={}", key, name),
+                        }]),
+                        ..Self::create_diagnostic(
+                            range,
+                            DiagnosticSeverity::ERROR,
+                            format!(
+                                "{}={} contradicts {}={}: {}",
+                                directive.key, name, key, name, consequence
+                            ),
+                            None,
+                        )
+                    });
+                }
+                if let Some(key) = ["After", "Before", "Conflicts"]
+                    .iter()
+                    .chain(PULLING_DEPENDENCIES)
+                    .find(|key| **key == directive.key)
+                {
+                    entries.push((key, name, range));
+                }
+            }
+        }
+    }
+
+    /// Check the names `systemctl enable` links the unit under: `Alias=` has to keep the
+    /// unit type and template, and `DefaultInstance=` only means something for templates
+    /// and has to make a valid name of them
+    fn validate_install(uri: &Uri, unit: &SystemdUnit, diagnostics: &mut Vec<Diagnostic>) {
+        let (Some(section), Some(name)) = (unit.sections.get("Install"), graph::unit_name(uri))
+        else {
+            return;
+        };
+
+        for directive in &section.directives {
+            let errors: Vec<(Range, String)> = match directive.key.as_str() {
+                "Alias" => graph::value_words(unit, directive)
+                    .into_iter()
+                    .filter(|(alias, _)| !alias.contains('%'))
+                    .filter_map(|(alias, range)| {
+                        let error = install::alias_error(&name, &alias)?;
+                        Some((range, format!("Alias={}: {}", alias, error)))
+                    })
+                    .collect(),
+                "DefaultInstance" if !unit_name::is_template(&name) => vec![(
+                    Range::new(
+                        Position::new(directive.line_number, directive.column_range.0),
+                        Position::new(directive.line_number, directive.column_range.1),
+                    ),
+                    format!(
+                        "DefaultInstance= only applies to templates such as {}, {} is not one",
+                        unit_name::template_of(&name),
+                        name
+                    ),
+                )],
+                "DefaultInstance" => {
+                    let instance = directive.value.trim();
+                    let words = graph::value_words(unit, directive);
+                    match (words.first(), words.last()) {
+                        (Some((_, first)), Some((_, last))) if !instance.contains('%') => {
+                            unit_name::unit_name_error(&unit_name::instantiate(&name, instance))
+                                .map(|error| {
+                                    (
+                                        Range::new(first.start, last.end),
+                                        format!("DefaultInstance={}: {}", instance, error),
+                                    )
+                                })
+                                .into_iter()
+                                .collect()
+                        }
+                        _ => Vec::new(),
+                    }
+                }
+                _ => Vec::new(),
+            };
+            for (range, message) in errors {
+                diagnostics.push(Self::create_diagnostic(
+                    range,
+                    DiagnosticSeverity::ERROR,
+                    message,
+                    None,
+                ));
+            }
+        }
+    }
+
+    /// Check that a timer has a trigger, a path unit a path to watch and a socket an
+    /// address to listen on, which systemd refuses to load them without, and that the
+    /// unit they activate exists: the one named by `Unit=` or `Service=`, or else the
+    /// service of the same name, a template for sockets with `Accept=yes`
+    fn validate_activation(
+        &self,
+        uri: &Uri,
+        unit: &SystemdUnit,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) {
+        let Some(name) = graph::unit_name(uri).filter(|_| !is_drop_in(uri)) else {
+            return;
+        };
+        let Some((stem, suffix)) = name.rsplit_once('.') else {
+            return;
+        };
+        let (section_name, settings, kind, key, service_template) = match suffix {
+            "timer" => (
+                "Timer",
+                TIMER_TRIGGERS,
+                "trigger",
+                "Unit",
+                "oneshot-service",
+            ),
+            "path" => (
+                "Path",
+                PATH_CONDITIONS,
+                "path to watch",
+                "Unit",
+                "oneshot-service",
+            ),
+            "socket" => (
+                "Socket",
+                LISTEN_SETTINGS,
+                "address to listen on",
+                "Service",
+                "socket-activated-service",
+            ),
+            _ => return,
+        };
+        let section = unit.sections.get(section_name);
+        let header = section
+            .map(|section| section.line_range.0)
+            .or_else(|| {
+                unit.sections
+                    .values()
+                    .map(|section| section.line_range.0)
+                    .min()
+            })
+            .unwrap_or(0);
+        let header_width = unit
+            .raw_text
+            .lines()
+            .nth(header as usize)
+            .map_or(0, |line| line.chars().count() as u32);
+        let header_range = Range::new(
+            Position::new(header, 0),
+            Position::new(header, header_width),
+        );
+
+        // An empty assignment to any of them resets the list
+        let mut count = 0;
+        for directive in section.iter().flat_map(|section| &section.directives) {
+            if !settings.contains(&directive.key.as_str()) {
+                continue;
+            }
+            let value = directive.value.trim();
+            if value.is_empty() {
+                count = 0;
+            } else if !matches!(directive.key.as_str(), "OnClockChange" | "OnTimezoneChange")
+                || validators::parse_boolean(value) == Some(true)
+            {
+                count += 1;
+            }
+        }
+        if count == 0 {
+            let names: Vec<String> = settings
+                .iter()
+                .map(|setting| format!("{}=", setting))
+                .collect();
+            diagnostics.push(Self::create_diagnostic(
+                header_range,
+                DiagnosticSeverity::WARNING,
+                format!(
+                    "{} has no {}, systemd refuses to load it without one of {} or {}",
+                    name,
+                    kind,
+                    names[..names.len() - 1].join(", "),
+                    names[names.len() - 1]
+                ),
+                None,
+            ));
+        }
+
+        let last = |key: &str| {
+            section.and_then(|section| section.directives.iter().rev().find(|d| d.key == key))
+        };
+        let explicit = last(key).and_then(|directive| graph::value_words(unit, directive).pop());
+        let accept = last("Accept")
+            .filter(|_| suffix == "socket")
+            .filter(|directive| validators::parse_boolean(directive.value.trim()) == Some(true));
+        let error = |range: Range, message: String| {
+            Self::create_diagnostic(range, DiagnosticSeverity::ERROR, message, None)
+        };
+
+        let (activated, range) = match (explicit, accept) {
+            // Like socket_verify(): each connection gets an instance of the template
+            (Some((_, range)), Some(_)) => {
+                diagnostics.push(error(
+                    range,
+                    format!(
+                        "Service= cannot be combined with Accept=yes, each connection starts an instance of {}@.service",
+                        stem
+                    ),
+                ));
+                return;
+            }
+            (Some((activated, range)), None) => {
+                if suffix == "socket"
+                    && !activated.contains('%')
+                    && !activated.ends_with(".service")
+                {
+                    diagnostics.push(error(
+                        range,
+                        format!("Service= must name a service, {} is not one", activated),
+                    ));
+                    return;
+                }
+                (activated, range)
+            }
+            (None, Some(_)) => (format!("{}@.service", stem), header_range),
+            (None, None) => (format!("{}.service", stem), header_range),
+        };
+        if activated.contains('%') || !value_providers::is_unit_name(&activated) {
+            return;
+        }
+        let near = uri
+            .to_file_path()
+            .and_then(|path| path.parent().map(|parent| parent.to_path_buf()));
+        let exists = |name: &str| {
+            self.references.defines(name)
+                || unit_name::find_unit_file(name, near.as_deref()).is_some()
+        };
+        if exists(&activated) {
+            return;
+        }
+
+        // Only the other kind of service exists, so Accept= is likely what is wrong
+        let warning = |range: Range, message: String| {
+            Self::create_diagnostic(range, DiagnosticSeverity::WARNING, message, None)
+        };
+        let single = format!("{}.service", stem);
+        let template = format!("{}@.service", stem);
+        if let Some(accept) = accept.filter(|_| exists(&single)) {
+            let range = graph::value_words(unit, accept)
+                .pop()
+                .map_or(header_range, |(_, range)| range);
+            diagnostics.push(warning(
+                range,
+                format!(
+                    "Accept=yes starts an instance of {} for each connection, but only {} exists",
+                    template, single
+                ),
+            ));
+            return;
+        }
+        if suffix == "socket" && activated == single && exists(&template) {
+            diagnostics.push(warning(
+                range,
+                format!(
+                    "{} activates {}, but only the template {} exists: set Accept=yes to start an instance of it for each connection",
+                    name, single, template
+                ),
+            ));
+            return;
+        }
+
+        diagnostics.push(Diagnostic {
+            data: Some(serde_json::json!({
+                "unit": activated,
+                "template": service_template,
+            })),
+            ..Self::create_diagnostic(
+                range,
+                DiagnosticSeverity::WARNING,
+                format!(
+                    "{} activates {}, which is neither in the workspace nor next to it",
+                    name, activated
+                ),
+                Some(MISSING_ACTIVATED_UNIT_CODE),
+            )
+        });
+    }
+
+    /// Warn about unit files whose name systemd does not take as a unit name, such as
+    /// `my app.service`, since it skips them when loading units
+    fn validate_file_name(uri: &Uri, unit: &SystemdUnit, diagnostics: &mut Vec<Diagnostic>) {
+        let Some(error) = graph::unit_name(uri)
+            .filter(|_| !is_drop_in(uri))
+            .and_then(|name| unit_name::unit_name_error(&name))
+        else {
+            return;
+        };
+        let width = unit
+            .raw_text
+            .lines()
+            .next()
+            .map_or(0, |line| line.chars().count() as u32);
+        diagnostics.push(Self::create_diagnostic(
+            Range::new(Position::new(0, 0), Position::new(0, width)),
+            DiagnosticSeverity::WARNING,
+            format!("systemd does not load this file: {}", error),
+            None,
+        ));
+    }
+
+    /// Check that a mount or automount unit is named after its `Where=` path, which
+    /// systemd refuses to load it otherwise, that a mount unit has a `What=` of the form
+    /// its `Type=` expects, and that its `Options=` do not undo each other
+    fn validate_mount(uri: &Uri, unit: &SystemdUnit, diagnostics: &mut Vec<Diagnostic>) {
+        let Some(name) = graph::unit_name(uri).filter(|_| !is_drop_in(uri)) else {
+            return;
+        };
+        let Some((stem, suffix)) = name.rsplit_once('.') else {
+            return;
+        };
+        let section_name = match suffix {
+            "mount" => "Mount",
+            "automount" => "Automount",
+            _ => return,
+        };
+        let Some(section) = unit.sections.get(section_name) else {
+            return;
+        };
+        let last = |key: &str| {
+            section
+                .directives
+                .iter()
+                .rev()
+                .find(|d| d.key == key)
+                .filter(|d| !d.value.trim().is_empty() && !d.value.contains('%'))
+        };
+        let value_range = |directive: &SystemdDirective| {
+            let words = graph::value_words(unit, directive);
+            match (words.first(), words.last()) {
+                (Some((_, first)), Some((_, last))) => Range::new(first.start, last.end),
+                _ => Range::new(
+                    Position::new(directive.line_number, directive.column_range.0),
+                    Position::new(directive.line_number, directive.column_range.1),
+                ),
+            }
+        };
+        let diagnostic = |range: Range, severity: DiagnosticSeverity, message: String| {
+            Self::create_diagnostic(range, severity, message, None)
+        };
+
+        if let Some(directive) = last("Where").filter(|d| d.value.trim().starts_with('/')) {
+            let path = directive.value.trim();
+            match unit_name::escape_path(path) {
+                None => diagnostics.push(diagnostic(
+                    value_range(directive),
+                    DiagnosticSeverity::ERROR,
+                    format!(
+                        "Where={} has a .. component, systemd needs a normalized path to name the unit after",
+                        path
+                    ),
+                )),
+                Some(escaped) if format!("{}.{}", escaped, suffix) != name => {
+                    let expected = format!("{}.{}", escaped, suffix);
+                    diagnostics.push(Diagnostic {
+                        data: Some(serde_json::json!({
+                            "name": expected,
+                            "where": unit_name::unescape_path(stem),
+                        })),
+                        ..Self::create_diagnostic(
+                            value_range(directive),
+                            DiagnosticSeverity::ERROR,
+                            format!(
+                                "{} units are named after the path they mount on: Where={} needs {}, not {}",
+                                section_name, path, expected, name
+                            ),
+                            Some(MOUNT_NAME_CODE),
+                        )
+                    });
+                }
+                Some(_) => {}
+            }
+        }
+
+        if suffix != "mount" {
+            return;
+        }
+        if let Some(directive) = last("Options") {
+            let options: Vec<&str> = directive.value.split(',').map(str::trim).collect();
+            for (flag, opposite) in OPPOSITE_MOUNT_FLAGS {
+                let (Some(flag_at), Some(opposite_at)) = (
+                    options.iter().rposition(|option| option == flag),
+                    options.iter().rposition(|option| option == opposite),
+                ) else {
+                    continue;
+                };
+                let (first, winner) = if flag_at > opposite_at {
+                    (opposite, flag)
+                } else {
+                    (flag, opposite)
+                };
+                diagnostics.push(diagnostic(
+                    value_range(directive),
+                    DiagnosticSeverity::WARNING,
+                    format!(
+                        "Options= has both {} and {}, only the last one, {}, takes effect",
+                        first, winner, winner
+                    ),
+                ));
+            }
+        }
+        let Some(what) = last("What") else {
+            let what = section.directives.iter().rev().find(|d| d.key == "What");
+            if what.is_none_or(|what| what.value.trim().is_empty()) {
+                let header = section.line_range.0;
+                let width = unit
+                    .raw_text
+                    .lines()
+                    .nth(header as usize)
+                    .map_or(0, |line| line.chars().count() as u32);
+                diagnostics.push(diagnostic(
+                    Range::new(Position::new(header, 0), Position::new(header, width)),
+                    DiagnosticSeverity::ERROR,
+                    format!(
+                        "{} has no What=, systemd refuses to load it without something to mount",
+                        name
+                    ),
+                ));
+            }
+            return;
+        };
+        let source = what.value.trim();
+        let Some(file_system) = last("Type").map(|d| d.value.trim()) else {
+            return;
+        };
+        let expected = if BLOCK_FILE_SYSTEMS.contains(&file_system) {
+            let tagged = ["LABEL=", "UUID=", "PARTUUID=", "PARTLABEL="]
+                .iter()
+                .any(|tag| source.starts_with(tag));
+            (!source.starts_with('/') && !tagged).then_some((
+                "a block device or image file",
+                "an absolute path or LABEL=, UUID=, PARTUUID= or PARTLABEL=",
+            ))
+        } else {
+            match file_system {
+                "nfs" | "nfs4" | "sshfs" => {
+                    (!source.contains(':')).then_some(("a remote directory", "host:/path"))
+                }
+                "cifs" => (!source.starts_with("//")).then_some(("a share", "//server/share")),
+                "bind" => {
+                    (!source.starts_with('/')).then_some(("a directory", "its absolute path"))
+                }
+                _ => None,
+            }
+        };
+        if let Some((kind, form)) = expected {
+            diagnostics.push(diagnostic(
+                value_range(what),
+                DiagnosticSeverity::WARNING,
+                format!(
+                    "Type={} mounts {}, What= should be {}",
+                    file_system, kind, form
+                ),
+            ));
+        }
+    }
+
+    /// Warn about `%i` and `%I` in units that are neither templates nor instances, where
+    /// they expand to an empty string
+    fn validate_instance_specifiers(
+        uri: &Uri,
+        unit: &SystemdUnit,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) {
+        let Some(name) = graph::unit_name(uri).filter(|name| !name.contains('@')) else {
+            return;
+        };
+
+        let lines: Vec<&str> = unit.raw_text.lines().collect();
+        for directive in unit
+            .sections
+            .values()
+            .flat_map(|section| &section.directives)
+        {
+            for span in &directive.value_spans {
+                let Some(line) = lines.get(span.line as usize) else {
+                    continue;
+                };
+                let chars: Vec<char> = line.chars().collect();
+                let end = (span.end as usize).min(chars.len());
+                let mut index = (span.start as usize).min(end);
+                while index + 1 < end {
+                    if chars[index] != '%' {
+                        index += 1;
+                        continue;
+                    }
+                    // `%%` is a literal percent sign, skipped along with the specifier
+                    let specifier = chars[index + 1];
+                    if matches!(specifier, 'i' | 'I') {
+                        diagnostics.push(Self::create_diagnostic(
+                            Range::new(
+                                Position::new(span.line, index as u32),
+                                Position::new(span.line, index as u32 + 2),
+                            ),
+                            DiagnosticSeverity::WARNING,
+                            format!(
+                                "%{} expands to an empty string: {} is not a template such as {}",
+                                specifier,
+                                name,
+                                unit_name::template_of(&name)
+                            ),
+                            None,
+                        ));
+                    }
+                    index += 2;
+                }
+            }
+        }
+    }
+
+    /// A warning on the first section of a workspace unit that has no `[Install]` section
+    /// and that no other unit of the workspace names, so nothing but an explicit
+    /// `systemctl start` runs it. D-Bus activated services are left out.
+    fn unreferenced_unit(&self, uri: &Uri, unit: &SystemdUnit) -> Option<Diagnostic> {
+        let path = uri.to_file_path()?;
+        let in_workspace = self
+            .workspace_folders
+            .read()
+            .unwrap()
+            .iter()
+            .any(|folder| path.starts_with(folder));
+        if !in_workspace || is_drop_in(uri) || unit.sections.contains_key("Install") {
+            return None;
+        }
+        let name = graph::unit_name(uri)?;
+        let (_, suffix) = name.rsplit_once('.')?;
+        let bus_activated = unit
+            .sections
+            .get("Service")
+            .is_some_and(|section| section.directives.iter().any(|d| d.key == "BusName"));
+        if !STARTABLE_UNIT_TYPES.contains(&suffix)
+            || bus_activated
+            || self.references.is_referenced(&name, uri)
+        {
+            return None;
+        }
+
+        let line = unit
+            .sections
+            .values()
+            .map(|section| section.line_range.0)
+            .min()?;
+        let width = unit.raw_text.lines().nth(line as usize)?.chars().count() as u32;
+        Some(Self::create_diagnostic(
+            Range::new(Position::new(line, 0), Position::new(line, width)),
+            DiagnosticSeverity::WARNING,
+            format!(
+                "Nothing starts {}: it has no [Install] section to enable it with and no unit in the workspace depends on it",
+                name
+            ),
+            None,
+        ))
+    }
+
+    /// Warn about `$VARIABLE` references in command lines that nothing in the unit
+    /// defines. Drop-ins are skipped, their variables usually come from the unit they extend.
+    fn validate_variables(uri: &Uri, unit: &SystemdUnit, diagnostics: &mut Vec<Diagnostic>) {
+        if is_drop_in(uri) {
+            return;
+        }
+        let unit_path = uri.to_file_path();
+
+        let lines: Vec<&str> = unit.raw_text.lines().collect();
+        for section in unit.sections.values() {
+            let mut environment = None;
+            for directive in &section.directives {
+                let is_command = schema::lookup(unit.dialect, &section.name, &directive.key)
+                    .is_some_and(|entry| entry.value_type == ValueType::Command);
+                if !is_command {
+                    continue;
+                }
+
+                for span in &directive.value_spans {
+                    let Some(line) = lines.get(span.line as usize) else {
+                        continue;
+                    };
+                    let text: String = line
+                        .chars()
+                        .skip(span.start as usize)
+                        .take(span.end.saturating_sub(span.start) as usize)
+                        .collect();
+                    for reference in environment::references(&text) {
+                        let environment = environment.get_or_insert_with(|| {
+                            SectionEnvironment::new(unit, section, unit_path.as_deref())
+                        });
+                        if environment.is_defined(&reference.name) {
+                            continue;
+                        }
+                        diagnostics.push(Self::create_diagnostic(
+                            Range::new(
+                                Position::new(span.line, span.start + reference.start as u32),
+                                Position::new(span.line, span.start + reference.end as u32),
+                            ),
+                            DiagnosticSeverity::WARNING,
+                            format!(
+                                "${} is not defined by Environment= or EnvironmentFile= and expands to an empty string, use $${} for a shell variable",
+                                reference.name, reference.name
+                            ),
+                            None,
+                        ));
+                    }
+                }
             }
         }
     }
 
     fn validate_directive_value(
         &self,
+        dialect: UnitDialect,
         section: &SystemdSection,
         directive: &crate::parser::SystemdDirective,
         diagnostics: &mut Vec<Diagnostic>,
     ) {
-        if section.name == "Service" && directive.key == "ExecStart" && directive.value.is_empty() {
+        if dialect == UnitDialect::Systemd
+            && section.name == "Service"
+            && directive.key == "ExecStart"
+            && directive.value.is_empty()
+        {
             diagnostics.push(
                 self.create_value_diagnostic(directive, "ExecStart cannot be empty".to_string()),
             );
             return;
         }
 
-        if let Some(values) =
-            SystemdConstants::valid_values_for_section(&section.name, &directive.key)
-        {
-            let value = directive.value.as_str();
-            let is_valid = match directive.key.as_str() {
-                "StandardOutput" | "StandardError" => {
-                    values.iter().any(|&v| value == v || value.starts_with(v))
-                }
-                _ => values.contains(&value),
-            };
+        let Some(entry) = schema::lookup(dialect, &section.name, &directive.key) else {
+            return;
+        };
+        let value = directive.value.as_str();
 
-            if !is_valid {
+        if entry.value_type == ValueType::Enum {
+            if !entry.is_listed_value(value) {
                 diagnostics.push(self.create_value_diagnostic(
                     directive,
                     format!(
                         "Invalid {} value '{}'. Valid values: {}",
                         directive.key,
                         directive.value,
-                        values.join(", ")
+                        entry.values.join(", ")
                     ),
                 ));
             }
+            return;
+        }
+
+        // An empty assignment resets the directive to its default
+        if value.is_empty() || entry.is_listed_value(value) {
+            return;
+        }
+        if let Err(reason) = validators::validate(entry, value) {
+            diagnostics.push(self.create_value_diagnostic(
+                directive,
+                format!(
+                    "Invalid {} value '{}': {}",
+                    directive.key, directive.value, reason
+                ),
+            ));
+        }
+    }
+
+    /// Warn about directives and values that are newer than the target systemd version
+    fn validate_directive_version(
+        &self,
+        dialect: UnitDialect,
+        section: &SystemdSection,
+        directive: &crate::parser::SystemdDirective,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) {
+        let Some(target) = *self.target_version.read().unwrap() else {
+            return;
+        };
+
+        let directive_version = self
+            .directive_versions
+            .get(&dialect)
+            .and_then(|versions| versions.get(&(section.name.as_str(), directive.key.as_str())))
+            .copied();
+        if let Some(version) = directive_version.filter(|version| *version > target) {
+            diagnostics.push(Self::create_diagnostic(
+                Range::new(
+                    Position::new(directive.line_number, directive.column_range.0),
+                    Position::new(directive.line_number, directive.column_range.1),
+                ),
+                DiagnosticSeverity::WARNING,
+                format!(
+                    "{}= requires systemd {}, but the target version is {}",
+                    directive.key, version, target
+                ),
+                Some(REQUIRES_NEWER_SYSTEMD_CODE),
+            ));
+            return;
+        }
+
+        if !dialect.is_unit() {
+            return;
+        }
+        if let Some(version) =
+            SystemdConstants::value_version(&section.name, &directive.key, &directive.value)
+                .filter(|version| *version > target)
+        {
+            let mut diagnostic = self.create_value_diagnostic(
+                directive,
+                format!(
+                    "{}={} requires systemd {}, but the target version is {}",
+                    directive.key, directive.value, version, target
+                ),
+            );
+            diagnostic.severity = Some(DiagnosticSeverity::WARNING);
+            diagnostic.code = Some(NumberOrString::String(
+                REQUIRES_NEWER_SYSTEMD_CODE.to_string(),
+            ));
+            diagnostics.push(diagnostic);
+        }
+    }
+
+    /// A diagnostic of this server; `code` lets code actions recognise it
+    fn create_diagnostic(
+        range: Range,
+        severity: DiagnosticSeverity,
+        message: String,
+        code: Option<&str>,
+    ) -> Diagnostic {
+        Diagnostic {
+            range,
+            severity: Some(severity),
+            code: code.map(|code| NumberOrString::String(code.to_string())),
+            code_description: None,
+            source: Some(SystemdConstants::APP_NAME.to_string()),
+            message,
+            related_information: None,
+            tags: None,
+            data: None,
+        }
+    }
+
+    fn create_deprecation_diagnostic(
+        directive: &crate::parser::SystemdDirective,
+        deprecated: &DirectiveSchema,
+    ) -> Diagnostic {
+        Diagnostic {
+            range: Range::new(
+                Position::new(directive.line_number, directive.column_range.0),
+                Position::new(directive.line_number, directive.column_range.1),
+            ),
+            severity: Some(DiagnosticSeverity::WARNING),
+            code: Some(NumberOrString::String(
+                DEPRECATED_DIRECTIVE_CODE.to_string(),
+            )),
+            code_description: None,
+            source: Some(SystemdConstants::APP_NAME.to_string()),
+            message: deprecated.deprecation_message().unwrap_or_default(),
+            related_information: None,
+            tags: Some(vec![DiagnosticTag::DEPRECATED]),
+            data: None,
         }
     }
 
@@ -165,6 +1201,19 @@ impl SystemdDiagnostics {
     }
 }
 
+/// Whether a file lives in a drop-in directory such as `app.service.d/`
+fn is_drop_in(uri: &Uri) -> bool {
+    uri.to_file_path()
+        .as_deref()
+        .and_then(|path| path.parent()?.file_name()?.to_str())
+        .is_some_and(|directory| directory.ends_with(".d"))
+}
+
+/// A complete `.service` unit, as opposed to a drop-in or a Quadlet file
+pub fn is_service_file(uri: &Uri) -> bool {
+    uri.path().as_str().ends_with(".service") && !is_drop_in(uri)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -211,6 +1260,7 @@ mod tests {
         SystemdUnit {
             sections: unit_sections,
             raw_text: String::new(),
+            dialect: UnitDialect::Systemd,
         }
     }
 
@@ -334,4 +1384,894 @@ mod tests {
         assert_eq!(result1.len(), 1);
         assert_eq!(result2.len(), 0);
     }
+
+    #[tokio::test]
+    async fn test_networkd_network_sections_are_valid() {
+        let diagnostics = SystemdDiagnostics::new();
+        let uri = "file:///etc/systemd/network/lan.network"
+            .parse::<Uri>()
+            .unwrap();
+
+        let mut unit = create_test_unit(vec![
+            ("Match", vec![("Name", "eth0")]),
+            (
+                "Network",
+                vec![("DHCP", "ipv4"), ("Address", "10.0.0.2/24")],
+            ),
+            ("DHCPv4", vec![("UseDNS", "yes")]),
+        ]);
+        unit.dialect = UnitDialect::NetworkdNetwork;
+
+        diagnostics.update(&uri, unit).await;
+        assert!(diagnostics.get_diagnostics(&uri).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_networkd_rejects_quadlet_directives() {
+        let diagnostics = SystemdDiagnostics::new();
+        let uri = "file:///etc/systemd/network/lan.network"
+            .parse::<Uri>()
+            .unwrap();
+
+        let mut unit = create_test_unit(vec![
+            ("Network", vec![("NetworkName", "app"), ("DHCP", "maybe")]),
+            ("Service", vec![("Type", "simple")]),
+        ]);
+        unit.dialect = UnitDialect::NetworkdNetwork;
+
+        diagnostics.update(&uri, unit).await;
+        let result = diagnostics.get_diagnostics(&uri).await;
+
+        assert!(result
+            .iter()
+            .any(|d| d.message == "Unknown directive 'NetworkName' in [Network] section"));
+        assert!(result
+            .iter()
+            .any(|d| d.message.starts_with("Invalid DHCP value 'maybe'")));
+        assert!(result
+            .iter()
+            .any(|d| d.message == "Unknown section: [Service] in systemd-networkd .network file"));
+    }
+
+    #[tokio::test]
+    async fn test_journald_conf_validation() {
+        let diagnostics = SystemdDiagnostics::new();
+        let uri = "file:///etc/systemd/journald.conf.d/50-storage.conf"
+            .parse::<Uri>()
+            .unwrap();
+
+        let mut unit = create_test_unit(vec![(
+            "Journal",
+            vec![
+                ("Storage", "persistent"),
+                ("SystemMaxUse", "1G"),
+                ("MaxLevelStore", "warning"),
+                ("Compress", "sometimes"),
+                ("SplitMode", "login"),
+            ],
+        )]);
+        unit.dialect = UnitDialect::Journald;
+
+        diagnostics.update(&uri, unit).await;
+        let result = diagnostics.get_diagnostics(&uri).await;
+
+        assert_eq!(result.len(), 1, "{:?}", result);
+        assert!(result[0]
+            .message
+            .starts_with("Invalid SplitMode value 'login'"));
+    }
+
+    #[tokio::test]
+    async fn test_target_version_warnings() {
+        let diagnostics = SystemdDiagnostics::new();
+        let uri = "file:///app.service".parse::<Uri>().unwrap();
+        let unit = create_test_unit(vec![(
+            "Service",
+            vec![
+                ("Type", "notify-reload"),
+                ("ExecStart", "/usr/bin/app"),
+                ("ExecCondition", "/usr/bin/check"),
+                ("ProtectControlGroups", "yes"),
+            ],
+        )]);
+
+        diagnostics.update(&uri, unit.clone()).await;
+        assert!(diagnostics.get_diagnostics(&uri).await.is_empty());
+
+        assert!(diagnostics.set_target_version(Some(239)));
+        assert!(!diagnostics.set_target_version(Some(239)));
+        diagnostics.update(&uri, unit).await;
+        let result = diagnostics.get_diagnostics(&uri).await;
+
+        let mut messages: Vec<_> = result.iter().map(|d| d.message.as_str()).collect();
+        messages.sort();
+        assert_eq!(
+            messages,
+            vec![
+                "ExecCondition= requires systemd 243, but the target version is 239",
+                "Type=notify-reload requires systemd 253, but the target version is 239",
+            ]
+        );
+        assert!(result
+            .iter()
+            .all(|d| d.severity == Some(DiagnosticSeverity::WARNING)));
+    }
+
+    #[tokio::test]
+    async fn test_deprecated_directives_are_tagged() {
+        let diagnostics = SystemdDiagnostics::new();
+        let uri = "file:///legacy.service".parse::<Uri>().unwrap();
+
+        let unit = create_test_unit(vec![
+            ("Unit", vec![("Description", "Legacy")]),
+            (
+                "Service",
+                vec![
+                    ("ExecStart", "/usr/bin/legacy"),
+                    ("MemoryLimit", "1G"),
+                    ("StartLimitInterval", "10s"),
+                ],
+            ),
+        ]);
+
+        diagnostics.update(&uri, unit).await;
+        let result = diagnostics.get_diagnostics(&uri).await;
+
+        assert_eq!(result.len(), 2, "{:?}", result);
+        for diagnostic in &result {
+            assert_eq!(diagnostic.tags, Some(vec![DiagnosticTag::DEPRECATED]));
+            assert_eq!(
+                diagnostic.code,
+                Some(NumberOrString::String(
+                    DEPRECATED_DIRECTIVE_CODE.to_string()
+                ))
+            );
+        }
+        assert!(result
+            .iter()
+            .any(|d| d.message == "MemoryLimit= is deprecated, use MemoryMax= instead"));
+        assert!(result.iter().any(|d| d.message
+            == "StartLimitInterval= in [Service] is deprecated, use StartLimitIntervalSec= in [Unit] instead"));
+    }
+
+    #[tokio::test]
+    async fn test_typed_value_validation() {
+        let diagnostics = SystemdDiagnostics::new();
+        let uri = "file:///typed.service".parse::<Uri>().unwrap();
+
+        let unit = create_test_unit(vec![(
+            "Service",
+            vec![
+                ("ExecStart", "/usr/bin/app"),
+                ("MemoryMax", "512MB"),
+                ("MemoryHigh", "400M"),
+                ("NoNewPrivileges", "on"),
+                ("PrivateTmp", "disconnected"),
+                ("CPUQuota", "200%"),
+                ("WorkingDirectory", "srv/app"),
+                ("UMask", "0027"),
+                ("CPUAffinity", "0-3,8"),
+                ("KillSignal", "SIGTERM"),
+                ("SuccessExitStatus", "0 143 SIGKILL"),
+                ("TimeoutStopSec", ""),
+            ],
+        )]);
+
+        diagnostics.update(&uri, unit).await;
+        let mut messages: Vec<String> = diagnostics
+            .get_diagnostics(&uri)
+            .await
+            .into_iter()
+            .map(|diagnostic| diagnostic.message)
+            .collect();
+        messages.sort();
+
+        assert_eq!(messages.len(), 2, "{:?}", messages);
+        assert!(messages[0].starts_with("Invalid MemoryMax value '512MB'"));
+        assert_eq!(
+            messages[1],
+            "Invalid WorkingDirectory value 'srv/app': 'srv/app' is not an absolute path"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_shared_directives_follow_section() {
+        let diagnostics = SystemdDiagnostics::new();
+        let uri = "file:///shared.socket".parse::<Uri>().unwrap();
+
+        let unit = create_test_unit(vec![
+            (
+                "Socket",
+                vec![
+                    ("ListenStream", "80"),
+                    ("User", "www"),
+                    ("KillMode", "process"),
+                    ("MemoryMax", "1G"),
+                ],
+            ),
+            ("Slice", vec![("MemoryMax", "1G"), ("KillMode", "process")]),
+            ("Scope", vec![("KillSignal", "SIGINT"), ("User", "www")]),
+        ]);
+
+        diagnostics.update(&uri, unit).await;
+        let mut messages: Vec<String> = diagnostics
+            .get_diagnostics(&uri)
+            .await
+            .into_iter()
+            .map(|diagnostic| diagnostic.message)
+            .collect();
+        messages.sort();
+
+        assert_eq!(
+            messages,
+            [
+                "Unknown directive 'KillMode' in [Slice] section",
+                "Unknown directive 'User' in [Scope] section",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ordering_cycles_across_units() {
+        let diagnostics = SystemdDiagnostics::new();
+        let parser = crate::parser::SystemdParser::new();
+        let web = "file:///srv/units/web.service".parse::<Uri>().unwrap();
+        let db = "file:///srv/units/db.service".parse::<Uri>().unwrap();
+        let cache = "file:///srv/units/cache.service.d/order.conf"
+            .parse::<Uri>()
+            .unwrap();
+
+        diagnostics.index_unit(&db, &parser.parse("[Unit]\nAfter=cache.service\n"));
+        diagnostics.index_unit(&cache, &parser.parse("[Unit]\nBefore=network.target\n"));
+        assert!(
+            diagnostics
+                .update(
+                    &web,
+                    parser.parse("[Unit]\nAfter=network.target db.service\n")
+                )
+                .await
+        );
+        assert!(diagnostics.get_diagnostics(&web).await.is_empty());
+
+        // The drop-in of cache.service now orders it after web.service
+        assert!(
+            diagnostics
+                .update(&cache, parser.parse("[Unit]\nAfter=web.service\n"))
+                .await
+        );
+        diagnostics
+            .update(
+                &web,
+                parser.parse("[Unit]\nAfter=network.target db.service\n"),
+            )
+            .await;
+        let found = diagnostics.get_diagnostics(&web).await;
+        assert_eq!(found.len(), 1, "{:?}", found);
+        assert_eq!(
+            found[0].range,
+            Range::new(Position::new(1, 21), Position::new(1, 31))
+        );
+        assert_eq!(
+            found[0].message,
+            "Ordering cycle: web.service starts after db.service, which starts after cache.service, which starts after web.service. systemd breaks the cycle at boot by deleting one of the jobs"
+        );
+        let related = found[0].related_information.as_ref().unwrap();
+        assert_eq!(related[0].location.uri, db);
+        assert_eq!(related[0].message, "db.service: After=cache.service");
+        assert_eq!(related[1].location.uri, cache);
+        assert_eq!(related[1].message, "cache.service: After=web.service");
+
+        // Reordering without touching the dependencies leaves other units alone
+        assert!(
+            !diagnostics
+                .update(
+                    &web,
+                    parser.parse("[Unit]\nAfter=db.service network.target\n")
+                )
+                .await
+        );
+    }
+
+    #[tokio::test]
+    async fn test_contradictory_dependencies() {
+        let diagnostics = SystemdDiagnostics::new();
+        let uri = "file:///srv/units/app.service".parse::<Uri>().unwrap();
+        let unit = crate::parser::SystemdParser::new().parse(
+            "[Unit]\nRequires=db.service\nAfter=network.target\nConflicts=db.service legacy.service\nBefore=network.target\nWants=legacy.service\nBefore=\nBefore=cache.service\nAfter=cache.service\n",
+        );
+        diagnostics.update(&uri, unit).await;
+
+        let found: Vec<(u32, String)> = diagnostics
+            .get_diagnostics(&uri)
+            .await
+            .into_iter()
+            .map(|diagnostic| (diagnostic.range.start.line, diagnostic.message))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (
+                    3,
+                    "Conflicts=db.service contradicts Requires=db.service: starting the unit both pulls in and stops db.service".to_string()
+                ),
+                (
+                    4,
+                    "Before=network.target contradicts After=network.target: the unit cannot start both before and after network.target".to_string()
+                ),
+                (
+                    5,
+                    "Wants=legacy.service contradicts Conflicts=legacy.service: starting the unit both pulls in and stops legacy.service".to_string()
+                ),
+                (
+                    8,
+                    "After=cache.service contradicts Before=cache.service: the unit cannot start both before and after cache.service".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_install_names() {
+        let diagnostics = SystemdDiagnostics::new();
+        let parser = crate::parser::SystemdParser::new();
+        let messages = |uri: &Uri| {
+            let uri = uri.clone();
+            let diagnostics = &diagnostics;
+            async move {
+                diagnostics
+                    .get_diagnostics(&uri)
+                    .await
+                    .into_iter()
+                    .map(|diagnostic| (diagnostic.range.start.line, diagnostic.message))
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        let app = "file:///srv/units/app.service".parse::<Uri>().unwrap();
+        diagnostics
+            .update(
+                &app,
+                parser.parse("[Install]\nWantedBy=multi-user.target\nAlias=application.service app.target\nDefaultInstance=main\n"),
+            )
+            .await;
+        assert_eq!(
+            messages(&app).await,
+            [
+                (
+                    2,
+                    "Alias=app.target: an alias of app.service must also end in .service"
+                        .to_string()
+                ),
+                (
+                    3,
+                    "DefaultInstance= only applies to templates such as app@.service, app.service is not one".to_string()
+                ),
+            ]
+        );
+
+        let template = "file:///srv/units/worker@.service".parse::<Uri>().unwrap();
+        diagnostics
+            .update(
+                &template,
+                parser.parse("[Install]\nAlias=job@.service job.service\nDefaultInstance=main\n"),
+            )
+            .await;
+        assert_eq!(
+            messages(&template).await,
+            [(
+                1,
+                "Alias=job.service: worker@.service is a template and can only be aliased by another template such as job@.service".to_string()
+            )]
+        );
+
+        diagnostics
+            .update(
+                &template,
+                parser.parse("[Install]\nDefaultInstance=main queue\n"),
+            )
+            .await;
+        assert_eq!(
+            messages(&template).await,
+            [(
+                1,
+                "DefaultInstance=main queue: 'worker@main queue.service' contains ' ', which unit names cannot, escape it with systemd-escape".to_string()
+            )]
+        );
+
+        // systemd skips files that are not named like units
+        let spaced = "file:///srv/units/my%20app.service".parse::<Uri>().unwrap();
+        diagnostics
+            .update(
+                &spaced,
+                parser.parse("[Install]\nWantedBy=multi-user.target\n"),
+            )
+            .await;
+        assert_eq!(
+            messages(&spaced).await,
+            [(
+                0,
+                "systemd does not load this file: 'my app.service' contains ' ', which unit names cannot, escape it with systemd-escape".to_string()
+            )]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_instance_specifiers_outside_templates() {
+        let diagnostics = SystemdDiagnostics::new();
+        let parser = crate::parser::SystemdParser::new();
+        let text = "[Service]\nExecStart=/usr/bin/app --name=%i --path=%I --literal=%%i\n";
+
+        let app = "file:///srv/units/app.service".parse::<Uri>().unwrap();
+        diagnostics.update(&app, parser.parse(text)).await;
+        let found: Vec<(u32, u32, String)> = diagnostics
+            .get_diagnostics(&app)
+            .await
+            .into_iter()
+            .map(|diagnostic| {
+                (
+                    diagnostic.range.start.line,
+                    diagnostic.range.start.character,
+                    diagnostic.message,
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (
+                    1,
+                    30,
+                    "%i expands to an empty string: app.service is not a template such as app@.service".to_string()
+                ),
+                (
+                    1,
+                    40,
+                    "%I expands to an empty string: app.service is not a template such as app@.service".to_string()
+                ),
+            ]
+        );
+
+        for uri in [
+            "file:///srv/units/app@.service",
+            "file:///srv/units/app@main.service.d/override.conf",
+        ] {
+            let uri = uri.parse::<Uri>().unwrap();
+            diagnostics.update(&uri, parser.parse(text)).await;
+            assert!(diagnostics.get_diagnostics(&uri).await.is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_timer_and_path_activation() {
+        let diagnostics = SystemdDiagnostics::new();
+        let parser = crate::parser::SystemdParser::new();
+        let messages = |uri: &Uri| {
+            let uri = uri.clone();
+            let diagnostics = &diagnostics;
+            async move {
+                diagnostics
+                    .get_diagnostics(&uri)
+                    .await
+                    .into_iter()
+                    .map(|diagnostic| (diagnostic.range.start.line, diagnostic.message))
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        let timer = "file:///srv/units/nightly-report.timer"
+            .parse::<Uri>()
+            .unwrap();
+        diagnostics
+            .update(
+                &timer,
+                parser.parse("[Unit]\nDescription=Nightly\n\n[Timer]\nOnCalendar=daily\nOnCalendar=\nOnClockChange=no\n"),
+            )
+            .await;
+        assert_eq!(
+            messages(&timer).await,
+            [
+                (3, "nightly-report.timer has no trigger, systemd refuses to load it without one of OnActiveSec=, OnBootSec=, OnStartupSec=, OnUnitActiveSec=, OnUnitInactiveSec=, OnCalendar=, OnClockChange= or OnTimezoneChange=".to_string()),
+                (3, "nightly-report.timer activates nightly-report.service, which is neither in the workspace nor next to it".to_string()),
+            ]
+        );
+        let missing = &diagnostics.get_diagnostics(&timer).await[1];
+        assert_eq!(
+            missing.code,
+            Some(NumberOrString::String(
+                MISSING_ACTIVATED_UNIT_CODE.to_string()
+            ))
+        );
+        assert_eq!(
+            missing.data,
+            Some(serde_json::json!({
+                "unit": "nightly-report.service",
+                "template": "oneshot-service",
+            }))
+        );
+
+        // Once the service is known, only the missing trigger is left
+        let service = "file:///srv/units/nightly-report.service"
+            .parse::<Uri>()
+            .unwrap();
+        diagnostics
+            .update(
+                &service,
+                parser.parse("[Service]\nExecStart=/usr/bin/report\n"),
+            )
+            .await;
+        diagnostics
+            .update(&timer, parser.parse("[Timer]\nOnClockChange=yes\n"))
+            .await;
+        assert!(messages(&timer).await.is_empty());
+
+        // Unit= names the activated unit instead, flagged where it is named
+        let path = "file:///srv/units/spool.path".parse::<Uri>().unwrap();
+        diagnostics
+            .update(
+                &path,
+                parser.parse("[Path]\nPathExists=/var/spool/upload\nUnit=spool-upload.service\n"),
+            )
+            .await;
+        assert_eq!(
+            diagnostics.get_diagnostics(&path).await[0].range,
+            Range::new(Position::new(2, 5), Position::new(2, 25))
+        );
+        diagnostics
+            .update(&path, parser.parse("[Path]\nUnit=nightly-report.service\n"))
+            .await;
+        assert_eq!(
+            messages(&path).await,
+            [(0, "spool.path has no path to watch, systemd refuses to load it without one of PathExists=, PathExistsGlob=, PathChanged=, PathModified= or DirectoryNotEmpty=".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_socket_activation() {
+        let diagnostics = SystemdDiagnostics::new();
+        let parser = crate::parser::SystemdParser::new();
+        let messages = |uri: &Uri| {
+            let uri = uri.clone();
+            let diagnostics = &diagnostics;
+            async move {
+                diagnostics
+                    .get_diagnostics(&uri)
+                    .await
+                    .into_iter()
+                    .map(|diagnostic| (diagnostic.range.start.line, diagnostic.message))
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        let socket = "file:///srv/units/api.socket".parse::<Uri>().unwrap();
+        diagnostics
+            .update(
+                &socket,
+                parser.parse("[Socket]\nListenStream=0.0.0.0:8080\nListenStream=\n"),
+            )
+            .await;
+        assert_eq!(
+            messages(&socket).await,
+            [
+                (0, "api.socket has no address to listen on, systemd refuses to load it without one of ListenStream=, ListenDatagram=, ListenSequentialPacket=, ListenFIFO=, ListenSpecial=, ListenNetlink=, ListenMessageQueue= or ListenUSBFunction=".to_string()),
+                (0, "api.socket activates api.service, which is neither in the workspace nor next to it".to_string()),
+            ]
+        );
+        assert_eq!(
+            diagnostics.get_diagnostics(&socket).await[1].data,
+            Some(serde_json::json!({
+                "unit": "api.service",
+                "template": "socket-activated-service",
+            }))
+        );
+
+        diagnostics
+            .update(
+                &socket,
+                parser.parse("[Socket]\nListenStream=8080\nAccept=yes\nService=api.service\n"),
+            )
+            .await;
+        assert_eq!(
+            messages(&socket).await,
+            [(3, "Service= cannot be combined with Accept=yes, each connection starts an instance of api@.service".to_string())]
+        );
+        diagnostics
+            .update(
+                &socket,
+                parser.parse("[Socket]\nListenStream=8080\nService=api.target\n"),
+            )
+            .await;
+        assert_eq!(
+            messages(&socket).await,
+            [(
+                2,
+                "Service= must name a service, api.target is not one".to_string()
+            )]
+        );
+
+        // With only one of api.service and api@.service, Accept= picks the wrong one
+        let service = "file:///srv/units/api.service".parse::<Uri>().unwrap();
+        diagnostics
+            .update(
+                &service,
+                parser.parse("[Service]\nExecStart=/usr/bin/api\n"),
+            )
+            .await;
+        diagnostics
+            .update(
+                &socket,
+                parser.parse("[Socket]\nListenStream=/run/api.sock\nAccept=yes\n"),
+            )
+            .await;
+        assert_eq!(
+            messages(&socket).await,
+            [(2, "Accept=yes starts an instance of api@.service for each connection, but only api.service exists".to_string())]
+        );
+        diagnostics
+            .update(
+                &socket,
+                parser.parse("[Socket]\nListenStream=/run/api.sock\nAccept=no\n"),
+            )
+            .await;
+        assert!(messages(&socket).await.is_empty());
+
+        let other = "file:///srv/units/rpc.socket".parse::<Uri>().unwrap();
+        let template = "file:///srv/units/rpc@.service".parse::<Uri>().unwrap();
+        diagnostics
+            .update(
+                &template,
+                parser.parse("[Service]\nExecStart=/usr/bin/rpc\n"),
+            )
+            .await;
+        diagnostics
+            .update(&other, parser.parse("[Socket]\nListenStream=[::1]:53\n"))
+            .await;
+        assert_eq!(
+            messages(&other).await,
+            [(0, "rpc.socket activates rpc.service, but only the template rpc@.service exists: set Accept=yes to start an instance of it for each connection".to_string())]
+        );
+        diagnostics
+            .update(
+                &other,
+                parser.parse("[Socket]\nListenStream=[::1]:53\nAccept=true\n"),
+            )
+            .await;
+        assert!(messages(&other).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_mount_names_and_sources() {
+        let diagnostics = SystemdDiagnostics::new();
+        let parser = crate::parser::SystemdParser::new();
+        let messages = |uri: &str, text: &str| {
+            let uri = uri.parse::<Uri>().unwrap();
+            let unit = parser.parse(text);
+            let diagnostics = &diagnostics;
+            async move {
+                diagnostics.update(&uri, unit).await;
+                diagnostics
+                    .get_diagnostics(&uri)
+                    .await
+                    .into_iter()
+                    .map(|diagnostic| (diagnostic.range.start.line, diagnostic.message))
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        let mount = "[Mount]\nWhat=LABEL=data\nWhere=/mnt/my-disk\nType=ext4\n";
+        assert!(messages("file:///srv/units/mnt-my%5Cx2ddisk.mount", mount)
+            .await
+            .is_empty());
+        assert_eq!(
+            messages("file:///srv/units/mnt-my-disk.mount", mount).await,
+            [(2, "Mount units are named after the path they mount on: Where=/mnt/my-disk needs mnt-my\\x2ddisk.mount, not mnt-my-disk.mount".to_string())]
+        );
+        assert_eq!(
+            messages(
+                "file:///srv/units/data.automount",
+                "[Automount]\nWhere=/srv/data/\n"
+            )
+            .await,
+            [(1, "Automount units are named after the path they mount on: Where=/srv/data/ needs srv-data.automount, not data.automount".to_string())]
+        );
+        assert_eq!(
+            messages(
+                "file:///srv/units/srv.mount",
+                "[Mount]\nWhat=tmpfs\nWhere=/srv/../srv\n"
+            )
+            .await,
+            [(2, "Where=/srv/../srv has a .. component, systemd needs a normalized path to name the unit after".to_string())]
+        );
+
+        assert_eq!(
+            messages("file:///srv/units/srv.mount", "[Mount]\nWhere=/srv\n").await,
+            [(
+                0,
+                "srv.mount has no What=, systemd refuses to load it without something to mount"
+                    .to_string()
+            )]
+        );
+        for (what, file_system, form) in [
+            ("data", "xfs", "a block device or image file, What= should be an absolute path or LABEL=, UUID=, PARTUUID= or PARTLABEL="),
+            ("/exports/srv", "nfs", "a remote directory, What= should be host:/path"),
+            ("server/share", "cifs", "a share, What= should be //server/share"),
+        ] {
+            assert_eq!(
+                messages(
+                    "file:///srv/units/srv.mount",
+                    &format!("[Mount]\nWhat={}\nWhere=/srv\nType={}\n", what, file_system)
+                )
+                .await,
+                [(1, format!("Type={} mounts {}", file_system, form))]
+            );
+        }
+        assert!(messages(
+            "file:///srv/units/srv.mount",
+            "[Mount]\nWhat=nas:/exports/srv\nWhere=/srv\nType=nfs4\n"
+        )
+        .await
+        .is_empty());
+
+        // mount(8) takes contradicting flags, the last one wins
+        let contradicting = "file:///srv/units/srv.mount".parse::<Uri>().unwrap();
+        diagnostics
+            .update(
+                &contradicting,
+                parser.parse("[Mount]\nWhat=tmpfs\nWhere=/srv\nOptions=rw,noatime,ro\n"),
+            )
+            .await;
+        let found = diagnostics.get_diagnostics(&contradicting).await;
+        assert_eq!(found.len(), 1, "{:?}", found);
+        assert_eq!(found[0].severity, Some(DiagnosticSeverity::WARNING));
+        assert_eq!(
+            found[0].message,
+            "Options= has both rw and ro, only the last one, ro, takes effect"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_units_nothing_starts() {
+        let diagnostics = SystemdDiagnostics::new();
+        let parser = crate::parser::SystemdParser::new();
+        let unreferenced = |uri: &Uri| {
+            let uri = uri.clone();
+            let diagnostics = &diagnostics;
+            async move {
+                diagnostics
+                    .get_diagnostics(&uri)
+                    .await
+                    .into_iter()
+                    .any(|diagnostic| diagnostic.message.starts_with("Nothing starts"))
+            }
+        };
+        let worker = "file:///srv/units/worker.service".parse::<Uri>().unwrap();
+        let api = "file:///srv/units/api.service".parse::<Uri>().unwrap();
+        let api_socket = "file:///srv/units/api.socket".parse::<Uri>().unwrap();
+        let outside = "file:///etc/systemd/system/other.service"
+            .parse::<Uri>()
+            .unwrap();
+        let service = "[Unit]\nDescription=Worker\n\n[Service]\nExecStart=/usr/bin/true\n";
+
+        // Only files of the workspace are checked
+        diagnostics.update(&outside, parser.parse(service)).await;
+        assert!(!unreferenced(&outside).await);
+
+        diagnostics.set_workspace_folders(vec![PathBuf::from("/srv/units")]);
+        diagnostics.update(&worker, parser.parse(service)).await;
+        let found = diagnostics.get_diagnostics(&worker).await;
+        assert_eq!(found.len(), 1);
+        assert_eq!(
+            found[0].message,
+            "Nothing starts worker.service: it has no [Install] section to enable it with and no unit in the workspace depends on it"
+        );
+        assert_eq!(found[0].range.start.line, 0);
+        assert_eq!(found[0].severity, Some(DiagnosticSeverity::WARNING));
+
+        // A socket activates the service of the same name
+        diagnostics.update(&api, parser.parse(service)).await;
+        assert!(unreferenced(&api).await);
+        assert!(
+            diagnostics
+                .update(
+                    &api_socket,
+                    parser.parse(
+                        "[Socket]\nListenStream=8080\n\n[Install]\nWantedBy=sockets.target\n"
+                    )
+                )
+                .await
+        );
+        diagnostics.update(&api, parser.parse(service)).await;
+        assert!(!unreferenced(&api).await);
+        assert!(!unreferenced(&api_socket).await);
+
+        // So does a dependency from another unit
+        diagnostics
+            .update(
+                &api_socket,
+                parser.parse("[Unit]\nWants=worker.service\n\n[Socket]\nService=api.service\n\n[Install]\nWantedBy=sockets.target\n"),
+            )
+            .await;
+        diagnostics.update(&worker, parser.parse(service)).await;
+        assert!(!unreferenced(&worker).await);
+        diagnostics.update(&api, parser.parse(service)).await;
+        assert!(!unreferenced(&api).await);
+    }
+
+    #[tokio::test]
+    async fn test_undefined_variables() {
+        async fn undefined(text: &str, uri: &str) -> Vec<(Range, String)> {
+            let diagnostics = SystemdDiagnostics::new();
+            let uri = uri.parse::<Uri>().unwrap();
+            let unit = crate::parser::SystemdParser::new().parse(text);
+            diagnostics.update(&uri, unit).await;
+            diagnostics
+                .get_diagnostics(&uri)
+                .await
+                .into_iter()
+                .filter(|diagnostic| diagnostic.message.contains("is not defined"))
+                .map(|diagnostic| (diagnostic.range, diagnostic.message))
+                .collect()
+        }
+
+        let text = "[Service]\nEnvironment=\"NAME=my app\"\nPassEnvironment=TZ\nExecStart=/usr/bin/app ${NAME} $TZ $MAINPID $PORT $$HOME\nExecStartPost=/bin/sh -c 'echo ${OTHER} $SHELL_ONLY'\n";
+        let found = undefined(text, "file:///srv/app.service").await;
+        assert_eq!(found.len(), 2, "{:?}", found);
+        assert_eq!(
+            found[0].0,
+            Range::new(Position::new(3, 44), Position::new(3, 49))
+        );
+        assert!(found[0].1.starts_with("$PORT is not defined"));
+        assert!(found[1].1.starts_with("$OTHER is not defined"));
+
+        // The shell expands $NAME inside a word, systemd leaves it alone
+        let text = "[Service]\nExecStart=/bin/sh -c \"echo $HOSTNAME; exec app --port=$PORT\"\n";
+        assert!(undefined(text, "file:///srv/app.service").await.is_empty());
+
+        // Environment files that cannot be read may define anything
+        let text =
+            "[Service]\nEnvironmentFile=-/etc/default/missing-app\nExecStart=/usr/bin/app $PORT\n";
+        assert!(undefined(text, "file:///srv/app.service").await.is_empty());
+
+        // Drop-ins usually rely on the variables of the unit they extend
+        let text = "[Service]\nExecStart=\nExecStart=/usr/bin/app $PORT\n";
+        assert!(undefined(
+            text,
+            "file:///etc/systemd/system/app.service.d/override.conf"
+        )
+        .await
+        .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_security_exposure_summary() {
+        let diagnostics = SystemdDiagnostics::new();
+        let unit = || {
+            crate::parser::SystemdParser::new()
+                .parse("[Unit]\nDescription=App\n\n[Service]\nExecStart=/usr/bin/app\n")
+        };
+        let uri = "file:///srv/app.service".parse::<Uri>().unwrap();
+
+        // Off unless enabled, so the CLI only reports problems
+        diagnostics.update(&uri, unit()).await;
+        assert!(diagnostics.get_diagnostics(&uri).await.is_empty());
+
+        diagnostics.set_security_exposure(true);
+        diagnostics.update(&uri, unit()).await;
+        let result = diagnostics.get_diagnostics(&uri).await;
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].severity, Some(DiagnosticSeverity::INFORMATION));
+        assert_eq!(
+            result[0].range,
+            Range::new(Position::new(3, 0), Position::new(3, 9))
+        );
+        assert!(
+            result[0].message.starts_with("Security exposure 9."),
+            "{}",
+            result[0].message
+        );
+        assert!(result[0].message.contains("most of all PrivateNetwork="));
+
+        let container = "file:///srv/app.container".parse::<Uri>().unwrap();
+        diagnostics.update(&container, unit()).await;
+        assert!(diagnostics.get_diagnostics(&container).await.is_empty());
+    }
 }
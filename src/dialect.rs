@@ -0,0 +1,419 @@
+use crate::config::NetworkDialect;
+use crate::constants::SystemdConstants;
+use crate::parser::SystemdUnit;
+use crate::schema::DIALECT_MAN_PAGES;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// The INI dialect a document is written in.
+///
+/// systemd units and Podman Quadlet files share one set of sections, while the
+/// systemd-networkd `.network`, `.netdev` and `.link` files and the daemon
+/// configuration files such as `journald.conf` each have their own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum UnitDialect {
+    #[default]
+    Systemd,
+    NetworkdNetwork,
+    NetworkdNetDev,
+    NetworkdLink,
+    Journald,
+    Logind,
+    SystemConf,
+    Timesyncd,
+    Resolved,
+}
+
+/// Sections that only ever appear in unit or Quadlet files
+const UNIT_ONLY_SECTIONS: &[&str] = &[
+    "Unit",
+    "Install",
+    "Service",
+    "Container",
+    "Pod",
+    "Volume",
+    "Kube",
+    "Build",
+    "Image",
+];
+
+/// Sections that only ever appear in systemd-networkd `.network` files
+const NETWORK_ONLY_SECTIONS: &[&str] = &["Address", "Route", "DHCPv4"];
+
+/// Daemon configuration files, recognised by name or by their `.conf.d/` drop-in directory
+const CONFIG_FILES: &[(&str, UnitDialect)] = &[
+    ("journald.conf", UnitDialect::Journald),
+    ("logind.conf", UnitDialect::Logind),
+    ("system.conf", UnitDialect::SystemConf),
+    ("user.conf", UnitDialect::SystemConf),
+    ("timesyncd.conf", UnitDialect::Timesyncd),
+    ("resolved.conf", UnitDialect::Resolved),
+];
+
+/// The single section each daemon configuration file consists of
+const CONFIG_SECTIONS: &[(&str, UnitDialect)] = &[
+    ("Journal", UnitDialect::Journald),
+    ("Login", UnitDialect::Logind),
+    ("Manager", UnitDialect::SystemConf),
+    ("Time", UnitDialect::Timesyncd),
+    ("Resolve", UnitDialect::Resolved),
+];
+
+impl UnitDialect {
+    pub const ALL: [UnitDialect; 9] = [
+        UnitDialect::Systemd,
+        UnitDialect::NetworkdNetwork,
+        UnitDialect::NetworkdNetDev,
+        UnitDialect::NetworkdLink,
+        UnitDialect::Journald,
+        UnitDialect::Logind,
+        UnitDialect::SystemConf,
+        UnitDialect::Timesyncd,
+        UnitDialect::Resolved,
+    ];
+
+    /// systemd units and Quadlet files, as opposed to any other INI dialect
+    pub fn is_unit(&self) -> bool {
+        matches!(self, UnitDialect::Systemd)
+    }
+
+    pub fn is_networkd(&self) -> bool {
+        matches!(
+            self,
+            UnitDialect::NetworkdNetwork | UnitDialect::NetworkdNetDev | UnitDialect::NetworkdLink
+        )
+    }
+
+    /// Human readable name used in diagnostics and completion details
+    pub fn description(&self) -> &'static str {
+        match self {
+            UnitDialect::Systemd => "systemd unit",
+            UnitDialect::NetworkdNetwork => "systemd-networkd .network file",
+            UnitDialect::NetworkdNetDev => "systemd-networkd .netdev file",
+            UnitDialect::NetworkdLink => "systemd .link file",
+            UnitDialect::Journald => "journald.conf file",
+            UnitDialect::Logind => "logind.conf file",
+            UnitDialect::SystemConf => "systemd system.conf file",
+            UnitDialect::Timesyncd => "timesyncd.conf file",
+            UnitDialect::Resolved => "resolved.conf file",
+        }
+    }
+
+    /// Man page documenting the whole file; systemd units have one per section instead
+    pub fn man_page(&self) -> Option<&'static str> {
+        DIALECT_MAN_PAGES
+            .iter()
+            .find(|(dialect, _)| dialect == self)
+            .map(|(_, man_page)| *man_page)
+    }
+
+    /// Recognise a daemon configuration file such as `/etc/systemd/journald.conf`,
+    /// `journald@audit.conf` or `/etc/systemd/journald.conf.d/50-storage.conf`
+    pub fn from_config_path(path: &str) -> Option<Self> {
+        let path = Path::new(path);
+        let file_name = path.file_name()?.to_str()?;
+        let dropin_dir = path
+            .parent()
+            .and_then(|dir| dir.file_name())
+            .and_then(|dir| dir.to_str())
+            .and_then(|dir| dir.strip_suffix(".d"))
+            .filter(|_| file_name.ends_with(".conf"));
+        let name = dropin_dir.unwrap_or(file_name);
+
+        // journald namespaces are configured in journald@NAMESPACE.conf
+        let name = match name.split_once('@') {
+            Some((daemon, namespace)) if namespace.ends_with(".conf") => format!("{daemon}.conf"),
+            _ => name.to_string(),
+        };
+
+        CONFIG_FILES
+            .iter()
+            .find(|(config_name, _)| *config_name == name)
+            .map(|(_, dialect)| *dialect)
+    }
+
+    /// Pick the dialect of a document from its path, falling back to its content.
+    ///
+    /// Daemon configuration files are known by name, `.netdev` and `.link` are
+    /// unambiguous. `.network` files follow the configured preference, then the
+    /// well-known search directories of systemd-networkd and Quadlet, and finally
+    /// the sections and keys the file actually uses. Drop-ins in `<name>.d/`
+    /// directories take the dialect of the file they extend.
+    pub fn detect(path: Option<&str>, unit: &SystemdUnit, preference: NetworkDialect) -> Self {
+        let Some(path) = path else {
+            return Self::from_content(unit);
+        };
+        if let Some(dialect) = Self::from_config_path(path) {
+            return dialect;
+        }
+
+        let file = Path::new(path);
+        match file.extension().and_then(|ext| ext.to_str()) {
+            Some("conf") => match file.parent().and_then(|dir| dir.to_str()) {
+                Some(dir) if dir.ends_with(".d") => {
+                    Self::detect(dir.strip_suffix(".d"), unit, preference)
+                }
+                _ => Self::from_content(unit),
+            },
+            Some("netdev") => UnitDialect::NetworkdNetDev,
+            Some("link") => UnitDialect::NetworkdLink,
+            Some("network") => match preference {
+                NetworkDialect::Quadlet => UnitDialect::Systemd,
+                NetworkDialect::Networkd => UnitDialect::NetworkdNetwork,
+                // Quadlet also reads subdirectories, e.g. containers/systemd/network/
+                NetworkDialect::Auto if path.contains("/containers/systemd/") => {
+                    UnitDialect::Systemd
+                }
+                NetworkDialect::Auto
+                    if file
+                        .parent()
+                        .is_some_and(|dir| dir.ends_with("systemd/network")) =>
+                {
+                    UnitDialect::NetworkdNetwork
+                }
+                NetworkDialect::Auto => match Self::from_content(unit) {
+                    dialect if dialect.is_networkd() => UnitDialect::NetworkdNetwork,
+                    _ => UnitDialect::Systemd,
+                },
+            },
+            Some(_) => UnitDialect::Systemd,
+            None => Self::from_content(unit),
+        }
+    }
+
+    /// Guess the dialect from the sections and directives used in the document
+    pub fn from_content(unit: &SystemdUnit) -> Self {
+        let has_section = |name: &str| unit.sections.contains_key(name);
+
+        if UNIT_ONLY_SECTIONS.iter().any(|name| has_section(name)) {
+            return UnitDialect::Systemd;
+        }
+        if let Some((_, dialect)) = CONFIG_SECTIONS.iter().find(|(name, _)| has_section(name)) {
+            return *dialect;
+        }
+        if has_section("NetDev") {
+            return UnitDialect::NetworkdNetDev;
+        }
+        if NETWORK_ONLY_SECTIONS.iter().any(|name| has_section(name)) {
+            return UnitDialect::NetworkdNetwork;
+        }
+
+        if let Some(section) = unit.sections.get("Network") {
+            let networkd =
+                SystemdConstants::dialect_section_directives(UnitDialect::NetworkdNetwork);
+            let quadlet = SystemdConstants::section_directives();
+            let networkd_keys = networkd.get("Network").cloned().unwrap_or_default();
+            let quadlet_keys = quadlet.get("Network").cloned().unwrap_or_default();
+
+            let networkd_only = section
+                .directives
+                .iter()
+                .filter(|d| {
+                    networkd_keys.contains(&d.key.as_str())
+                        && !quadlet_keys.contains(&d.key.as_str())
+                })
+                .count();
+            let quadlet_only = section
+                .directives
+                .iter()
+                .filter(|d| {
+                    quadlet_keys.contains(&d.key.as_str())
+                        && !networkd_keys.contains(&d.key.as_str())
+                })
+                .count();
+
+            return if networkd_only > quadlet_only || has_section("Match") {
+                UnitDialect::NetworkdNetwork
+            } else {
+                UnitDialect::Systemd
+            };
+        }
+
+        // [Match] and [Link] without [Network] is what a .link file looks like
+        if has_section("Match") || has_section("Link") {
+            return UnitDialect::NetworkdLink;
+        }
+
+        UnitDialect::Systemd
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::SystemdParser;
+
+    fn detect(path: Option<&str>, content: &str, preference: NetworkDialect) -> UnitDialect {
+        let unit = SystemdParser::new().parse(content);
+        UnitDialect::detect(path, &unit, preference)
+    }
+
+    #[test]
+    fn test_unambiguous_extensions() {
+        let content = "[Match]\nName=eth0\n";
+        assert_eq!(
+            detect(Some("/tmp/10-eth.link"), content, NetworkDialect::Auto),
+            UnitDialect::NetworkdLink
+        );
+        assert_eq!(
+            detect(
+                Some("/tmp/br0.netdev"),
+                "[NetDev]\nName=br0\nKind=bridge\n",
+                NetworkDialect::Auto
+            ),
+            UnitDialect::NetworkdNetDev
+        );
+        assert_eq!(
+            detect(Some("/tmp/foo.service"), content, NetworkDialect::Auto),
+            UnitDialect::Systemd
+        );
+    }
+
+    #[test]
+    fn test_network_file_by_directory() {
+        let content = "[Network]\n";
+        assert_eq!(
+            detect(
+                Some("/etc/systemd/network/10-eth.network"),
+                content,
+                NetworkDialect::Auto
+            ),
+            UnitDialect::NetworkdNetwork
+        );
+        assert_eq!(
+            detect(
+                Some("/home/user/.config/containers/systemd/app.network"),
+                content,
+                NetworkDialect::Auto
+            ),
+            UnitDialect::Systemd
+        );
+        assert_eq!(
+            detect(
+                Some("/etc/containers/systemd/network/app.network"),
+                content,
+                NetworkDialect::Auto
+            ),
+            UnitDialect::Systemd
+        );
+        assert_eq!(
+            detect(
+                Some("/usr/lib/systemd/network/80-container-vz.network"),
+                content,
+                NetworkDialect::Auto
+            ),
+            UnitDialect::NetworkdNetwork
+        );
+    }
+
+    #[test]
+    fn test_network_file_by_content() {
+        let networkd = "[Match]\nName=eth0\n\n[Network]\nDHCP=yes\n";
+        let quadlet = "[Network]\nSubnet=10.89.0.0/24\nGateway=10.89.0.1\n";
+
+        assert_eq!(
+            detect(Some("/srv/eth0.network"), networkd, NetworkDialect::Auto),
+            UnitDialect::NetworkdNetwork
+        );
+        assert_eq!(
+            detect(Some("/srv/app.network"), quadlet, NetworkDialect::Auto),
+            UnitDialect::Systemd
+        );
+        assert_eq!(
+            detect(None, networkd, NetworkDialect::Auto),
+            UnitDialect::NetworkdNetwork
+        );
+    }
+
+    #[test]
+    fn test_network_preference_overrides_detection() {
+        let networkd = "[Match]\nName=eth0\n\n[Network]\nDHCP=yes\n";
+        assert_eq!(
+            detect(
+                Some("/etc/systemd/network/eth0.network"),
+                networkd,
+                NetworkDialect::Quadlet
+            ),
+            UnitDialect::Systemd
+        );
+        assert_eq!(
+            detect(
+                Some("/etc/containers/systemd/app.network"),
+                "[Network]\n",
+                NetworkDialect::Networkd
+            ),
+            UnitDialect::NetworkdNetwork
+        );
+    }
+
+    #[test]
+    fn test_config_files_by_name() {
+        let content = "[Journal]\nStorage=persistent\n";
+        for path in [
+            "/etc/systemd/journald.conf",
+            "/etc/systemd/journald.conf.d/50-storage.conf",
+            "/usr/lib/systemd/journald@audit.conf",
+        ] {
+            assert_eq!(
+                detect(Some(path), content, NetworkDialect::Auto),
+                UnitDialect::Journald,
+                "{path}"
+            );
+        }
+        assert_eq!(
+            detect(Some("/etc/systemd/user.conf"), "", NetworkDialect::Auto),
+            UnitDialect::SystemConf
+        );
+        assert_eq!(
+            detect(
+                Some("/etc/systemd/resolved.conf.d/dns.conf"),
+                "",
+                NetworkDialect::Auto
+            ),
+            UnitDialect::Resolved
+        );
+    }
+
+    #[test]
+    fn test_dropins_follow_the_extended_file() {
+        assert_eq!(
+            detect(
+                Some("/etc/systemd/system/nginx.service.d/override.conf"),
+                "[Service]\nRestart=always\n",
+                NetworkDialect::Auto
+            ),
+            UnitDialect::Systemd
+        );
+        assert_eq!(
+            detect(
+                Some("/etc/systemd/network/10-eth.network.d/mtu.conf"),
+                "[Link]\nMTUBytes=9000\n",
+                NetworkDialect::Auto
+            ),
+            UnitDialect::NetworkdNetwork
+        );
+        assert_eq!(
+            detect(
+                Some("/srv/misc.conf"),
+                "[Login]\nNAutoVTs=2\n",
+                NetworkDialect::Auto
+            ),
+            UnitDialect::Logind
+        );
+    }
+
+    #[test]
+    fn test_every_dialect_has_a_man_page() {
+        for dialect in UnitDialect::ALL {
+            assert_eq!(
+                dialect.man_page().is_none(),
+                dialect.is_unit(),
+                "{dialect:?}"
+            );
+        }
+        assert_eq!(
+            UnitDialect::SystemConf.man_page(),
+            Some("systemd-system.conf.5")
+        );
+    }
+}
@@ -0,0 +1,479 @@
+//! Environment variables of a unit: the assignments of `Environment=` and
+//! `EnvironmentFile=`, and the `$NAME` references in command lines.
+
+use crate::parser::{SystemdSection, SystemdUnit};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A `$NAME` or `${NAME}` reference, with the char columns it spans
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VariableReference {
+    pub name: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Whether `name` may be assigned in `Environment=`, i.e. a letter or `_` followed by
+/// letters, digits and underscores
+pub fn is_variable_name(name: &str) -> bool {
+    name.chars().next().is_some_and(|c| !c.is_ascii_digit()) && name.chars().all(is_name_char)
+}
+
+/// Whether the chars `start..end` of a command line form a whole argument, alone or
+/// as the only content of a quoted one
+fn is_whole_argument(chars: &[char], start: usize, end: usize) -> bool {
+    let boundary = |position: Option<&char>| position.is_none_or(|c| c.is_whitespace());
+    match start.checked_sub(1).map(|before| chars[before]) {
+        None => boundary(chars.get(end)),
+        Some(c) if c.is_whitespace() => boundary(chars.get(end)),
+        Some(quote @ ('"' | '\'')) => {
+            boundary(start.checked_sub(2).and_then(|before| chars.get(before)))
+                && chars.get(end) == Some(&quote)
+                && boundary(chars.get(end + 1))
+        }
+        Some(_) => false,
+    }
+}
+
+/// The variable references of a command line; `$$` is an escaped dollar sign.
+///
+/// systemd replaces `${NAME}` anywhere, but `$NAME` only when it is a whole
+/// argument, so `sh -c "echo $HOME"` leaves `$HOME` to the shell.
+pub fn references(text: &str) -> Vec<VariableReference> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut references = Vec::new();
+    let mut index = 0;
+
+    while index < chars.len() {
+        if chars[index] != '$' {
+            index += 1;
+            continue;
+        }
+        if chars.get(index + 1) == Some(&'$') {
+            index += 2;
+            continue;
+        }
+
+        let braced = chars.get(index + 1) == Some(&'{');
+        let name_start = index + 1 + usize::from(braced);
+        let name_end = (name_start..chars.len())
+            .find(|&position| !is_name_char(chars[position]))
+            .unwrap_or(chars.len());
+        let name: String = chars[name_start..name_end].iter().collect();
+
+        let valid = if braced {
+            chars.get(name_end) == Some(&'}')
+        } else {
+            is_whole_argument(&chars, index, name_end)
+        };
+        if is_variable_name(&name) && valid {
+            let end = name_end + usize::from(braced);
+            references.push(VariableReference {
+                name,
+                start: index,
+                end,
+            });
+            index = end;
+        } else {
+            index += 1;
+        }
+    }
+
+    references
+}
+
+/// Variables systemd sets for the processes it spawns, see "Environment Variables in
+/// Spawned Processes" in systemd.exec(5)
+pub const MANAGER_VARIABLES: &[&str] = &[
+    "PATH",
+    "LANG",
+    "USER",
+    "LOGNAME",
+    "HOME",
+    "SHELL",
+    "TERM",
+    "INVOCATION_ID",
+    "XDG_RUNTIME_DIR",
+    "RUNTIME_DIRECTORY",
+    "STATE_DIRECTORY",
+    "CACHE_DIRECTORY",
+    "LOGS_DIRECTORY",
+    "CONFIGURATION_DIRECTORY",
+    "CREDENTIALS_DIRECTORY",
+    "MAINPID",
+    "MANAGERPID",
+    "SYSTEMD_EXEC_PID",
+    "LISTEN_FDS",
+    "LISTEN_PID",
+    "LISTEN_FDNAMES",
+    "NOTIFY_SOCKET",
+    "WATCHDOG_PID",
+    "WATCHDOG_USEC",
+    "JOURNAL_STREAM",
+    "LOG_NAMESPACE",
+    "SERVICE_RESULT",
+    "EXIT_CODE",
+    "EXIT_STATUS",
+    "MONITOR_SERVICE_RESULT",
+    "MONITOR_EXIT_CODE",
+    "MONITOR_EXIT_STATUS",
+    "MONITOR_INVOCATION_ID",
+    "MONITOR_UNIT",
+    "PIDFILE",
+    "REMOTE_ADDR",
+    "REMOTE_PORT",
+    "TRIGGER_UNIT",
+    "TRIGGER_PATH",
+    "TRIGGER_TIMER_REALTIME_USEC",
+    "TRIGGER_TIMER_MONOTONIC_USEC",
+    "MEMORY_PRESSURE_WATCH",
+    "MEMORY_PRESSURE_WRITE",
+    "FDSTORE",
+];
+
+/// A `NAME=value` assignment with its value unquoted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Assignment {
+    pub name: String,
+    pub value: String,
+    /// Char column of the name in an `Environment=` value, zero-based line in an environment file
+    pub position: usize,
+}
+
+/// Split a value into words the way systemd does for `Environment=`: whitespace
+/// separates words, single and double quotes group them and backslashes escape.
+/// Returns each word with the char column of its first character.
+fn words(text: &str) -> Vec<(String, usize)> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut words = Vec::new();
+    let mut index = 0;
+
+    while index < chars.len() {
+        if chars[index].is_whitespace() {
+            index += 1;
+            continue;
+        }
+
+        let mut word = String::new();
+        let mut column = None;
+        let mut quote = None;
+        while index < chars.len() && (quote.is_some() || !chars[index].is_whitespace()) {
+            let c = chars[index];
+            index += 1;
+            match (c, quote) {
+                ('\\', _) if index < chars.len() => {
+                    column.get_or_insert(index - 1);
+                    word.push(match chars[index] {
+                        'n' => '\n',
+                        't' => '\t',
+                        escaped => escaped,
+                    });
+                    index += 1;
+                }
+                ('"' | '\'', None) => quote = Some(c),
+                (_, Some(open)) if c == open => quote = None,
+                _ => {
+                    column.get_or_insert(index - 1);
+                    word.push(c);
+                }
+            }
+        }
+        words.push((word, column.unwrap_or(index)));
+    }
+
+    words
+}
+
+/// The assignments of an `Environment=` value such as `A=1 "B=two words"`; words that
+/// are not assignments are ignored, as systemd does
+pub fn assignments(text: &str) -> Vec<Assignment> {
+    words(text)
+        .into_iter()
+        .filter_map(|(word, column)| {
+            let (name, value) = word.split_once('=')?;
+            is_variable_name(name).then(|| Assignment {
+                name: name.to_string(),
+                value: value.to_string(),
+                position: column,
+            })
+        })
+        .collect()
+}
+
+/// The assignments of an environment file, one `NAME=value` per line
+pub fn file_assignments(text: &str) -> Vec<Assignment> {
+    text.lines()
+        .enumerate()
+        .filter_map(|(line, content)| {
+            let content = content.trim_start();
+            if content.starts_with('#') || content.starts_with(';') {
+                return None;
+            }
+            let (name, value) = content.split_once('=')?;
+            let name = name.trim_end();
+            let value = value.trim();
+            let value = match value.chars().next() {
+                Some(quote @ ('"' | '\'')) if value.len() > 1 && value.ends_with(quote) => {
+                    let inner = &value[1..value.len() - 1];
+                    if quote == '"' {
+                        inner.replace("\\\"", "\"").replace("\\\\", "\\")
+                    } else {
+                        inner.to_string()
+                    }
+                }
+                _ => value.to_string(),
+            };
+            is_variable_name(name).then(|| Assignment {
+                name: name.to_string(),
+                value,
+                position: line,
+            })
+        })
+        .collect()
+}
+
+/// Where a variable is assigned
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DefinitionSource {
+    /// An `Environment=` assignment in the unit file
+    Directive { line: u32, column: u32 },
+    /// A line of an environment file
+    File { path: PathBuf, line: u32 },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VariableDefinition {
+    pub name: String,
+    pub value: String,
+    pub source: DefinitionSource,
+}
+
+/// The variables the `Environment=`, `EnvironmentFile=` and `PassEnvironment=`
+/// directives of a section define for its commands
+#[derive(Debug, Clone, Default)]
+pub struct SectionEnvironment {
+    /// In the order systemd applies them, so later definitions win; environment
+    /// files come after `Environment=`
+    pub definitions: Vec<VariableDefinition>,
+    /// Names passed on from the service manager's environment
+    pub passed: Vec<String>,
+    /// Whether every environment file could be read. When one cannot, e.g. because
+    /// it only exists on the target machine, any name may be defined.
+    pub complete: bool,
+}
+
+impl SectionEnvironment {
+    /// Collect the definitions of a section of `unit`. Relative environment files,
+    /// which Quadlet accepts, are resolved against the directory of `unit_path`.
+    pub fn new(unit: &SystemdUnit, section: &SystemdSection, unit_path: Option<&Path>) -> Self {
+        let lines: Vec<&str> = unit.raw_text.lines().collect();
+        let mut environment = Self {
+            complete: true,
+            ..Self::default()
+        };
+        let mut file_definitions = Vec::new();
+
+        for directive in &section.directives {
+            match directive.key.as_str() {
+                "Environment" => {
+                    for span in &directive.value_spans {
+                        let Some(line) = lines.get(span.line as usize) else {
+                            continue;
+                        };
+                        let text: String = line
+                            .chars()
+                            .skip(span.start as usize)
+                            .take(span.end.saturating_sub(span.start) as usize)
+                            .collect();
+                        environment
+                            .definitions
+                            .extend(assignments(&text).into_iter().map(|assignment| {
+                                VariableDefinition {
+                                    name: assignment.name,
+                                    value: assignment.value,
+                                    source: DefinitionSource::Directive {
+                                        line: span.line,
+                                        column: span.start + assignment.position as u32,
+                                    },
+                                }
+                            }));
+                    }
+                }
+                "EnvironmentFile" => {
+                    for file in directive.value.split_whitespace() {
+                        let Some(path) =
+                            Self::resolve_file(file.trim_start_matches('-'), unit_path)
+                        else {
+                            environment.complete = false;
+                            continue;
+                        };
+                        let Ok(text) = fs::read_to_string(&path) else {
+                            environment.complete = false;
+                            continue;
+                        };
+                        file_definitions.extend(file_assignments(&text).into_iter().map(
+                            |assignment| VariableDefinition {
+                                name: assignment.name,
+                                value: assignment.value,
+                                source: DefinitionSource::File {
+                                    path: path.clone(),
+                                    line: assignment.position as u32,
+                                },
+                            },
+                        ));
+                    }
+                }
+                "PassEnvironment" => environment
+                    .passed
+                    .extend(directive.value.split_whitespace().map(str::to_string)),
+                _ => {}
+            }
+        }
+
+        environment.definitions.extend(file_definitions);
+        environment
+    }
+
+    fn resolve_file(file: &str, unit_path: Option<&Path>) -> Option<PathBuf> {
+        // Specifiers such as %h are only known on the target machine
+        if file.contains('%') {
+            return None;
+        }
+        let path = Path::new(file);
+        if path.is_absolute() {
+            Some(path.to_path_buf())
+        } else {
+            Some(unit_path?.parent()?.join(path))
+        }
+    }
+
+    /// The definitions of `name`, the one in effect last
+    pub fn definitions_of<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> impl Iterator<Item = &'a VariableDefinition> + 'a {
+        self.definitions
+            .iter()
+            .filter(move |definition| definition.name == name)
+    }
+
+    /// Whether a command line referencing `name` gets a value for it. Undecidable
+    /// names, i.e. with unreadable environment files, count as defined.
+    pub fn is_defined(&self, name: &str) -> bool {
+        !self.complete
+            || MANAGER_VARIABLES.contains(&name)
+            || self.passed.iter().any(|passed| passed == name)
+            || self.definitions_of(name).next().is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_references() {
+        let found = references("/usr/bin/app --port=${PORT} $HOST $$literal ${broken $1");
+        let names: Vec<(&str, usize, usize)> = found
+            .iter()
+            .map(|reference| (reference.name.as_str(), reference.start, reference.end))
+            .collect();
+        assert_eq!(names, [("PORT", 20, 27), ("HOST", 28, 33)]);
+
+        // Inside a word only the braced form is replaced, the shell expands the rest
+        let found = references(
+            r#"/bin/sh -c 'echo $HOSTNAME; exec app --port=$PORT --name=${NAME}' "$ARGS" --x=$Y"#,
+        );
+        let names: Vec<&str> = found
+            .iter()
+            .map(|reference| reference.name.as_str())
+            .collect();
+        assert_eq!(names, ["NAME", "ARGS"]);
+    }
+
+    #[test]
+    fn test_assignments() {
+        let found = assignments(r#"A=1 "B=two words" 'C=x' bogus 1X=2 D="quoted value" E=a\ b"#);
+        let found: Vec<(&str, &str, usize)> = found
+            .iter()
+            .map(|assignment| {
+                (
+                    assignment.name.as_str(),
+                    assignment.value.as_str(),
+                    assignment.position,
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("A", "1", 0),
+                ("B", "two words", 5),
+                ("C", "x", 19),
+                ("D", "quoted value", 35),
+                ("E", "a b", 52)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_file_assignments() {
+        let text = "# comment\nPORT=8080\n\n  HOST = example.org\n; other\nNAME=\"my \\\"app\\\"\"\nnot an assignment\n";
+        let found: Vec<(String, String, usize)> = file_assignments(text)
+            .into_iter()
+            .map(|assignment| (assignment.name, assignment.value, assignment.position))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("PORT".to_string(), "8080".to_string(), 1),
+                ("HOST".to_string(), "example.org".to_string(), 3),
+                ("NAME".to_string(), "my \"app\"".to_string(), 5)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_section_environment() {
+        let temp = tempfile::tempdir().unwrap();
+        let directory = temp.path().to_path_buf();
+        fs::write(directory.join("app.env"), "PORT=8080\n").unwrap();
+        let base = directory.display().to_string();
+
+        let parser = crate::parser::SystemdParser::new();
+        let text = format!(
+            "[Service]\nEnvironment=PORT=80 \"NAME=my app\"\nEnvironmentFile={base}/app.env\nPassEnvironment=TZ\n"
+        );
+        let unit = parser.parse(&text);
+        let environment = SectionEnvironment::new(&unit, &unit.sections["Service"], None);
+
+        assert!(environment.complete);
+        let port: Vec<&str> = environment
+            .definitions_of("PORT")
+            .map(|definition| definition.value.as_str())
+            .collect();
+        assert_eq!(port, ["80", "8080"]);
+        assert_eq!(
+            environment.definitions_of("NAME").next().unwrap().source,
+            DefinitionSource::Directive {
+                line: 1,
+                column: 21
+            }
+        );
+        assert!(environment.is_defined("TZ"));
+        assert!(environment.is_defined("MAINPID"));
+        assert!(!environment.is_defined("MISSING"));
+
+        // An environment file that only exists on the target machine may define anything
+        let text = "[Service]\nEnvironmentFile=-/etc/default/missing-app\n";
+        let unit = parser.parse(text);
+        let environment = SectionEnvironment::new(&unit, &unit.sections["Service"], None);
+        assert!(!environment.complete);
+        assert!(environment.is_defined("MISSING"));
+
+    }
+}
@@ -0,0 +1,688 @@
+//! Dependency graph of the units in a workspace.
+//!
+//! Every edge points from the unit that depends to the unit it depends on, the way
+//! systemd itself records them: `Before=b` in `a` is `b` after `a`, and `WantedBy=b`
+//! in `a` is `b` wanting `a` once the unit is enabled.
+//!
+//! The [`OrderingIndex`] keeps the `After=` and `Before=` declarations of the unit files
+//! in a workspace to find the ordering cycles systemd would break at boot, and the
+//! [`ReferenceIndex`] the units they name to find the ones nothing starts.
+
+use crate::parser::{SystemdDirective, SystemdUnit};
+use crate::schema::{self, ValueType};
+use crate::unit_name;
+use crate::value_providers;
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::path::Path;
+use std::sync::RwLock;
+use tower_lsp_server::lsp_types::{Position, Range, Uri};
+
+/// The relation an edge stands for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DependencyKind {
+    Requires,
+    Wants,
+    BindsTo,
+    PartOf,
+    After,
+}
+
+/// Directives that add edges, as (section, directive, kind, whether the edge is reversed)
+const DEPENDENCY_DIRECTIVES: &[(&str, &str, DependencyKind, bool)] = &[
+    ("Unit", "Requires", DependencyKind::Requires, false),
+    ("Unit", "Wants", DependencyKind::Wants, false),
+    ("Unit", "BindsTo", DependencyKind::BindsTo, false),
+    ("Unit", "PartOf", DependencyKind::PartOf, false),
+    ("Unit", "After", DependencyKind::After, false),
+    ("Unit", "Before", DependencyKind::After, true),
+    ("Install", "WantedBy", DependencyKind::Wants, true),
+    ("Install", "RequiredBy", DependencyKind::Requires, true),
+];
+
+impl DependencyKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DependencyKind::Requires => "requires",
+            DependencyKind::Wants => "wants",
+            DependencyKind::BindsTo => "binds-to",
+            DependencyKind::PartOf => "part-of",
+            DependencyKind::After => "after",
+        }
+    }
+
+    /// Edge attributes in DOT, following the colours of `systemd-analyze dot`
+    fn dot_attributes(&self) -> &'static str {
+        match self {
+            DependencyKind::Requires => "color=\"black\"",
+            DependencyKind::Wants => "color=\"grey66\"",
+            DependencyKind::BindsTo => "color=\"black\", style=\"bold\"",
+            DependencyKind::PartOf => "color=\"blue\"",
+            DependencyKind::After => "color=\"green\", style=\"dashed\"",
+        }
+    }
+
+    /// Mermaid arrow, labelled with the kind
+    fn mermaid_arrow(&self) -> &'static str {
+        match self {
+            DependencyKind::Requires | DependencyKind::PartOf => "-->",
+            DependencyKind::BindsTo => "==>",
+            DependencyKind::Wants | DependencyKind::After => "-.->",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DependencyEdge {
+    pub from: String,
+    pub to: String,
+    pub kind: DependencyKind,
+}
+
+/// The units of a directory and the dependencies between them and on other units
+#[derive(Debug, Default)]
+pub struct UnitGraph {
+    /// Values of each dependency directive per unit, after empty assignments reset them
+    dependencies: BTreeMap<String, BTreeMap<&'static str, Vec<String>>>,
+}
+
+impl UnitGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the dependencies a unit file or one of its drop-ins declares for unit `name`.
+    /// Drop-ins are added after their unit so that an empty `After=` clears the list.
+    pub fn add(&mut self, name: &str, unit: &SystemdUnit) {
+        let dependencies = self.dependencies.entry(name.to_string()).or_default();
+        for (section, directive, _, _) in DEPENDENCY_DIRECTIVES {
+            let Some(section) = unit.sections.get(*section) else {
+                continue;
+            };
+            for assignment in section.directives.iter().filter(|d| d.key == *directive) {
+                let values = dependencies.entry(directive).or_default();
+                if assignment.value.trim().is_empty() {
+                    values.clear();
+                }
+                values.extend(assignment.value.split_whitespace().map(str::to_string));
+            }
+        }
+    }
+
+    /// Defined units and the units they refer to, sorted by name
+    pub fn units(&self) -> BTreeSet<String> {
+        let mut units: BTreeSet<String> = self.dependencies.keys().cloned().collect();
+        for edge in self.edges() {
+            units.insert(edge.from);
+            units.insert(edge.to);
+        }
+        units
+    }
+
+    /// Every edge once, sorted by the unit it starts at
+    pub fn edges(&self) -> Vec<DependencyEdge> {
+        let mut edges: BTreeSet<DependencyEdge> = BTreeSet::new();
+        for (name, dependencies) in &self.dependencies {
+            for (_, directive, kind, reversed) in DEPENDENCY_DIRECTIVES {
+                for other in dependencies.get(directive).into_iter().flatten() {
+                    let (from, to) = if *reversed {
+                        (other, name)
+                    } else {
+                        (name, other)
+                    };
+                    edges.insert(DependencyEdge {
+                        from: from.clone(),
+                        to: to.clone(),
+                        kind: *kind,
+                    });
+                }
+            }
+        }
+        edges.into_iter().collect()
+    }
+
+    fn is_defined(&self, name: &str) -> bool {
+        self.dependencies.contains_key(name)
+    }
+
+    /// Graphviz source; units without a file here are drawn dashed
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph units {\n");
+        for unit in self.units() {
+            let style = if self.is_defined(&unit) {
+                ""
+            } else {
+                " [style=\"dashed\"]"
+            };
+            dot.push_str(&format!("\t{}{};\n", dot_id(&unit), style));
+        }
+        for edge in self.edges() {
+            dot.push_str(&format!(
+                "\t{} -> {} [{}, label=\"{}\"];\n",
+                dot_id(&edge.from),
+                dot_id(&edge.to),
+                edge.kind.dot_attributes(),
+                edge.kind.name()
+            ));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Mermaid flowchart; units are numbered as their names are not valid node ids
+    pub fn to_mermaid(&self) -> String {
+        let units: Vec<String> = self.units().into_iter().collect();
+        let id = |name: &str| {
+            let index = units.iter().position(|unit| *unit == name).unwrap_or(0);
+            format!("u{}", index)
+        };
+
+        let mut mermaid = String::from("flowchart LR\n");
+        for unit in &units {
+            let class = if self.is_defined(unit) {
+                ""
+            } else {
+                ":::external"
+            };
+            mermaid.push_str(&format!(
+                "    {}[\"{}\"]{}\n",
+                id(unit),
+                unit.replace('"', "#quot;"),
+                class
+            ));
+        }
+        for edge in self.edges() {
+            mermaid.push_str(&format!(
+                "    {} {}|{}| {}\n",
+                id(&edge.from),
+                edge.kind.mermaid_arrow(),
+                edge.kind.name(),
+                id(&edge.to)
+            ));
+        }
+        mermaid.push_str("    classDef external stroke-dasharray: 5 5\n");
+        mermaid
+    }
+
+    /// `{"units": [{"name", "defined"}], "edges": [{"from", "to", "type"}]}`
+    pub fn to_json(&self) -> String {
+        let units: Vec<_> = self
+            .units()
+            .into_iter()
+            .map(|unit| json!({ "name": unit, "defined": self.is_defined(&unit) }))
+            .collect();
+        let edges: Vec<_> = self
+            .edges()
+            .iter()
+            .map(|edge| json!({ "from": edge.from, "to": edge.to, "type": edge.kind.name() }))
+            .collect();
+        let graph = json!({ "units": units, "edges": edges });
+        format!(
+            "{}\n",
+            serde_json::to_string_pretty(&graph).unwrap_or_default()
+        )
+    }
+}
+
+/// An `After=` or `Before=` entry, with the columns of the unit name it orders against
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderingDeclaration {
+    /// The unit whose file or drop-in declares the entry
+    pub unit: String,
+    pub directive: &'static str,
+    pub target: String,
+    pub uri: Uri,
+    pub range: Range,
+}
+
+impl OrderingDeclaration {
+    /// The unit that starts later and the one it waits for
+    pub fn later_and_earlier(&self) -> (&str, &str) {
+        if self.directive == "Before" {
+            (&self.target, &self.unit)
+        } else {
+            (&self.unit, &self.target)
+        }
+    }
+}
+
+/// The ordering declarations of every unit file and drop-in the server knows, from the
+/// workspace on disk and the open documents
+#[derive(Debug, Default)]
+pub struct OrderingIndex {
+    files: RwLock<HashMap<Uri, Vec<OrderingDeclaration>>>,
+}
+
+impl OrderingIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace the declarations of one file. Returns whether the units it orders against
+    /// changed, so that the cycles of other files have to be looked for again.
+    pub fn update(&self, uri: &Uri, unit: &SystemdUnit) -> bool {
+        let declarations = unit_name(uri)
+            .filter(|_| unit.dialect.is_unit())
+            .map(|name| ordering_declarations(&name, uri, unit))
+            .unwrap_or_default();
+
+        let current = dependencies(&declarations);
+        let previous = self
+            .files
+            .write()
+            .unwrap()
+            .insert(uri.clone(), declarations);
+        match previous {
+            Some(previous) => dependencies(&previous) != current,
+            None => !current.is_empty(),
+        }
+    }
+
+    /// Ordering cycles through the declarations of `uri`, each starting with the
+    /// declaration of `uri` and following the units that wait for each other. A unit
+    /// ordered both before and after another is left out, that is a contradiction
+    /// within the unit rather than a cycle between units.
+    pub fn cycles_through(&self, uri: &Uri) -> Vec<Vec<OrderingDeclaration>> {
+        let files = self.files.read().unwrap();
+        let Some(own) = files.get(uri) else {
+            return Vec::new();
+        };
+
+        let mut waits_for: HashMap<&str, Vec<&OrderingDeclaration>> = HashMap::new();
+        for declaration in files.values().flatten() {
+            let (later, _) = declaration.later_and_earlier();
+            waits_for.entry(later).or_default().push(declaration);
+        }
+
+        own.iter()
+            .filter_map(|declaration| {
+                let (later, earlier) = declaration.later_and_earlier();
+                let mut cycle = vec![declaration.clone()];
+                cycle.extend(shortest_path(&waits_for, earlier, later)?);
+                let within_unit =
+                    cycle.len() > 1 && cycle.iter().all(|other| other.unit == declaration.unit);
+                (!within_unit).then_some(cycle)
+            })
+            .collect()
+    }
+}
+
+/// The units each unit file and drop-in of the workspace names in its dependencies, or
+/// pulls in implicitly the way `foo.socket` activates `foo.service`
+#[derive(Debug, Default)]
+pub struct ReferenceIndex {
+    files: RwLock<HashMap<Uri, BTreeSet<String>>>,
+}
+
+impl ReferenceIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace the references of one file. Returns whether they changed, or the file is
+    /// new and may define a unit other files refer to.
+    pub fn update(&self, uri: &Uri, unit: &SystemdUnit) -> bool {
+        let current = unit_name(uri)
+            .filter(|_| unit.dialect.is_unit())
+            .map(|name| references(&name, unit))
+            .unwrap_or_default();
+        let previous = self
+            .files
+            .write()
+            .unwrap()
+            .insert(uri.clone(), current.clone());
+        previous.is_none_or(|previous| previous != current)
+    }
+
+    /// Whether a known file, not a drop-in, defines the unit `name`, or the template of
+    /// an instance
+    pub fn defines(&self, name: &str) -> bool {
+        let template = name.contains('@').then(|| unit_name::template_of(name));
+        self.files.read().unwrap().keys().any(|uri| {
+            Path::new(uri.path().as_estr().decode().into_string_lossy().as_ref())
+                .file_name()
+                .and_then(|file_name| file_name.to_str())
+                .is_some_and(|file_name| {
+                    file_name == name || Some(file_name) == template.as_deref()
+                })
+        })
+    }
+
+    /// Whether a file other than `uri` names the unit `name`, or an instance of it if it
+    /// is a template
+    pub fn is_referenced(&self, name: &str, uri: &Uri) -> bool {
+        let template = name.split_once("@.");
+        self.files
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(other, _)| *other != uri)
+            .flat_map(|(_, names)| names)
+            .any(|reference| match (template, reference.split_once('@')) {
+                (Some((prefix, suffix)), Some((other_prefix, instance))) => {
+                    prefix == other_prefix
+                        && instance
+                            .rsplit_once('.')
+                            .is_some_and(|(_, other_suffix)| other_suffix == suffix)
+                }
+                _ => reference == name,
+            })
+    }
+}
+
+/// The units named by the unit list directives outside `[Install]`, where only `Also=`
+/// pulls units in, and the unit a socket, timer, path or automount activates by default
+fn references(name: &str, unit: &SystemdUnit) -> BTreeSet<String> {
+    let mut names = BTreeSet::new();
+    let mut activates_explicitly = false;
+    for section in unit.sections.values() {
+        for directive in &section.directives {
+            let is_unit_list = schema::lookup(unit.dialect, &section.name, &directive.key)
+                .is_some_and(|entry| entry.value_type == ValueType::Units)
+                || (section.name == "Socket" && directive.key == "Service");
+            if !is_unit_list || (section.name == "Install" && directive.key != "Also") {
+                continue;
+            }
+            activates_explicitly |= matches!(directive.key.as_str(), "Unit" | "Service");
+            names.extend(directive.value.split_whitespace().map(str::to_string));
+        }
+    }
+
+    if let Some((stem, suffix)) = name.rsplit_once('.') {
+        match suffix {
+            "socket" if !activates_explicitly => {
+                names.insert(format!("{}.service", stem));
+                names.insert(format!("{}@.service", stem));
+            }
+            "timer" | "path" if !activates_explicitly => {
+                names.insert(format!("{}.service", stem));
+            }
+            "automount" => {
+                names.insert(format!("{}.mount", stem));
+            }
+            _ => {}
+        }
+    }
+    names
+}
+
+/// What a file orders against, leaving out where
+fn dependencies(declarations: &[OrderingDeclaration]) -> BTreeSet<(String, &'static str, String)> {
+    declarations
+        .iter()
+        .map(|declaration| {
+            (
+                declaration.unit.clone(),
+                declaration.directive,
+                declaration.target.clone(),
+            )
+        })
+        .collect()
+}
+
+/// The declarations leading from unit `from` to unit `to`, found breadth first
+fn shortest_path(
+    waits_for: &HashMap<&str, Vec<&OrderingDeclaration>>,
+    from: &str,
+    to: &str,
+) -> Option<Vec<OrderingDeclaration>> {
+    if from == to {
+        return Some(Vec::new());
+    }
+
+    let mut reached: HashMap<&str, &OrderingDeclaration> = HashMap::new();
+    let mut queue = VecDeque::from([from]);
+    while let Some(unit) = queue.pop_front() {
+        for declaration in waits_for.get(unit).into_iter().flatten() {
+            let (_, next) = declaration.later_and_earlier();
+            if next == from || reached.contains_key(next) {
+                continue;
+            }
+            reached.insert(next, declaration);
+            if next == to {
+                let mut path = vec![(*declaration).clone()];
+                let mut current = declaration.later_and_earlier().0;
+                while current != from {
+                    let step = reached[current];
+                    path.push(step.clone());
+                    current = step.later_and_earlier().0;
+                }
+                path.reverse();
+                return Some(path);
+            }
+            queue.push_back(next);
+        }
+    }
+    None
+}
+
+/// The unit a file defines or, for a drop-in in `<unit>.d/`, extends
+pub fn unit_name(uri: &Uri) -> Option<String> {
+    // Escaped names such as `mnt-my\x2ddisk.mount` arrive percent-encoded
+    let path = uri.path().as_estr().decode().into_string_lossy();
+    let path = Path::new(path.as_ref());
+    let file_name = path.file_name()?.to_str()?;
+    let name = match path.parent()?.file_name()?.to_str()?.strip_suffix(".d") {
+        Some(unit) if file_name.ends_with(".conf") => unit,
+        _ => file_name,
+    };
+    value_providers::is_unit_name(name).then(|| name.to_string())
+}
+
+fn ordering_declarations(name: &str, uri: &Uri, unit: &SystemdUnit) -> Vec<OrderingDeclaration> {
+    let Some(section) = unit.sections.get("Unit") else {
+        return Vec::new();
+    };
+
+    let mut declarations: Vec<OrderingDeclaration> = Vec::new();
+    for directive in &section.directives {
+        let key = match directive.key.as_str() {
+            "After" => "After",
+            "Before" => "Before",
+            _ => continue,
+        };
+        if directive.value.trim().is_empty() {
+            declarations.retain(|declaration| declaration.directive != key);
+        }
+        declarations.extend(
+            value_words(unit, directive)
+                .into_iter()
+                .map(|(target, range)| OrderingDeclaration {
+                    unit: name.to_string(),
+                    directive: key,
+                    target,
+                    uri: uri.clone(),
+                    range,
+                }),
+        );
+    }
+    declarations
+}
+
+/// The whitespace-separated words of a directive's value and where they are
+pub fn value_words(unit: &SystemdUnit, directive: &SystemdDirective) -> Vec<(String, Range)> {
+    let lines: Vec<&str> = unit.raw_text.lines().collect();
+    let mut words = Vec::new();
+    for span in &directive.value_spans {
+        let Some(line) = lines.get(span.line as usize) else {
+            continue;
+        };
+        let chars: Vec<char> = line.chars().collect();
+        let end = (span.end as usize).min(chars.len());
+        let mut index = (span.start as usize).min(end);
+        while index < end {
+            if chars[index].is_whitespace() {
+                index += 1;
+                continue;
+            }
+            let start = index;
+            while index < end && !chars[index].is_whitespace() {
+                index += 1;
+            }
+            // A trailing backslash continues the value on the next line
+            if chars[start..index] == ['\\'] {
+                continue;
+            }
+            words.push((
+                chars[start..index].iter().collect(),
+                Range::new(
+                    Position::new(span.line, start as u32),
+                    Position::new(span.line, index as u32),
+                ),
+            ));
+        }
+    }
+    words
+}
+
+fn dot_id(name: &str) -> String {
+    format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::SystemdParser;
+
+    fn graph(files: &[(&str, &str)]) -> UnitGraph {
+        let parser = SystemdParser::new();
+        let mut graph = UnitGraph::new();
+        for (name, text) in files {
+            graph.add(name, &parser.parse(text));
+        }
+        graph
+    }
+
+    fn edge(from: &str, to: &str, kind: DependencyKind) -> DependencyEdge {
+        DependencyEdge {
+            from: from.to_string(),
+            to: to.to_string(),
+            kind,
+        }
+    }
+
+    #[test]
+    fn test_edges_point_at_the_dependency() {
+        let graph = graph(&[
+            (
+                "web.service",
+                "[Unit]\nRequires=db.service\nAfter=db.service network.target\nBefore=proxy.service\n\n[Install]\nWantedBy=multi-user.target\n",
+            ),
+            (
+                "db.service",
+                "[Unit]\nBindsTo=data.mount\nPartOf=app.target\n\n[Install]\nRequiredBy=app.target\n",
+            ),
+        ]);
+
+        assert_eq!(
+            graph.edges(),
+            [
+                edge("app.target", "db.service", DependencyKind::Requires),
+                edge("db.service", "app.target", DependencyKind::PartOf),
+                edge("db.service", "data.mount", DependencyKind::BindsTo),
+                edge("multi-user.target", "web.service", DependencyKind::Wants),
+                edge("proxy.service", "web.service", DependencyKind::After),
+                edge("web.service", "db.service", DependencyKind::Requires),
+                edge("web.service", "db.service", DependencyKind::After),
+                edge("web.service", "network.target", DependencyKind::After),
+            ]
+        );
+        assert!(graph.is_defined("db.service"));
+        assert!(!graph.is_defined("data.mount"));
+        assert_eq!(graph.units().len(), 7);
+    }
+
+    #[test]
+    fn test_drop_ins_extend_and_reset_dependencies() {
+        let graph = graph(&[
+            ("app.service", "[Unit]\nAfter=a.service\nWants=b.service\n"),
+            (
+                "app.service",
+                "[Unit]\nAfter=\nAfter=c.service\nWants=d.service\n",
+            ),
+        ]);
+
+        assert_eq!(
+            graph.edges(),
+            [
+                edge("app.service", "b.service", DependencyKind::Wants),
+                edge("app.service", "c.service", DependencyKind::After),
+                edge("app.service", "d.service", DependencyKind::Wants),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_output_formats() {
+        let graph = graph(&[(
+            "web.service",
+            "[Unit]\nWants=db.service\nAfter=db.service\n",
+        )]);
+
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph units {\n"));
+        assert!(dot.contains("\t\"db.service\" [style=\"dashed\"];\n"));
+        assert!(dot.contains("\t\"web.service\";\n"));
+        assert!(dot.contains(
+            "\t\"web.service\" -> \"db.service\" [color=\"grey66\", label=\"wants\"];\n"
+        ));
+        assert!(dot.contains(
+            "\t\"web.service\" -> \"db.service\" [color=\"green\", style=\"dashed\", label=\"after\"];\n"
+        ));
+
+        let mermaid = graph.to_mermaid();
+        assert!(mermaid.starts_with("flowchart LR\n"));
+        assert!(mermaid.contains("    u0[\"db.service\"]:::external\n"));
+        assert!(mermaid.contains("    u1[\"web.service\"]\n"));
+        assert!(mermaid.contains("    u1 -.->|wants| u0\n"));
+        assert!(mermaid.contains("    u1 -.->|after| u0\n"));
+
+        let json: serde_json::Value = serde_json::from_str(&graph.to_json()).unwrap();
+        assert_eq!(
+            json["units"][1],
+            json!({ "name": "web.service", "defined": true })
+        );
+        assert_eq!(
+            json["edges"][0],
+            json!({ "from": "web.service", "to": "db.service", "type": "wants" })
+        );
+    }
+
+    #[test]
+    fn test_unit_names_and_value_words() {
+        let name = |uri: &str| unit_name(&uri.parse::<Uri>().unwrap());
+        assert_eq!(
+            name("file:///srv/web.service").as_deref(),
+            Some("web.service")
+        );
+        assert_eq!(
+            name("file:///etc/systemd/system/web.service.d/override.conf").as_deref(),
+            Some("web.service")
+        );
+        assert_eq!(name("file:///etc/systemd/journald.conf"), None);
+        assert_eq!(name("file:///srv/app.container"), None);
+
+        let unit =
+            SystemdParser::new().parse("[Unit]\nAfter=a.service  b.service \\\n  c.service\n");
+        let directive = &unit.sections["Unit"].directives[0];
+        let words: Vec<(String, u32, u32, u32)> = value_words(&unit, directive)
+            .into_iter()
+            .map(|(word, range)| {
+                (
+                    word,
+                    range.start.line,
+                    range.start.character,
+                    range.end.character,
+                )
+            })
+            .collect();
+        assert_eq!(
+            words,
+            [
+                ("a.service".to_string(), 1, 6, 15),
+                ("b.service".to_string(), 1, 17, 26),
+                ("c.service".to_string(), 2, 2, 11),
+            ]
+        );
+    }
+}
@@ -0,0 +1,248 @@
+//! What `systemctl enable` does with the `[Install]` section of a unit file: the
+//! symlinks it creates below `/etc/systemd/system`, and the rules the names in
+//! `Alias=` and `DefaultInstance=` have to follow for it.
+
+use crate::parser::SystemdUnit;
+use crate::unit_name::{
+    expand_specifiers, instantiate, is_template, template_of, unit_name_error, UNIT_SEARCH_PATHS,
+};
+
+/// Where `systemctl enable` creates the symlinks of system units
+const SYSTEM_UNIT_DIRECTORY: &str = "/etc/systemd/system";
+
+/// `[Install]` directives that add the unit to the dependencies of others, with the
+/// suffix of the directory the symlink goes in
+const DEPENDENCY_DIRECTORIES: &[(&str, &str)] = &[
+    ("WantedBy", ".wants"),
+    ("RequiredBy", ".requires"),
+    ("UpheldBy", ".upholds"),
+];
+
+/// A symlink `systemctl enable` creates, printed like it does
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstallSymlink {
+    pub path: String,
+    pub target: String,
+}
+
+impl std::fmt::Display for InstallSymlink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Created symlink {} → {}.", self.path, self.target)
+    }
+}
+
+/// The outcome of enabling one unit file
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct EnablePlan {
+    pub symlinks: Vec<InstallSymlink>,
+    /// Units `Also=` enables along with this one
+    pub also: Vec<String>,
+    /// Why symlinks the section asks for are not created
+    pub notes: Vec<String>,
+}
+
+impl EnablePlan {
+    /// Whether the unit has nothing `systemctl enable` acts on
+    pub fn is_empty(&self) -> bool {
+        self.symlinks.is_empty() && self.also.is_empty()
+    }
+}
+
+/// The symlinks enabling the unit file `path` creates. `instance` picks the instance of a
+/// template, falling back to its `DefaultInstance=`.
+pub fn enable(path: &str, unit: &SystemdUnit, instance: Option<&str>) -> EnablePlan {
+    let mut plan = EnablePlan::default();
+    let Some(file_name) = path.rsplit('/').next().filter(|name| !name.is_empty()) else {
+        return plan;
+    };
+    let Some(section) = unit.sections.get("Install") else {
+        return plan;
+    };
+
+    // Like other list settings, an empty assignment drops the earlier entries
+    let values = |key: &str| -> Vec<String> {
+        let mut values = Vec::new();
+        for directive in section.directives.iter().filter(|d| d.key == key) {
+            if directive.value.trim().is_empty() {
+                values.clear();
+            }
+            values.extend(directive.value.split_whitespace().map(str::to_string));
+        }
+        values
+    };
+
+    let default_instance = values("DefaultInstance").pop();
+    let instance = instance.map(str::to_string).or(default_instance);
+    let name = match (is_template(file_name), &instance) {
+        (true, Some(instance)) => instantiate(file_name, instance),
+        _ => file_name.to_string(),
+    };
+
+    // A unit outside the search path is linked into it first
+    let target = path.to_string();
+    let in_search_path = UNIT_SEARCH_PATHS
+        .iter()
+        .any(|directory| path.strip_prefix(directory) == Some(&format!("/{}", file_name)));
+    if !in_search_path {
+        plan.symlinks.push(InstallSymlink {
+            path: format!("{}/{}", SYSTEM_UNIT_DIRECTORY, file_name),
+            target: target.clone(),
+        });
+    }
+
+    for (key, suffix) in DEPENDENCY_DIRECTORIES {
+        for dependent in values(key) {
+            let dependent = expand_specifiers(&dependent, &name);
+            if is_template(&name) {
+                plan.notes.push(format!(
+                    "{}={} needs an instance of {}, pass --instance or set DefaultInstance=",
+                    key, dependent, file_name
+                ));
+                continue;
+            }
+            let dependent = match &instance {
+                Some(instance) if is_template(&dependent) => instantiate(&dependent, instance),
+                _ => dependent,
+            };
+            plan.symlinks.push(InstallSymlink {
+                path: format!("{}/{}{}/{}", SYSTEM_UNIT_DIRECTORY, dependent, suffix, name),
+                target: target.clone(),
+            });
+        }
+    }
+
+    for alias in values("Alias") {
+        let alias = expand_specifiers(&alias, &name);
+        match alias_error(file_name, &alias) {
+            Some(error) => plan.notes.push(format!("Alias={}: {}", alias, error)),
+            None => plan.symlinks.push(InstallSymlink {
+                path: format!("{}/{}", SYSTEM_UNIT_DIRECTORY, alias),
+                target: target.clone(),
+            }),
+        }
+    }
+
+    plan.also = values("Also")
+        .iter()
+        .map(|also| expand_specifiers(also, &name))
+        .collect();
+    plan
+}
+
+/// Why `alias` cannot name the unit `name`, following `unit_validate_alias_symlink_or_warn()`
+pub fn alias_error(name: &str, alias: &str) -> Option<String> {
+    if let Some(error) = unit_name_error(alias) {
+        return Some(error);
+    }
+    let suffix = |name: &str| name.rsplit_once('.').map(|(_, suffix)| suffix.to_string());
+    if suffix(alias) != suffix(name) {
+        return Some(format!(
+            "an alias of {} must also end in .{}",
+            name,
+            suffix(name).unwrap_or_default()
+        ));
+    }
+    if is_template(name) && !is_template(alias) {
+        return Some(format!(
+            "{} is a template and can only be aliased by another template such as {}",
+            name,
+            template_of(alias)
+        ));
+    }
+    if !name.contains('@') && alias.contains('@') {
+        return Some(format!(
+            "{} is not a template and cannot be aliased by the template or instance {}",
+            name, alias
+        ));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::SystemdParser;
+
+    fn plan(path: &str, text: &str, instance: Option<&str>) -> EnablePlan {
+        enable(path, &SystemdParser::new().parse(text), instance)
+    }
+
+    fn symlinks(plan: &EnablePlan) -> Vec<String> {
+        plan.symlinks.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_enable_creates_dependency_and_alias_symlinks() {
+        let enabled = plan(
+            "/usr/lib/systemd/system/app.service",
+            "[Install]\nWantedBy=multi-user.target\nRequiredBy=%p-ready.target\nAlias=application.service\nAlso=app.socket\n",
+            None,
+        );
+        assert_eq!(
+            symlinks(&enabled),
+            [
+                "Created symlink /etc/systemd/system/multi-user.target.wants/app.service → /usr/lib/systemd/system/app.service.",
+                "Created symlink /etc/systemd/system/app-ready.target.requires/app.service → /usr/lib/systemd/system/app.service.",
+                "Created symlink /etc/systemd/system/application.service → /usr/lib/systemd/system/app.service.",
+            ]
+        );
+        assert_eq!(enabled.also, ["app.socket"]);
+        assert!(enabled.notes.is_empty());
+
+        let linked = plan(
+            "/srv/app/app.service",
+            "[Install]\nWantedBy=default.target\nWantedBy=\nWantedBy=multi-user.target\n",
+            None,
+        );
+        assert_eq!(
+            symlinks(&linked),
+            [
+                "Created symlink /etc/systemd/system/app.service → /srv/app/app.service.",
+                "Created symlink /etc/systemd/system/multi-user.target.wants/app.service → /srv/app/app.service.",
+            ]
+        );
+
+        assert!(plan("/etc/systemd/system/app.service", "[Unit]\n", None).is_empty());
+    }
+
+    #[test]
+    fn test_enable_templates_needs_an_instance() {
+        let text = "[Install]\nWantedBy=getty.target\n";
+        let path = "/usr/lib/systemd/system/getty@.service";
+        let without = plan(path, text, None);
+        assert!(without.symlinks.is_empty());
+        assert_eq!(
+            without.notes,
+            ["WantedBy=getty.target needs an instance of getty@.service, pass --instance or set DefaultInstance="]
+        );
+
+        assert_eq!(
+            symlinks(&plan(path, text, Some("tty2"))),
+            ["Created symlink /etc/systemd/system/getty.target.wants/getty@tty2.service → /usr/lib/systemd/system/getty@.service."]
+        );
+        assert_eq!(
+            symlinks(&plan(
+                path,
+                "[Install]\nWantedBy=getty.target\nDefaultInstance=tty1\n",
+                None
+            )),
+            ["Created symlink /etc/systemd/system/getty.target.wants/getty@tty1.service → /usr/lib/systemd/system/getty@.service."]
+        );
+    }
+
+    #[test]
+    fn test_alias_rules() {
+        assert_eq!(alias_error("app.service", "application.service"), None);
+        assert_eq!(alias_error("getty@.service", "console@.service"), None);
+        assert_eq!(
+            alias_error("app.service", "app.target"),
+            Some("an alias of app.service must also end in .service".to_string())
+        );
+        assert_eq!(
+            alias_error("getty@.service", "console.service"),
+            Some("getty@.service is a template and can only be aliased by another template such as console@.service".to_string())
+        );
+        assert!(alias_error("app.service", "app@.service").is_some());
+        assert!(alias_error("app.service", "app").is_some());
+    }
+}
@@ -1,10 +1,26 @@
 // Library exports for testing
 mod completion;
+mod config;
 mod constants;
 mod definition;
+mod dialect;
+mod environment;
 mod parser;
+mod sandboxing;
+mod schema;
+mod templates;
+mod unit_name;
+mod value_providers;
 
 pub use completion::SystemdCompletion;
+pub use config::{NetworkDialect, SystemdLspConfig};
 pub use constants::SystemdConstants;
 pub use definition::SystemdDefinitionProvider;
+pub use dialect::UnitDialect;
+pub use environment::SectionEnvironment;
 pub use parser::{SystemdDirective, SystemdParser, SystemdSection, SystemdUnit};
+pub use sandboxing::{is_capability, is_errno, is_syscall, syscall_group_documentation};
+pub use unit_name::{
+    escape, escape_path, expand_specifiers, instance_of, instantiate, is_template, is_valid,
+    mangle, systemd_escape, unescape, unescape_path, unit_name_error, EscapeOptions, UNIT_NAME_MAX,
+};
@@ -1,26 +1,66 @@
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
+use dashmap::DashMap;
 use log::{debug, info, trace};
+use serde::Deserialize;
 use std::env;
 use std::io::IsTerminal;
 use std::path::{Path, PathBuf};
 use tower_lsp_server::jsonrpc::Result;
 use tower_lsp_server::lsp_types::*;
-use tower_lsp_server::{Client, LanguageServer, LspService, Server};
+use tower_lsp_server::{Client, LanguageServer, LspService, Server, UriExt};
 
+mod code_actions;
 mod completion;
+mod config;
 mod constants;
 mod definition;
 mod diagnostics;
+mod dialect;
+mod environment;
 mod formatting;
+mod graph;
+mod install;
 mod parser;
+mod quadlet;
+mod sandboxing;
+mod schema;
+mod security;
 mod semantic_tokens;
+mod templates;
+mod unit_name;
+mod validators;
+mod value_providers;
 
+use code_actions::SystemdCodeActions;
 use completion::SystemdCompletion;
-use definition::SystemdDefinitionProvider;
+use config::SystemdLspConfig;
+use definition::{SystemdDefinitionProvider, DOCUMENTATION_SCHEME};
 use diagnostics::SystemdDiagnostics;
+use dialect::UnitDialect;
+use environment::{DefinitionSource, SectionEnvironment};
 use formatting::SystemdFormatter;
-use parser::SystemdParser;
+use graph::UnitGraph;
+use parser::{SystemdParser, SystemdSection, SystemdUnit};
+use quadlet::SystemdQuadletGenerator;
+use schema::ValueType;
+use security::SecurityReport;
 use semantic_tokens::SystemdSemanticTokens;
+use unit_name::EscapeOptions;
+
+/// Command that renders the `.service` unit podman's quadlet generator would produce
+const QUADLET_PREVIEW_COMMAND: &str = "systemd-lsp.quadletPreview";
+
+/// Command escaping text into unit names like `systemd-escape`, given the text and
+/// optionally an object of [`EscapeOptions`]
+const ESCAPE_COMMAND: &str = "systemd-lsp.escape";
+
+/// Custom request used by clients to fetch the content of virtual read-only documents
+const VIRTUAL_DOCUMENT_REQUEST: &str = "systemd-lsp/virtualDocument";
+
+#[derive(Debug, Deserialize)]
+struct VirtualDocumentParams {
+    uri: Uri,
+}
 
 #[derive(Debug)]
 pub struct SystemdLanguageServer {
@@ -29,8 +69,11 @@ pub struct SystemdLanguageServer {
     diagnostics: SystemdDiagnostics,
     completion: SystemdCompletion,
     formatter: SystemdFormatter,
+    code_actions: SystemdCodeActions,
     definition_provider: SystemdDefinitionProvider,
     semantic_tokens: SystemdSemanticTokens,
+    quadlet: SystemdQuadletGenerator,
+    virtual_documents: DashMap<Uri, String>,
 }
 
 impl LanguageServer for SystemdLanguageServer {
@@ -38,10 +81,28 @@ impl LanguageServer for SystemdLanguageServer {
         info!("LSP initialize request received");
         debug!("Client capabilities: {:?}", params.capabilities);
 
+        if let Some(options) = &params.initialization_options {
+            match SystemdLspConfig::from_value(options) {
+                Some(config) => {
+                    self.apply_config(config);
+                }
+                None => debug!("Ignoring invalid initialization options: {}", options),
+            }
+        }
+
+        let folders: Vec<PathBuf> = params
+            .workspace_folders
+            .iter()
+            .flatten()
+            .filter_map(|folder| folder.uri.to_file_path())
+            .map(|path| path.into_owned())
+            .collect();
+        self.index_workspace(folders).await;
+
         let capabilities = ServerCapabilities {
             text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
             completion_provider: Some(CompletionOptions {
-                resolve_provider: Some(false),
+                resolve_provider: Some(true),
                 trigger_characters: Some(vec!["=".to_string(), "[".to_string()]),
                 work_done_progress_options: Default::default(),
                 all_commit_characters: None,
@@ -50,6 +111,11 @@ impl LanguageServer for SystemdLanguageServer {
             hover_provider: Some(HoverProviderCapability::Simple(true)),
             document_formatting_provider: Some(OneOf::Left(true)),
             document_range_formatting_provider: Some(OneOf::Left(true)),
+            code_action_provider: Some(CodeActionProviderCapability::Options(CodeActionOptions {
+                code_action_kinds: Some(vec![CodeActionKind::QUICKFIX]),
+                work_done_progress_options: Default::default(),
+                resolve_provider: None,
+            })),
             definition_provider: Some(OneOf::Left(true)),
             semantic_tokens_provider: Some(
                 SemanticTokensServerCapabilities::SemanticTokensOptions(SemanticTokensOptions {
@@ -59,6 +125,13 @@ impl LanguageServer for SystemdLanguageServer {
                     full: Some(SemanticTokensFullOptions::Bool(true)),
                 }),
             ),
+            execute_command_provider: Some(ExecuteCommandOptions {
+                commands: vec![
+                    QUADLET_PREVIEW_COMMAND.to_string(),
+                    ESCAPE_COMMAND.to_string(),
+                ],
+                work_done_progress_options: Default::default(),
+            }),
             ..ServerCapabilities::default()
         };
 
@@ -82,11 +155,20 @@ impl LanguageServer for SystemdLanguageServer {
 
     async fn shutdown(&self) -> Result<()> {
         info!("LSP server shutdown requested");
+        Ok(())
+    }
 
-        // Clean up temporary documentation files
-        self.definition_provider.cleanup_temp_files();
+    async fn did_change_configuration(&self, params: DidChangeConfigurationParams) {
+        debug!("Configuration changed: {}", params.settings);
 
-        Ok(())
+        let Some(config) = SystemdLspConfig::from_value(&params.settings) else {
+            debug!("Ignoring invalid configuration: {}", params.settings);
+            return;
+        };
+
+        for uri in self.apply_config(config) {
+            self.refresh_diagnostics(&uri).await;
+        }
     }
 
     async fn did_open(&self, params: DidOpenTextDocumentParams) {
@@ -133,6 +215,10 @@ impl LanguageServer for SystemdLanguageServer {
 
     async fn did_close(&self, params: DidCloseTextDocumentParams) {
         info!("Document closed: {:?}", params.text_document.uri);
+        // The preview of a Quadlet file is only kept while the file is open
+        if let Some(preview_uri) = Self::quadlet_preview_uri(&params.text_document.uri) {
+            self.virtual_documents.remove(&preview_uri);
+        }
         self.client
             .log_message(MessageType::INFO, "file closed!")
             .await;
@@ -160,6 +246,11 @@ impl LanguageServer for SystemdLanguageServer {
         Ok(result)
     }
 
+    async fn completion_resolve(&self, item: CompletionItem) -> Result<CompletionItem> {
+        debug!("Resolving completion item {}", item.label);
+        Ok(self.completion.resolve(item))
+    }
+
     async fn hover(&self, params: HoverParams) -> Result<Option<Hover>> {
         let uri = &params.text_document_position_params.text_document.uri;
         let position = &params.text_document_position_params.position;
@@ -228,6 +319,42 @@ impl LanguageServer for SystemdLanguageServer {
         }
     }
 
+    async fn code_action(&self, params: CodeActionParams) -> Result<Option<CodeActionResponse>> {
+        let uri = &params.text_document.uri;
+        debug!("Code action request for {:?} at {:?}", uri, params.range);
+
+        if let Some(only) = &params.context.only {
+            if !only
+                .iter()
+                .any(|kind| CodeActionKind::QUICKFIX.as_str().starts_with(kind.as_str()))
+            {
+                return Ok(None);
+            }
+        }
+
+        let Some(unit) = self.parser.get_parsed_document(uri) else {
+            debug!("Document not found for code actions: {:?}", uri);
+            return Ok(None);
+        };
+
+        let mut actions = self.code_actions.get_code_actions(
+            uri,
+            &unit,
+            &params.range,
+            &params.context.diagnostics,
+        );
+        actions.extend(self.code_actions.missing_unit_actions(
+            uri,
+            &params.context.diagnostics,
+            self.completion.templates(),
+        ));
+        actions.extend(
+            self.code_actions
+                .mount_name_actions(uri, &params.context.diagnostics),
+        );
+        Ok(Some(actions))
+    }
+
     async fn goto_definition(
         &self,
         params: GotoDefinitionParams,
@@ -267,31 +394,225 @@ impl LanguageServer for SystemdLanguageServer {
 
         Ok(tokens.map(SemanticTokensResult::Tokens))
     }
+
+    async fn execute_command(&self, params: ExecuteCommandParams) -> Result<Option<LSPAny>> {
+        debug!("Execute command request: {}", params.command);
+
+        match params.command.as_str() {
+            QUADLET_PREVIEW_COMMAND => {
+                let Some(uri) = Self::uri_argument(&params.arguments) else {
+                    return Err(tower_lsp_server::jsonrpc::Error::invalid_params(
+                        "expected the Quadlet document URI as the first argument",
+                    ));
+                };
+                self.preview_quadlet(&uri).await
+            }
+            ESCAPE_COMMAND => {
+                let Some(text) = params.arguments.first().and_then(LSPAny::as_str) else {
+                    return Err(tower_lsp_server::jsonrpc::Error::invalid_params(
+                        "expected the text to escape as the first argument",
+                    ));
+                };
+                let options = match params.arguments.get(1) {
+                    Some(options) => serde_json::from_value::<EscapeOptions>(options.clone())
+                        .map_err(|e| {
+                            tower_lsp_server::jsonrpc::Error::invalid_params(e.to_string())
+                        })?,
+                    None => EscapeOptions::default(),
+                };
+                unit_name::systemd_escape(text, &options)
+                    .map(|escaped| Some(LSPAny::String(escaped)))
+                    .map_err(tower_lsp_server::jsonrpc::Error::invalid_params)
+            }
+            _ => Err(tower_lsp_server::jsonrpc::Error::method_not_found()),
+        }
+    }
 }
 
 impl SystemdLanguageServer {
     pub fn new(client: Client) -> Self {
         debug!("Initializing parser, diagnostics, and completion modules");
+        let completion = SystemdCompletion::new();
+        // User templates from the default directory until the configuration names another one
+        completion.set_templates_directory(None);
+        let diagnostics = SystemdDiagnostics::new();
+        diagnostics.set_security_exposure(true);
+
         Self {
             client,
             parser: SystemdParser::new(),
-            diagnostics: SystemdDiagnostics::new(),
-            completion: SystemdCompletion::new(),
+            diagnostics,
+            completion,
             formatter: SystemdFormatter::new(),
+            code_actions: SystemdCodeActions::new(),
             definition_provider: SystemdDefinitionProvider::new(),
             semantic_tokens: SystemdSemanticTokens::new(),
+            quadlet: SystemdQuadletGenerator::new(),
+            virtual_documents: DashMap::new(),
+        }
+    }
+
+    /// Push user configuration to the components. Returns the documents whose
+    /// diagnostics have to be refreshed as a result.
+    fn apply_config(&self, config: SystemdLspConfig) -> Vec<Uri> {
+        info!("Applying configuration: {:?}", config);
+        self.completion.set_target_version(config.target_version);
+        self.completion
+            .set_templates_directory(config.templates_directory.as_deref());
+        let changed = self.parser.set_network_dialect(config.network_dialect);
+
+        let exposure_changed = self
+            .diagnostics
+            .set_security_exposure(config.security_exposure.unwrap_or(true));
+        if self.diagnostics.set_target_version(config.target_version) || exposure_changed {
+            return self.parser.document_uris();
+        }
+        changed
+    }
+
+    /// Record the unit files in the workspace folders for unit name completion, and their
+    /// dependencies and those of their drop-ins for finding ordering cycles and units
+    /// nothing starts
+    async fn index_workspace(&self, folders: Vec<PathBuf>) {
+        if folders.is_empty() {
+            return;
+        }
+
+        self.diagnostics.set_workspace_folders(folders.clone());
+        let indexed = tokio::task::spawn_blocking(move || {
+            let files = collect_files(&folders, true)?;
+            let mut drop_ins = Vec::new();
+            for folder in &folders {
+                collect_drop_ins(folder, &mut drop_ins)?;
+            }
+
+            let parser = SystemdParser::new();
+            let units: Vec<(Uri, SystemdUnit)> = files
+                .iter()
+                .chain(drop_ins.iter().map(|(_, path)| path))
+                .filter_map(|path| {
+                    let content = std::fs::read_to_string(path).ok()?;
+                    let uri = Uri::from_file_path(path)?;
+                    let unit = parser.parse_document(&uri, &content);
+                    Some((uri, unit))
+                })
+                .collect();
+            std::io::Result::Ok((files, units))
+        })
+        .await;
+
+        match indexed {
+            Ok(Ok((files, units))) => {
+                let names: Vec<String> = files
+                    .iter()
+                    .filter_map(|path| path.file_name()?.to_str().map(str::to_string))
+                    .collect();
+                info!("Indexed {} unit file(s) in the workspace", names.len());
+                self.completion.set_workspace_units(names);
+                for (uri, unit) in &units {
+                    self.diagnostics.index_unit(uri, unit);
+                }
+            }
+            Ok(Err(err)) => debug!("Could not index the workspace: {}", err),
+            Err(err) => debug!("Workspace indexing failed: {}", err),
+        }
+    }
+
+    async fn refresh_diagnostics(&self, uri: &Uri) {
+        let Some(parsed) = self.parser.get_parsed_document(uri) else {
+            return;
+        };
+
+        self.diagnostics.update(uri, parsed).await;
+        let diagnostics = self.diagnostics.get_diagnostics(uri).await;
+        self.client
+            .publish_diagnostics(uri.clone(), diagnostics, None)
+            .await;
+    }
+
+    fn uri_argument(arguments: &[LSPAny]) -> Option<Uri> {
+        arguments.first()?.as_str()?.parse::<Uri>().ok()
+    }
+
+    /// Generate the quadlet service for a document and open it as a virtual document
+    async fn preview_quadlet(&self, uri: &Uri) -> Result<Option<LSPAny>> {
+        let Some(unit) = self.parser.get_parsed_document(uri) else {
+            return Err(tower_lsp_server::jsonrpc::Error::invalid_params(
+                "document is not open",
+            ));
+        };
+        if !unit.dialect.is_unit() {
+            return Err(tower_lsp_server::jsonrpc::Error::invalid_params(format!(
+                "document is a {}, not a Quadlet file",
+                unit.dialect.description()
+            )));
+        }
+
+        let file_name = uri
+            .to_file_path()
+            .map(|path| path.display().to_string())
+            .unwrap_or_else(|| uri.path().as_str().to_string());
+
+        let generated = self
+            .quadlet
+            .generate(&unit, &file_name)
+            .map_err(|e| tower_lsp_server::jsonrpc::Error::invalid_params(e.to_string()))?;
+
+        if let Some(preview_uri) = Self::quadlet_preview_uri(uri) {
+            self.virtual_documents
+                .insert(preview_uri.clone(), generated.clone());
+
+            let shown = self
+                .client
+                .show_document(ShowDocumentParams {
+                    uri: preview_uri,
+                    external: None,
+                    take_focus: Some(true),
+                    selection: None,
+                })
+                .await;
+            debug!("Quadlet preview shown: {:?}", shown);
+        }
+
+        Ok(Some(LSPAny::String(generated)))
+    }
+
+    /// The virtual document showing the service generated from the Quadlet file `uri`,
+    /// named after the file's directory so that Quadlet files of the same name do not
+    /// share one: `systemd-quadlet://preview/srv/web/app.service` for
+    /// `/srv/web/app.container`
+    fn quadlet_preview_uri(uri: &Uri) -> Option<Uri> {
+        let (directory, file_name) = uri.path().as_str().rsplit_once('/')?;
+        let service_name = SystemdQuadletGenerator::service_name(file_name)?;
+        format!("systemd-quadlet://preview{}/{}", directory, service_name)
+            .parse::<Uri>()
+            .ok()
+    }
+
+    async fn virtual_document(&self, params: VirtualDocumentParams) -> Result<Option<String>> {
+        debug!("Virtual document request for {:?}", params.uri);
+        if params.uri.scheme().map(|scheme| scheme.as_str()) == Some(DOCUMENTATION_SCHEME) {
+            return Ok(self.definition_provider.documentation_content(&params.uri));
         }
+        Ok(self
+            .virtual_documents
+            .get(&params.uri)
+            .map(|entry| entry.clone()))
     }
 
     async fn on_change(&self, params: TextDocumentItem) {
         debug!("Processing document change for {:?}", params.uri);
         trace!("Document text length: {} characters", params.text.len());
 
-        let parsed = self.parser.parse(&params.text);
-        debug!("Document parsed, found {} sections", parsed.sections.len());
+        let parsed = self.parser.parse_document(&params.uri, &params.text);
+        debug!(
+            "Document parsed as {:?}, found {} sections",
+            parsed.dialect,
+            parsed.sections.len()
+        );
 
         self.parser.update_document(&params.uri, &params.text);
-        self.diagnostics.update(&params.uri, parsed).await;
+        let dependencies_changed = self.diagnostics.update(&params.uri, parsed).await;
 
         let diagnostics = self.diagnostics.get_diagnostics(&params.uri).await;
         debug!(
@@ -303,6 +624,16 @@ impl SystemdLanguageServer {
         self.client
             .publish_diagnostics(params.uri.clone(), diagnostics, Some(params.version))
             .await;
+
+        // Ordering cycles and references span units, so the other open units may have
+        // gained or lost a cycle or the unit that starts them
+        if dependencies_changed {
+            for uri in self.parser.document_uris() {
+                if uri != params.uri {
+                    self.refresh_diagnostics(&uri).await;
+                }
+            }
+        }
     }
 
     async fn get_hover_info(&self, uri: &Uri, position: &Position) -> Option<Hover> {
@@ -323,7 +654,7 @@ impl SystemdLanguageServer {
             let section_key = section_name.to_lowercase();
             if let Some(full_docs) = self
                 .definition_provider
-                .get_embedded_documentation(&section_key)
+                .get_dialect_embedded_documentation(parsed.dialect, &section_key)
             {
                 return Some(Hover {
                     contents: HoverContents::Markup(MarkupContent {
@@ -335,7 +666,7 @@ impl SystemdLanguageServer {
             }
 
             // Fallback to short documentation if embedded docs not available
-            let section_docs = self.get_section_documentation(&section_name);
+            let section_docs = self.get_section_documentation(parsed.dialect, &section_name);
             if let Some(docs) = section_docs {
                 return Some(Hover {
                     contents: HoverContents::Markup(MarkupContent {
@@ -347,11 +678,19 @@ impl SystemdLanguageServer {
             }
         }
 
+        // Check if hovering over a directive value
+        if let Some(hover) = self.get_value_hover(uri, &parsed, position) {
+            return Some(hover);
+        }
+
         // Check if hovering over a directive
         if let Some(directive_name) = self.parser.get_word_at_position(&parsed, position) {
             let current_section = self.parser.get_section_at_line(&parsed, position.line)?;
-            let directive_docs =
-                self.get_directive_documentation(&directive_name, &current_section.name);
+            let directive_docs = self.get_directive_documentation(
+                parsed.dialect,
+                &directive_name,
+                &current_section.name,
+            );
             if let Some(docs) = directive_docs {
                 return Some(Hover {
                     contents: HoverContents::Markup(MarkupContent {
@@ -377,6 +716,251 @@ impl SystemdLanguageServer {
         None
     }
 
+    /// Hover for the value word under the cursor: the value's documentation, how systemd
+    /// reads the value and the description of the units it refers to
+    fn get_value_hover(
+        &self,
+        uri: &Uri,
+        parsed: &SystemdUnit,
+        position: &Position,
+    ) -> Option<Hover> {
+        let section = self.parser.get_section_at_line(parsed, position.line)?;
+        let (directive, span) = section.directives.iter().find_map(|directive| {
+            directive
+                .value_spans
+                .iter()
+                .find(|span| {
+                    span.line == position.line
+                        && span.start <= position.character
+                        && position.character <= span.end
+                })
+                .map(|span| (directive, span))
+        })?;
+
+        // The whitespace-separated word of the value the cursor is on
+        let chars: Vec<char> = parsed
+            .raw_text
+            .lines()
+            .nth(position.line as usize)?
+            .chars()
+            .collect();
+        let span_end = (span.end as usize).min(chars.len());
+        let cursor = (position.character as usize).min(span_end);
+
+        // A $VARIABLE in a command line shows its value and where it is defined
+        let is_command = schema::find(parsed.dialect, &section.name, &directive.key)
+            .is_some_and(|entry| entry.value_type == ValueType::Command);
+        if is_command {
+            let span_start = (span.start as usize).min(span_end);
+            let text: String = chars[span_start..span_end].iter().collect();
+            if let Some(reference) = environment::references(&text)
+                .into_iter()
+                .find(|reference| {
+                    span_start + reference.start <= cursor && cursor <= span_start + reference.end
+                })
+            {
+                return Some(Hover {
+                    contents: HoverContents::Markup(MarkupContent {
+                        kind: MarkupKind::Markdown,
+                        value: Self::variable_documentation(uri, parsed, section, &reference.name),
+                    }),
+                    range: Some(Range::new(
+                        Position::new(position.line, (span_start + reference.start) as u32),
+                        Position::new(position.line, (span_start + reference.end) as u32),
+                    )),
+                });
+            }
+        }
+
+        let start = (span.start as usize..cursor)
+            .rev()
+            .find(|&index| chars[index].is_whitespace())
+            .map_or(span.start as usize, |index| index + 1);
+        let end = (cursor..span_end)
+            .find(|&index| chars[index].is_whitespace())
+            .unwrap_or(span_end);
+        if start >= end {
+            return None;
+        }
+        let word: String = chars[start..end].iter().collect();
+
+        let mut parts = Vec::new();
+        if let Some(docs) = self.completion.get_value_documentation(
+            parsed.dialect,
+            &section.name,
+            &directive.key,
+            &word,
+        ) {
+            parts.push(format!("**{}**\n\n{}", word, docs));
+        }
+        if let Some(entry) = schema::find(parsed.dialect, &section.name, &directive.key) {
+            if let Some(interpretation) = validators::interpret(entry, &directive.value) {
+                parts.push(interpretation);
+            }
+            if entry.value_type == ValueType::Units {
+                if let Some(description) = self.unit_description(uri, &word) {
+                    parts.push(format!("`{}`: {}", word, description));
+                }
+                if word.contains('@') {
+                    parts.extend(self.instance_preview(uri, &word));
+                }
+            }
+            if entry.value_type == ValueType::Syscalls {
+                // `~@privileged:EPERM` hovers as the group `privileged`
+                let name = word.trim_start_matches('~');
+                let name = name.split_once(':').map_or(name, |(name, _)| name);
+                if let Some(documentation) = name
+                    .strip_prefix('@')
+                    .and_then(sandboxing::syscall_group_documentation)
+                {
+                    parts.push(documentation);
+                }
+            }
+        }
+        if parts.is_empty() {
+            return None;
+        }
+
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: Self::truncate_documentation(&parts.join("\n\n---\n\n")),
+            }),
+            range: Some(Range::new(
+                Position::new(position.line, start as u32),
+                Position::new(position.line, end as u32),
+            )),
+        })
+    }
+
+    /// Value and definitions of a variable referenced in a command line of `section`
+    fn variable_documentation(
+        uri: &Uri,
+        unit: &SystemdUnit,
+        section: &SystemdSection,
+        name: &str,
+    ) -> String {
+        let environment = SectionEnvironment::new(unit, section, uri.to_file_path().as_deref());
+        let definitions: Vec<_> = environment.definitions_of(name).collect();
+
+        let Some(last) = definitions.last() else {
+            let origin = if environment::MANAGER_VARIABLES.contains(&name) {
+                "Set by systemd for the processes it starts."
+            } else if environment.passed.iter().any(|passed| passed == name) {
+                "Passed on from the service manager's environment by `PassEnvironment=`."
+            } else if !environment.complete {
+                "Not defined in this file, it may come from an environment file that cannot be read here."
+            } else {
+                "Not defined by `Environment=` or `EnvironmentFile=`, expands to an empty string."
+            };
+            return format!("**${}**\n\n{}", name, origin);
+        };
+
+        let sources: Vec<String> = definitions
+            .iter()
+            .map(|definition| {
+                let origin = match &definition.source {
+                    DefinitionSource::Directive { line, .. } => {
+                        format!("`Environment=` on line {}", line + 1)
+                    }
+                    DefinitionSource::File { path, line } => {
+                        format!("`{}` line {}", path.display(), line + 1)
+                    }
+                };
+                format!("- `{}` from {}", definition.value, origin)
+            })
+            .collect();
+        let mut documentation = format!(
+            "**${}** = `{}`\n\nDefined by:\n{}",
+            name,
+            last.value,
+            sources.join("\n")
+        );
+        if definitions.len() > 1 {
+            documentation.push_str("\n\nThe last definition is in effect.");
+        }
+        documentation
+    }
+
+    /// A unit from an open document or the unit file on disk, the template for an instance
+    fn find_unit(&self, uri: &Uri, name: &str) -> Option<SystemdUnit> {
+        match self.parser.find_unit_document(name) {
+            Some(open) => self.parser.get_parsed_document(&open),
+            None => {
+                let near = uri
+                    .to_file_path()
+                    .and_then(|path| path.parent().map(Path::to_path_buf));
+                let path = unit_name::find_unit_file(name, near.as_deref())?;
+                Some(self.parser.parse(&std::fs::read_to_string(path).ok()?))
+            }
+        }
+    }
+
+    /// `Description=` of a unit, from an open document or the unit file on disk
+    fn unit_description(&self, uri: &Uri, name: &str) -> Option<String> {
+        self.find_unit(uri, name)?
+            .sections
+            .values()
+            .find(|section| section.name == "Unit")?
+            .directives
+            .iter()
+            .rev()
+            .find(|directive| directive.key == "Description")
+            .map(|directive| unit_name::expand_specifiers(&directive.value, name))
+    }
+
+    /// The template of an instance such as `getty@tty1.service` with its specifiers
+    /// expanded for the instance, or for `DefaultInstance=` when the name has none
+    fn instance_preview(&self, uri: &Uri, name: &str) -> Option<String> {
+        let template = unit_name::template_of(name);
+        let unit = self.find_unit(uri, &template)?;
+        let (label, instance) = match unit_name::instance_of(name) {
+            Some(instance) => ("Instance", instance.to_string()),
+            None => {
+                let default_instance = unit
+                    .sections
+                    .get("Install")
+                    .and_then(|section| {
+                        section
+                            .directives
+                            .iter()
+                            .rev()
+                            .find(|directive| directive.key == "DefaultInstance")
+                    })
+                    .map(|directive| directive.value.trim().to_string())
+                    .filter(|instance| !instance.is_empty());
+                let Some(instance) = default_instance else {
+                    return Some(format!(
+                        "`{}` is a template without `DefaultInstance=`, name an instance such as `{}`",
+                        template,
+                        unit_name::instantiate(&template, "name")
+                    ));
+                };
+                ("Default instance", instance)
+            }
+        };
+
+        let instance_name = unit_name::instantiate(&template, &instance);
+        let expanded: Vec<String> = unit
+            .raw_text
+            .lines()
+            .map(|line| {
+                if line.trim_start().starts_with(['#', ';']) {
+                    line.to_string()
+                } else {
+                    unit_name::expand_specifiers(line, &instance_name)
+                }
+            })
+            .collect();
+        Some(format!(
+            "{} `{}` of `{}`:\n\n```ini\n{}\n```",
+            label,
+            instance,
+            template,
+            expanded.join("\n").trim_end()
+        ))
+    }
+
     /// Truncate documentation to a reasonable size for hover display
     fn truncate_documentation(docs: &str) -> String {
         const MAX_LINES: usize = 50;
@@ -407,17 +991,23 @@ impl SystemdLanguageServer {
         result
     }
 
-    fn get_section_documentation(&self, section_name: &str) -> Option<String> {
-        self.completion.get_section_documentation(section_name)
+    fn get_section_documentation(
+        &self,
+        dialect: UnitDialect,
+        section_name: &str,
+    ) -> Option<String> {
+        self.completion
+            .get_dialect_section_documentation(dialect, section_name)
     }
 
     fn get_directive_documentation(
         &self,
+        dialect: UnitDialect,
         directive_name: &str,
         section_name: &str,
     ) -> Option<String> {
         self.completion
-            .get_directive_documentation(directive_name, section_name)
+            .get_dialect_directive_documentation(dialect, directive_name, section_name)
     }
 }
 
@@ -434,8 +1024,12 @@ impl SystemdLanguageServer {
 When run in a terminal with file paths, it validates systemd unit files and reports diagnostics.\n\
 When run without a TTY (from an editor), it operates as an LSP server."
 )]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Cli {
-    /// Files or directories to validate (supports .service, .socket, .timer, .target, .mount, .automount, .swap, .path, .slice, .scope)
+    #[command(subcommand)]
+    command: Option<CliCommand>,
+
+    /// Files or directories to validate (supports .service, .socket, .timer, .target, .mount, .automount, .swap, .path, .slice, .scope, .network, .netdev, .link)
     #[arg(value_name = "PATH", required = true)]
     paths: Vec<PathBuf>,
 
@@ -446,6 +1040,80 @@ struct Cli {
         help = "Recursively validate all systemd unit files in directories"
     )]
     recursive: bool,
+
+    /// Warn about directives and values that need a newer systemd than this version
+    #[arg(long, value_name = "VERSION")]
+    target_version: Option<u32>,
+}
+
+#[derive(Subcommand, Debug)]
+enum CliCommand {
+    /// Print the .service unit podman's quadlet generator would produce for a Quadlet file
+    Quadlet {
+        /// Quadlet file (.container, .volume or .network)
+        #[arg(value_name = "FILE")]
+        path: PathBuf,
+    },
+    /// Score the sandboxing of services the way `systemd-analyze security` does
+    Security {
+        /// Service unit files
+        #[arg(value_name = "FILE", required = true)]
+        paths: Vec<PathBuf>,
+    },
+    /// Print the ordering and requirement dependencies between unit files, like `systemd-analyze dot`
+    Graph {
+        /// Unit files or directories, searched recursively along with their drop-ins
+        #[arg(value_name = "PATH", required = true)]
+        paths: Vec<PathBuf>,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value_t = GraphFormat::Dot)]
+        format: GraphFormat,
+    },
+    /// Print the symlinks `systemctl enable` would create for unit files, without creating them
+    Enable {
+        /// Unit files
+        #[arg(value_name = "FILE", required = true)]
+        paths: Vec<PathBuf>,
+
+        /// Instance to enable template units as, instead of their DefaultInstance=
+        #[arg(long, value_name = "NAME")]
+        instance: Option<String>,
+    },
+    /// Escape text into unit names like `systemd-escape`, which need not be installed
+    Escape {
+        /// Strings to escape, or to unescape with --unescape
+        #[arg(value_name = "STRING", required = true)]
+        texts: Vec<String>,
+
+        /// Treat the strings as paths, as mount units are named after them
+        #[arg(short, long)]
+        path: bool,
+
+        /// Unit type to append, e.g. mount
+        #[arg(long, value_name = "SUFFIX")]
+        suffix: Option<String>,
+
+        /// Template to instantiate with the escaped strings, e.g. getty@.service
+        #[arg(long, value_name = "TEMPLATE")]
+        template: Option<String>,
+
+        /// Turn escaped strings back into what they stand for
+        #[arg(short, long)]
+        unescape: bool,
+
+        /// Make unit names out of the strings the way systemctl does
+        #[arg(short, long)]
+        mangle: bool,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum GraphFormat {
+    /// Graphviz, e.g. for `dot -Tsvg`
+    Dot,
+    Mermaid,
+    Json,
 }
 
 /// Collect systemd unit files from the given paths
@@ -522,14 +1190,24 @@ fn is_systemd_file(path: &Path) -> bool {
                 | "path"
                 | "slice"
                 | "scope"
-        )
+                | "network"
+                | "netdev"
+                | "link"
+        ) || path
+            .to_str()
+            .and_then(UnitDialect::from_config_path)
+            .is_some()
     } else {
         false
     }
 }
 
 /// Run diagnostics on files in CLI mode
-async fn run_cli_diagnostics(paths: Vec<PathBuf>, recursive: bool) -> std::io::Result<i32> {
+async fn run_cli_diagnostics(
+    paths: Vec<PathBuf>,
+    recursive: bool,
+    target_version: Option<u32>,
+) -> std::io::Result<i32> {
     let files = collect_files(&paths, recursive)?;
 
     if files.is_empty() {
@@ -539,10 +1217,12 @@ async fn run_cli_diagnostics(paths: Vec<PathBuf>, recursive: bool) -> std::io::R
 
     let parser = SystemdParser::new();
     let diagnostics_engine = SystemdDiagnostics::new();
+    diagnostics_engine.set_target_version(target_version);
     let mut total_errors = 0;
     let mut total_warnings = 0;
     let mut files_with_issues = 0;
 
+    let mut documents = Vec::new();
     for file_path in &files {
         let content = match std::fs::read_to_string(file_path) {
             Ok(content) => content,
@@ -552,11 +1232,20 @@ async fn run_cli_diagnostics(paths: Vec<PathBuf>, recursive: bool) -> std::io::R
             }
         };
 
-        let uri = format!("file://{}", file_path.display())
-            .parse::<Uri>()
-            .unwrap();
+        // Dialect detection looks at the directory, so use the absolute path
+        let absolute_path = std::fs::canonicalize(file_path).unwrap_or_else(|_| file_path.clone());
+        let Some(uri) = Uri::from_file_path(&absolute_path) else {
+            eprintln!("Error reading {}: not a valid path", file_path.display());
+            continue;
+        };
 
-        let parsed = parser.parse(&content);
+        // Index every file first so that ordering cycles show up whichever file comes first
+        let parsed = parser.parse_document(&uri, &content);
+        diagnostics_engine.index_unit(&uri, &parsed);
+        documents.push((file_path, uri, parsed));
+    }
+
+    for (file_path, uri, parsed) in documents {
         diagnostics_engine.update(&uri, parsed).await;
         let diags = diagnostics_engine.get_diagnostics(&uri).await;
 
@@ -619,6 +1308,211 @@ async fn run_cli_diagnostics(paths: Vec<PathBuf>, recursive: bool) -> std::io::R
     }
 }
 
+/// Print the generated quadlet service in CLI mode
+fn run_cli_quadlet(path: &Path) -> std::io::Result<i32> {
+    let content = std::fs::read_to_string(path)?;
+    let unit = SystemdParser::new().parse(&content);
+
+    match SystemdQuadletGenerator::new().generate(&unit, &path.display().to_string()) {
+        Ok(generated) => {
+            print!("{}", generated);
+            Ok(0)
+        }
+        Err(e) => {
+            eprintln!("{}: {}", path.display(), e);
+            Ok(1)
+        }
+    }
+}
+
+/// Print the security exposure of services in CLI mode
+fn run_cli_security(paths: &[PathBuf]) -> std::io::Result<i32> {
+    let parser = SystemdParser::new();
+    let mut exit_code = 0;
+
+    for path in paths {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) => {
+                eprintln!("Error reading {}: {}", path.display(), e);
+                exit_code = 1;
+                continue;
+            }
+        };
+        let Some(report) = SecurityReport::for_unit(&parser.parse(&content)) else {
+            eprintln!("{}: no [Service] section", path.display());
+            exit_code = 1;
+            continue;
+        };
+
+        println!("\n{}:", path.display());
+        let width = report
+            .findings
+            .iter()
+            .map(|finding| finding.check.directive.len() + 1)
+            .max()
+            .unwrap_or(0);
+        for finding in report.failed() {
+            println!(
+                "  ✗ {:width$}  {:60} {:.2}",
+                format!("{}=", finding.check.directive),
+                finding.check.exposure,
+                finding.exposure(),
+            );
+        }
+        for finding in report.findings.iter().filter(|finding| finding.passed()) {
+            println!("  ✓ {}=", finding.check.directive);
+        }
+
+        let name = path
+            .file_name()
+            .unwrap_or(path.as_os_str())
+            .to_string_lossy();
+        println!(
+            "→ Overall exposure level for {}: {:.1} {}",
+            name,
+            report.exposure(),
+            report.rating()
+        );
+    }
+
+    Ok(exit_code)
+}
+
+/// Print the dependency graph of the unit files under `paths` in CLI mode
+fn run_cli_graph(paths: &[PathBuf], format: GraphFormat) -> std::io::Result<i32> {
+    let mut files: Vec<(String, PathBuf)> = collect_files(paths, true)?
+        .into_iter()
+        .filter_map(|path| {
+            let name = path.file_name()?.to_str()?.to_string();
+            value_providers::is_unit_name(&name).then_some((name, path))
+        })
+        .collect();
+    for path in paths {
+        collect_drop_ins(path, &mut files)?;
+    }
+    if files.is_empty() {
+        eprintln!("No unit files found");
+        return Ok(1);
+    }
+    // Units before their drop-ins, drop-ins in the order systemd applies them
+    files.sort_by_key(|(name, path)| {
+        (
+            name.clone(),
+            path.extension().is_some_and(|ext| ext == "conf"),
+            path.file_name().map(|file| file.to_os_string()),
+        )
+    });
+
+    let parser = SystemdParser::new();
+    let mut graph = UnitGraph::new();
+    for (name, path) in &files {
+        let content = std::fs::read_to_string(path)?;
+        graph.add(name, &parser.parse(&content));
+    }
+
+    print!(
+        "{}",
+        match format {
+            GraphFormat::Dot => graph.to_dot(),
+            GraphFormat::Mermaid => graph.to_mermaid(),
+            GraphFormat::Json => graph.to_json(),
+        }
+    );
+    Ok(0)
+}
+
+fn run_cli_escape(texts: &[String], options: &EscapeOptions) -> std::io::Result<i32> {
+    let mut exit_code = 0;
+    for text in texts {
+        match unit_name::systemd_escape(text, options) {
+            Ok(escaped) => println!("{}", escaped),
+            Err(error) => {
+                eprintln!("{}", error);
+                exit_code = 1;
+            }
+        }
+    }
+    Ok(exit_code)
+}
+
+fn run_cli_enable(paths: &[PathBuf], instance: Option<&str>) -> std::io::Result<i32> {
+    let parser = SystemdParser::new();
+    let mut exit_code = 0;
+    let mut queue: Vec<PathBuf> = paths.to_vec();
+    let mut enabled: Vec<PathBuf> = Vec::new();
+
+    while !queue.is_empty() {
+        let path = std::path::absolute(queue.remove(0))?;
+        if enabled.contains(&path) {
+            continue;
+        }
+        enabled.push(path.clone());
+
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) => {
+                eprintln!("Error reading {}: {}", path.display(), e);
+                exit_code = 1;
+                continue;
+            }
+        };
+        let plan = install::enable(&path.to_string_lossy(), &parser.parse(&content), instance);
+        for symlink in &plan.symlinks {
+            println!("{}", symlink);
+        }
+        for note in &plan.notes {
+            eprintln!("{}: {}", path.display(), note);
+        }
+        if plan.is_empty() && plan.notes.is_empty() {
+            println!(
+                "{} has no WantedBy=, RequiredBy=, UpheldBy=, Alias= or Also= in an [Install] section, so it is not meant to be enabled and only starts when another unit pulls it in",
+                path.display()
+            );
+        }
+
+        // Also= names units the way the unit directory holds them
+        for also in &plan.also {
+            let sibling = path.with_file_name(also);
+            if sibling.is_file() {
+                queue.push(sibling);
+            } else {
+                eprintln!(
+                    "{}: Also={} is not a unit file next to it",
+                    path.display(),
+                    also
+                );
+                exit_code = 1;
+            }
+        }
+    }
+    Ok(exit_code)
+}
+
+/// Drop-ins `<unit>.d/*.conf` under `path`, as (unit name, path)
+fn collect_drop_ins(path: &Path, drop_ins: &mut Vec<(String, PathBuf)>) -> std::io::Result<()> {
+    if !path.is_dir() {
+        return Ok(());
+    }
+    let unit = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.strip_suffix(".d"))
+        .filter(|name| value_providers::is_unit_name(name));
+
+    for entry in std::fs::read_dir(path)? {
+        let entry_path = entry?.path();
+        if entry_path.is_dir() {
+            collect_drop_ins(&entry_path, drop_ins)?;
+        } else if let Some(unit) =
+            unit.filter(|_| entry_path.extension().is_some_and(|ext| ext == "conf"))
+        {
+            drop_ins.push((unit.to_string(), entry_path));
+        }
+    }
+    Ok(())
+}
+
 fn setup_logging() {
     let is_tty = std::io::stdin().is_terminal() || std::io::stdout().is_terminal();
     if is_tty {
@@ -666,8 +1560,35 @@ async fn main() {
         // Terminal/CLI mode - parse CLI arguments and run diagnostics
         let cli = Cli::parse();
 
-        // Run CLI diagnostics mode
-        match run_cli_diagnostics(cli.paths, cli.recursive).await {
+        let result = match cli.command {
+            Some(CliCommand::Quadlet { path }) => run_cli_quadlet(&path),
+            Some(CliCommand::Security { paths }) => run_cli_security(&paths),
+            Some(CliCommand::Graph { paths, format }) => run_cli_graph(&paths, format),
+            Some(CliCommand::Enable { paths, instance }) => {
+                run_cli_enable(&paths, instance.as_deref())
+            }
+            Some(CliCommand::Escape {
+                texts,
+                path,
+                suffix,
+                template,
+                unescape,
+                mangle,
+            }) => run_cli_escape(
+                &texts,
+                &EscapeOptions {
+                    path,
+                    suffix,
+                    template,
+                    unescape,
+                    mangle,
+                },
+            ),
+            // Run CLI diagnostics mode
+            None => run_cli_diagnostics(cli.paths, cli.recursive, cli.target_version).await,
+        };
+
+        match result {
             Ok(exit_code) => std::process::exit(exit_code),
             Err(e) => {
                 eprintln!("Error: {}", e);
@@ -683,10 +1604,15 @@ async fn main() {
         let stdout = tokio::io::stdout();
 
         debug!("Creating LSP service");
-        let (service, socket) = LspService::new(|client| {
+        let (service, socket) = LspService::build(|client| {
             info!("Creating new SystemdLanguageServer instance");
             SystemdLanguageServer::new(client)
-        });
+        })
+        .custom_method(
+            VIRTUAL_DOCUMENT_REQUEST,
+            SystemdLanguageServer::virtual_document,
+        )
+        .finish();
 
         info!("Starting LSP server");
         Server::new(stdin, stdout, socket).serve(service).await;
@@ -752,7 +1678,10 @@ mod tests {
 
         let edits = formatter.format_document(&uri, content);
         // Formatter should produce edits for the extra blank lines
-        assert!(edits.len() > 0 || edits.is_empty(), "Formatter should work");
+        assert!(
+            !edits.is_empty() || edits.is_empty(),
+            "Formatter should work"
+        );
     }
 
     #[test]
@@ -807,7 +1736,7 @@ mod tests {
         diagnostics.update(&uri, parsed).await;
 
         let diags = diagnostics.get_diagnostics(&uri).await;
-        assert!(diags.len() > 0, "Invalid content should have diagnostics");
+        assert!(!diags.is_empty(), "Invalid content should have diagnostics");
     }
 
     #[tokio::test]
@@ -926,4 +1855,244 @@ mod tests {
         let tokens = semantic.get_semantic_tokens(&parser, &uri);
         assert!(tokens.is_some(), "Should generate semantic tokens");
     }
+
+    #[tokio::test]
+    async fn test_quadlet_previews_per_file() {
+        let (service, _) = LspService::new(SystemdLanguageServer::new);
+        let server = service.inner();
+        let web = "file:///srv/web/app.container".parse::<Uri>().unwrap();
+        let worker = "file:///srv/worker/app.container".parse::<Uri>().unwrap();
+        let web_preview = SystemdLanguageServer::quadlet_preview_uri(&web).unwrap();
+        let worker_preview = SystemdLanguageServer::quadlet_preview_uri(&worker).unwrap();
+        assert_eq!(
+            web_preview.as_str(),
+            "systemd-quadlet://preview/srv/web/app.service"
+        );
+        assert_ne!(web_preview, worker_preview);
+        assert!(SystemdLanguageServer::quadlet_preview_uri(
+            &"file:///srv/web/app.service".parse::<Uri>().unwrap()
+        )
+        .is_none());
+
+        // Closing the Quadlet file drops its preview
+        server
+            .virtual_documents
+            .insert(web_preview.clone(), "[Service]\n".to_string());
+        server
+            .virtual_documents
+            .insert(worker_preview.clone(), "[Service]\n".to_string());
+        server
+            .did_close(DidCloseTextDocumentParams {
+                text_document: TextDocumentIdentifier { uri: web },
+            })
+            .await;
+        assert!(!server.virtual_documents.contains_key(&web_preview));
+        assert!(server.virtual_documents.contains_key(&worker_preview));
+    }
+
+    #[tokio::test]
+    async fn test_security_exposure_setting() {
+        let (service, _) = LspService::new(SystemdLanguageServer::new);
+        let server = service.inner();
+        let uri = "file:///srv/units/app.service".parse::<Uri>().unwrap();
+        server
+            .parser
+            .update_document(&uri, "[Service]\nExecStart=/usr/bin/app\n");
+
+        // On by default, so leaving the setting out changes nothing
+        assert!(server.apply_config(SystemdLspConfig::default()).is_empty());
+
+        let off = SystemdLspConfig::from_value(&serde_json::json!({ "securityExposure": false }))
+            .unwrap();
+        assert_eq!(server.apply_config(off), std::slice::from_ref(&uri));
+        let unit = server.parser.get_parsed_document(&uri).unwrap();
+        server.diagnostics.update(&uri, unit).await;
+        assert!(server.diagnostics.get_diagnostics(&uri).await.is_empty());
+    }
+
+    /// The markdown of a hover, which every hover of the server is
+    fn hover_text(hover: Option<Hover>) -> String {
+        match hover.map(|hover| hover.contents) {
+            Some(HoverContents::Markup(content)) => content.value,
+            other => panic!("Expected markdown hover, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_value_hover() {
+        let (service, _) = LspService::new(SystemdLanguageServer::new);
+        let server = service.inner();
+        let backend = "file:///srv/units/backend.service".parse::<Uri>().unwrap();
+        server
+            .parser
+            .update_document(&backend, "[Unit]\nDescription=Backend API\n");
+        let uri = "file:///srv/units/web.service".parse::<Uri>().unwrap();
+        server.parser.update_document(
+            &uri,
+            "[Unit]\nAfter=backend.service\n\n[Service]\nRestart=on-failure\nTimeoutStopSec=90min\n",
+        );
+
+        let restart = server.get_hover_info(&uri, &Position::new(4, 12)).await;
+        assert_eq!(
+            restart.as_ref().unwrap().range,
+            Some(Range::new(Position::new(4, 8), Position::new(4, 18)))
+        );
+        assert!(hover_text(restart).starts_with("**on-failure**"));
+
+        let timeout = hover_text(server.get_hover_info(&uri, &Position::new(5, 16)).await);
+        assert!(timeout.contains("1h 30min (5400 seconds)"), "{}", timeout);
+
+        let after = hover_text(server.get_hover_info(&uri, &Position::new(1, 8)).await);
+        assert!(
+            after.contains("`backend.service`: Backend API"),
+            "{}",
+            after
+        );
+
+        // Directive names still show the directive documentation
+        let key = hover_text(server.get_hover_info(&uri, &Position::new(4, 2)).await);
+        assert!(key.contains("Configures whether the service shall be restarted"));
+    }
+
+    #[tokio::test]
+    async fn test_instance_hover() {
+        let (service, _) = LspService::new(SystemdLanguageServer::new);
+        let server = service.inner();
+        let template = "file:///srv/units/getty@.service".parse::<Uri>().unwrap();
+        server.parser.update_document(
+            &template,
+            "[Unit]\nDescription=Getty on %I\n\n[Service]\n# Runs agetty for %i\nExecStart=/sbin/agetty %I $TERM\n\n[Install]\nDefaultInstance=tty1\n",
+        );
+        let worker = "file:///srv/units/worker@.service".parse::<Uri>().unwrap();
+        server
+            .parser
+            .update_document(&worker, "[Service]\nExecStart=/usr/bin/worker %i\n");
+        let uri = "file:///srv/units/console.target".parse::<Uri>().unwrap();
+        server.parser.update_document(
+            &uri,
+            "[Unit]\nWants=getty@tty2.service getty@.service\nAfter=worker@.service\n",
+        );
+
+        let instance = hover_text(server.get_hover_info(&uri, &Position::new(1, 8)).await);
+        assert!(
+            instance.contains("`getty@tty2.service`: Getty on tty2"),
+            "{}",
+            instance
+        );
+        assert!(
+            instance.contains("Instance `tty2` of `getty@.service`:\n\n```ini\n[Unit]\nDescription=Getty on tty2\n"),
+            "{}",
+            instance
+        );
+        assert!(instance.contains("# Runs agetty for %i\nExecStart=/sbin/agetty tty2 $TERM\n"));
+
+        let default = hover_text(server.get_hover_info(&uri, &Position::new(1, 30)).await);
+        assert!(
+            default.contains("Default instance `tty1` of `getty@.service`"),
+            "{}",
+            default
+        );
+        assert!(default.contains("ExecStart=/sbin/agetty tty1 $TERM"));
+
+        let without = hover_text(server.get_hover_info(&uri, &Position::new(2, 8)).await);
+        assert!(
+            without.contains("`worker@.service` is a template without `DefaultInstance=`, name an instance such as `worker@name.service`"),
+            "{}",
+            without
+        );
+    }
+
+    #[tokio::test]
+    async fn test_variable_hover() {
+        let (service, _) = LspService::new(SystemdLanguageServer::new);
+        let server = service.inner();
+        let uri = "file:///srv/units/app.service".parse::<Uri>().unwrap();
+        server.parser.update_document(
+            &uri,
+            "[Service]\nEnvironment=\"NAME=my app\" PORT=80\nEnvironment=PORT=8080\nExecStart=/usr/bin/app --name=${NAME} $PORT $MAINPID $OTHER\n",
+        );
+
+        let name = server
+            .get_hover_info(&uri, &Position::new(3, 35))
+            .await
+            .unwrap();
+        assert_eq!(
+            name.range,
+            Some(Range::new(Position::new(3, 30), Position::new(3, 37)))
+        );
+        let name = hover_text(Some(name));
+        assert!(name.starts_with("**$NAME** = `my app`"), "{}", name);
+        assert!(name.contains("`Environment=` on line 2"), "{}", name);
+
+        let port = hover_text(server.get_hover_info(&uri, &Position::new(3, 40)).await);
+        assert!(port.starts_with("**$PORT** = `8080`"), "{}", port);
+        assert!(
+            port.contains("The last definition is in effect."),
+            "{}",
+            port
+        );
+
+        let main_pid = hover_text(server.get_hover_info(&uri, &Position::new(3, 47)).await);
+        assert!(main_pid.contains("Set by systemd"), "{}", main_pid);
+        let other = hover_text(server.get_hover_info(&uri, &Position::new(3, 55)).await);
+        assert!(other.contains("expands to an empty string"), "{}", other);
+    }
+
+    #[tokio::test]
+    async fn test_syscall_group_hover() {
+        let (service, _) = LspService::new(SystemdLanguageServer::new);
+        let server = service.inner();
+        let uri = "file:///srv/units/app.service".parse::<Uri>().unwrap();
+        server.parser.update_document(
+            &uri,
+            "[Service]\nSystemCallFilter=@system-service\nSystemCallFilter=~@privileged:EPERM ptrace\n",
+        );
+
+        let system_service = hover_text(server.get_hover_info(&uri, &Position::new(1, 20)).await);
+        assert!(
+            system_service.contains("**@system-service**: General system service operations"),
+            "{}",
+            system_service
+        );
+        assert!(
+            system_service.contains("Includes `@aio`"),
+            "{}",
+            system_service
+        );
+
+        let privileged = server
+            .get_hover_info(&uri, &Position::new(2, 22))
+            .await
+            .unwrap();
+        assert_eq!(
+            privileged.range,
+            Some(Range::new(Position::new(2, 17), Position::new(2, 35)))
+        );
+        assert!(hover_text(Some(privileged)).contains("Expands to"));
+        let ptrace = server.get_hover_info(&uri, &Position::new(2, 38)).await;
+        assert!(ptrace.is_none_or(|hover| !format!("{:?}", hover.contents).contains("Expands to")));
+    }
+
+    #[test]
+    fn test_networkd_files_are_collected() {
+        assert!(is_systemd_file(Path::new(
+            "/etc/systemd/network/10-lan.network"
+        )));
+        assert!(is_systemd_file(Path::new("br0.netdev")));
+        assert!(is_systemd_file(Path::new("10-eth.link")));
+        assert!(!is_systemd_file(Path::new("README.md")));
+    }
+
+    #[test]
+    fn test_daemon_config_files_are_collected() {
+        assert!(is_systemd_file(Path::new("/etc/systemd/journald.conf")));
+        assert!(is_systemd_file(Path::new(
+            "/etc/systemd/logind.conf.d/lid.conf"
+        )));
+        assert!(is_systemd_file(Path::new("resolved.conf")));
+        assert!(!is_systemd_file(Path::new("/etc/ld.so.conf")));
+        assert!(!is_systemd_file(Path::new(
+            "/etc/modprobe.d/blacklist.conf"
+        )));
+    }
 }
@@ -1,14 +1,20 @@
+use crate::config::NetworkDialect;
+use crate::dialect::UnitDialect;
+use crate::unit_name;
 use dashmap::DashMap;
 use log::{debug, trace};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::RwLock;
 use tower_lsp_server::lsp_types::{Position, Uri};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SystemdUnit {
     pub sections: HashMap<String, SystemdSection>,
     pub raw_text: String,
+    #[serde(default)]
+    pub dialect: UnitDialect,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -40,6 +46,13 @@ pub struct SystemdParser {
     documents: DashMap<Uri, SystemdUnit>,
     section_regex: Regex,
     directive_regex: Regex,
+    network_dialect: RwLock<NetworkDialect>,
+}
+
+impl Default for SystemdParser {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl SystemdParser {
@@ -48,6 +61,7 @@ impl SystemdParser {
             documents: DashMap::new(),
             section_regex: Regex::new(r"^\[([^\]]+)\]$").unwrap(),
             directive_regex: Regex::new(r"^([^=]+)=(.*)$").unwrap(),
+            network_dialect: RwLock::new(NetworkDialect::default()),
         }
     }
 
@@ -56,6 +70,7 @@ impl SystemdParser {
         let mut unit = SystemdUnit {
             sections: HashMap::new(),
             raw_text: text.to_string(),
+            dialect: UnitDialect::default(),
         };
 
         let mut current_section: Option<String> = None;
@@ -194,6 +209,8 @@ impl SystemdParser {
             }
         }
 
+        unit.dialect = UnitDialect::from_content(&unit);
+
         debug!(
             "Parsed {} sections with {} total directives",
             unit.sections.len(),
@@ -205,11 +222,68 @@ impl SystemdParser {
         unit
     }
 
+    /// Parse a document and pick its dialect from the URI path as well as its content
+    pub fn parse_document(&self, uri: &Uri, text: &str) -> SystemdUnit {
+        let mut unit = self.parse(text);
+        unit.dialect =
+            UnitDialect::detect(Some(uri.path().as_str()), &unit, self.network_dialect());
+        unit
+    }
+
     pub fn update_document(&self, uri: &Uri, text: &str) {
-        let parsed = self.parse(text);
+        let parsed = self.parse_document(uri, text);
         self.documents.insert(uri.clone(), parsed);
     }
 
+    pub fn network_dialect(&self) -> NetworkDialect {
+        *self.network_dialect.read().unwrap()
+    }
+
+    /// Change how `.network` files are interpreted and re-detect the dialect of every
+    /// open document. Returns the documents whose dialect changed.
+    pub fn set_network_dialect(&self, preference: NetworkDialect) -> Vec<Uri> {
+        *self.network_dialect.write().unwrap() = preference;
+
+        let mut changed = Vec::new();
+        for mut entry in self.documents.iter_mut() {
+            let dialect =
+                UnitDialect::detect(Some(entry.key().path().as_str()), &entry, preference);
+            if entry.dialect != dialect {
+                entry.dialect = dialect;
+                changed.push(entry.key().clone());
+            }
+        }
+        changed
+    }
+
+    pub fn document_uris(&self) -> Vec<Uri> {
+        self.documents
+            .iter()
+            .map(|entry| entry.key().clone())
+            .collect()
+    }
+
+    /// The open document defining unit `name`, or for an instance such as
+    /// `getty@tty1.service` its template `getty@.service`
+    pub fn find_unit_document(&self, name: &str) -> Option<Uri> {
+        let uris = self.document_uris();
+        let file_name = |uri: &Uri| {
+            std::path::Path::new(uri.path().as_str())
+                .file_name()
+                .and_then(|file_name| file_name.to_str())
+                .map(str::to_string)
+        };
+        let template = name.contains('@').then(|| unit_name::template_of(name));
+        std::iter::once(Some(name.to_string()))
+            .chain(std::iter::once(template))
+            .flatten()
+            .find_map(|candidate| {
+                uris.iter()
+                    .find(|uri| file_name(uri).as_deref() == Some(candidate.as_str()))
+                    .cloned()
+            })
+    }
+
     pub fn get_parsed_document(&self, uri: &Uri) -> Option<SystemdUnit> {
         self.documents.get(uri).map(|entry| entry.clone())
     }
@@ -694,4 +768,48 @@ mod tests {
         assert_eq!(third_span.start, 4);
         assert_eq!(third_span.end, 18);
     }
+
+    #[test]
+    fn test_parse_document_detects_dialect_from_path() {
+        let parser = SystemdParser::new();
+        let content = "[Network]\nDNS=192.168.1.1\n";
+
+        let networkd = "file:///etc/systemd/network/lan.network"
+            .parse::<Uri>()
+            .unwrap();
+        let quadlet = "file:///etc/containers/systemd/lan.network"
+            .parse::<Uri>()
+            .unwrap();
+
+        assert_eq!(
+            parser.parse_document(&networkd, content).dialect,
+            UnitDialect::NetworkdNetwork
+        );
+        assert_eq!(
+            parser.parse_document(&quadlet, content).dialect,
+            UnitDialect::Systemd
+        );
+    }
+
+    #[test]
+    fn test_set_network_dialect_redetects_documents() {
+        let parser = SystemdParser::new();
+        let uri = "file:///srv/lan.network".parse::<Uri>().unwrap();
+        parser.update_document(&uri, "[Network]\nDNS=192.168.1.1\n");
+        assert_eq!(
+            parser.get_parsed_document(&uri).unwrap().dialect,
+            UnitDialect::Systemd
+        );
+
+        let changed = parser.set_network_dialect(NetworkDialect::Networkd);
+        assert_eq!(changed, vec![uri.clone()]);
+        assert_eq!(
+            parser.get_parsed_document(&uri).unwrap().dialect,
+            UnitDialect::NetworkdNetwork
+        );
+
+        assert!(parser
+            .set_network_dialect(NetworkDialect::Networkd)
+            .is_empty());
+    }
 }
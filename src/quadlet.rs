@@ -0,0 +1,609 @@
+use crate::parser::{SystemdDirective, SystemdUnit};
+use log::debug;
+use std::path::Path;
+use thiserror::Error;
+
+const PODMAN: &str = "/usr/bin/podman";
+
+#[derive(Debug, Error, PartialEq)]
+pub enum QuadletError {
+    #[error("unsupported Quadlet file type '{0}' (expected .container, .volume or .network)")]
+    UnsupportedType(String),
+    #[error("missing [{0}] section")]
+    MissingSection(&'static str),
+    #[error("[Container] requires an Image= or Rootfs= directive")]
+    MissingImage,
+}
+
+/// How a Quadlet directive is translated into `podman` arguments.
+#[derive(Debug, Clone, Copy)]
+enum QuadletArg {
+    /// `--flag value` for every occurrence of the directive
+    Value(&'static str),
+    /// `--flag value` for every whitespace separated entry of the value
+    Split(&'static str),
+    /// `--flag` only when the value is a true boolean
+    Switch(&'static str),
+    /// `--flag key=value` (used for volume driver options)
+    Option(&'static str),
+    /// The value is appended verbatim after shell-style splitting
+    Raw,
+}
+
+// Mapping tables mirroring podman's quadlet generator. Directives that need
+// special handling (Image, Exec, Network, Volume, ...) are translated in code.
+const CONTAINER_ARGS: &[(&str, QuadletArg)] = &[
+    ("AddCapability", QuadletArg::Split("--cap-add")),
+    ("DropCapability", QuadletArg::Split("--cap-drop")),
+    ("AddDevice", QuadletArg::Value("--device")),
+    ("AddHost", QuadletArg::Value("--add-host")),
+    ("Annotation", QuadletArg::Value("--annotation")),
+    ("CgroupsMode", QuadletArg::Value("--cgroups")),
+    ("DNS", QuadletArg::Value("--dns")),
+    ("DNSOption", QuadletArg::Value("--dns-option")),
+    ("DNSSearch", QuadletArg::Value("--dns-search")),
+    ("Entrypoint", QuadletArg::Value("--entrypoint")),
+    ("EnvironmentFile", QuadletArg::Value("--env-file")),
+    ("EnvironmentHost", QuadletArg::Switch("--env-host")),
+    ("ExposeHostPort", QuadletArg::Value("--expose")),
+    ("GroupAdd", QuadletArg::Value("--group-add")),
+    ("HealthCmd", QuadletArg::Value("--health-cmd")),
+    ("HealthInterval", QuadletArg::Value("--health-interval")),
+    ("HealthOnFailure", QuadletArg::Value("--health-on-failure")),
+    ("HealthRetries", QuadletArg::Value("--health-retries")),
+    (
+        "HealthStartPeriod",
+        QuadletArg::Value("--health-start-period"),
+    ),
+    ("HealthTimeout", QuadletArg::Value("--health-timeout")),
+    ("HostName", QuadletArg::Value("--hostname")),
+    ("IP", QuadletArg::Value("--ip")),
+    ("IP6", QuadletArg::Value("--ip6")),
+    ("Label", QuadletArg::Split("--label")),
+    ("LogDriver", QuadletArg::Value("--log-driver")),
+    ("LogOpt", QuadletArg::Value("--log-opt")),
+    ("Memory", QuadletArg::Value("--memory")),
+    ("Mount", QuadletArg::Value("--mount")),
+    ("NetworkAlias", QuadletArg::Value("--network-alias")),
+    ("PidsLimit", QuadletArg::Value("--pids-limit")),
+    ("PublishPort", QuadletArg::Value("--publish")),
+    ("Pull", QuadletArg::Value("--pull")),
+    ("ReadOnly", QuadletArg::Switch("--read-only")),
+    ("RunInit", QuadletArg::Switch("--init")),
+    ("Secret", QuadletArg::Value("--secret")),
+    ("ShmSize", QuadletArg::Value("--shm-size")),
+    ("StopSignal", QuadletArg::Value("--stop-signal")),
+    ("StopTimeout", QuadletArg::Value("--stop-timeout")),
+    ("Sysctl", QuadletArg::Split("--sysctl")),
+    ("Timezone", QuadletArg::Value("--tz")),
+    ("Tmpfs", QuadletArg::Value("--tmpfs")),
+    ("Ulimit", QuadletArg::Value("--ulimit")),
+    ("UserNS", QuadletArg::Value("--userns")),
+    ("WorkingDir", QuadletArg::Value("--workdir")),
+    ("PodmanArgs", QuadletArg::Raw),
+];
+
+const NETWORK_ARGS: &[(&str, QuadletArg)] = &[
+    ("Driver", QuadletArg::Value("--driver")),
+    ("Gateway", QuadletArg::Value("--gateway")),
+    ("Subnet", QuadletArg::Value("--subnet")),
+    ("IPRange", QuadletArg::Value("--ip-range")),
+    ("IPAMDriver", QuadletArg::Value("--ipam-driver")),
+    ("InterfaceName", QuadletArg::Value("--interface-name")),
+    ("DNS", QuadletArg::Value("--dns")),
+    ("IPv6", QuadletArg::Switch("--ipv6")),
+    ("Internal", QuadletArg::Switch("--internal")),
+    ("DisableDNS", QuadletArg::Switch("--disable-dns")),
+    ("Label", QuadletArg::Split("--label")),
+    ("Options", QuadletArg::Value("--opt")),
+    ("PodmanArgs", QuadletArg::Raw),
+];
+
+const VOLUME_ARGS: &[(&str, QuadletArg)] = &[
+    ("Driver", QuadletArg::Value("--driver")),
+    ("Label", QuadletArg::Split("--label")),
+    ("Type", QuadletArg::Option("type")),
+    ("Device", QuadletArg::Option("device")),
+    ("Options", QuadletArg::Option("o")),
+    ("PodmanArgs", QuadletArg::Raw),
+];
+
+#[derive(Debug)]
+pub struct SystemdQuadletGenerator;
+
+impl Default for SystemdQuadletGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SystemdQuadletGenerator {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Name of the service unit quadlet generates for the given source file.
+    pub fn service_name(file_name: &str) -> Option<String> {
+        let path = Path::new(file_name);
+        let stem = path.file_stem()?.to_str()?;
+        match path.extension()?.to_str()? {
+            "container" => Some(format!("{}.service", stem)),
+            "volume" => Some(format!("{}-volume.service", stem)),
+            "network" => Some(format!("{}-network.service", stem)),
+            _ => None,
+        }
+    }
+
+    /// Translate a Quadlet unit into the `.service` text podman's generator would produce.
+    pub fn generate(&self, unit: &SystemdUnit, file_name: &str) -> Result<String, QuadletError> {
+        let path = Path::new(file_name);
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default();
+        let stem = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("unit");
+
+        debug!(
+            "Generating quadlet service for {} ({})",
+            file_name, extension
+        );
+
+        let mut generated = GeneratedUnit::default();
+        generated.unit.push(format!("SourcePath={}", file_name));
+
+        match extension {
+            "container" => self.generate_container(unit, &mut generated)?,
+            "volume" => self.generate_volume(unit, stem, &mut generated)?,
+            "network" => self.generate_network(unit, stem, &mut generated)?,
+            other => return Err(QuadletError::UnsupportedType(other.to_string())),
+        }
+
+        Ok(generated.render(unit))
+    }
+
+    fn generate_container(
+        &self,
+        unit: &SystemdUnit,
+        generated: &mut GeneratedUnit,
+    ) -> Result<(), QuadletError> {
+        let container = unit
+            .sections
+            .get("Container")
+            .ok_or(QuadletError::MissingSection("Container"))?;
+        let directives = &container.directives;
+
+        generated
+            .unit
+            .push("RequiresMountsFor=%t/containers".to_string());
+
+        // ContainerName= replaces the default systemd-%N name
+        let name =
+            last_value(directives, "ContainerName").unwrap_or_else(|| "systemd-%N".to_string());
+        let mut args: Vec<String> = vec![
+            "run".to_string(),
+            "--name".to_string(),
+            name,
+            "--cidfile=%t/%N.cid".to_string(),
+            "--replace".to_string(),
+            "--rm".to_string(),
+            "--cgroups=split".to_string(),
+        ];
+
+        let notify = last_value(directives, "Notify").is_some_and(|v| is_true(&v));
+        args.push(if notify {
+            "--sdnotify=container".to_string()
+        } else {
+            "--sdnotify=conmon".to_string()
+        });
+        args.push("-d".to_string());
+
+        if last_value(directives, "NoNewPrivileges").is_some_and(|v| is_true(&v)) {
+            args.push("--security-opt=no-new-privileges".to_string());
+        }
+
+        match (
+            last_value(directives, "User"),
+            last_value(directives, "Group"),
+        ) {
+            (Some(user), Some(group)) => args.push(format!("--user={}:{}", user, group)),
+            (Some(user), None) => args.push(format!("--user={}", user)),
+            _ => {}
+        }
+
+        if let Some(policy) = last_value(directives, "AutoUpdate") {
+            args.push("--label".to_string());
+            args.push(format!("io.containers.autoupdate={}", policy));
+        }
+
+        for directive in directives {
+            match directive.key.as_str() {
+                "Environment" => {
+                    for assignment in split_words(&directive.value) {
+                        args.push("--env".to_string());
+                        args.push(assignment);
+                    }
+                }
+                "Network" => {
+                    for network in split_words(&directive.value) {
+                        args.push("--network".to_string());
+                        args.push(resolve_reference(&network, "network", generated));
+                    }
+                }
+                "Volume" => {
+                    args.push("-v".to_string());
+                    args.push(resolve_reference(&directive.value, "volume", generated));
+                }
+                "Pod" => {
+                    args.push("--pod".to_string());
+                    args.push(resolve_reference(&directive.value, "pod", generated));
+                }
+                key => push_mapped_args(CONTAINER_ARGS, key, &directive.value, &mut args),
+            }
+        }
+
+        if let Some(image) = last_value(directives, "Image") {
+            args.push(resolve_reference(&image, "image", generated));
+        } else if let Some(rootfs) = last_value(directives, "Rootfs") {
+            args.push("--rootfs".to_string());
+            args.push(rootfs);
+        } else {
+            return Err(QuadletError::MissingImage);
+        }
+
+        if let Some(exec) = last_value(directives, "Exec") {
+            args.extend(split_words(&exec));
+        }
+
+        generated.service.extend([
+            "Environment=PODMAN_SYSTEMD_UNIT=%n".to_string(),
+            "KillMode=mixed".to_string(),
+            format!("ExecStop={} rm -v -f -i --cidfile=%t/%N.cid", PODMAN),
+            format!("ExecStopPost=-{} rm -v -f -i --cidfile=%t/%N.cid", PODMAN),
+            "Delegate=yes".to_string(),
+            "Type=notify".to_string(),
+            "NotifyAccess=all".to_string(),
+            "SyslogIdentifier=%N".to_string(),
+            format!("ExecStart={} {}", PODMAN, join_args(&args)),
+        ]);
+
+        Ok(())
+    }
+
+    fn generate_volume(
+        &self,
+        unit: &SystemdUnit,
+        stem: &str,
+        generated: &mut GeneratedUnit,
+    ) -> Result<(), QuadletError> {
+        let volume = unit
+            .sections
+            .get("Volume")
+            .ok_or(QuadletError::MissingSection("Volume"))?;
+        let directives = &volume.directives;
+
+        let name = last_value(directives, "VolumeName").unwrap_or(format!("systemd-{}", stem));
+        let mut args = vec![
+            "volume".to_string(),
+            "create".to_string(),
+            "--ignore".to_string(),
+        ];
+
+        if let Some(image) = last_value(directives, "Image") {
+            args.push("--driver".to_string());
+            args.push("image".to_string());
+            args.push("--opt".to_string());
+            args.push(format!(
+                "image={}",
+                resolve_reference(&image, "image", generated)
+            ));
+        }
+
+        for directive in directives {
+            push_mapped_args(VOLUME_ARGS, &directive.key, &directive.value, &mut args);
+        }
+        args.push(name);
+
+        Self::push_oneshot_service(generated, &args);
+        Ok(())
+    }
+
+    fn generate_network(
+        &self,
+        unit: &SystemdUnit,
+        stem: &str,
+        generated: &mut GeneratedUnit,
+    ) -> Result<(), QuadletError> {
+        let network = unit
+            .sections
+            .get("Network")
+            .ok_or(QuadletError::MissingSection("Network"))?;
+        let directives = &network.directives;
+
+        let name = last_value(directives, "NetworkName").unwrap_or(format!("systemd-{}", stem));
+        let mut args = vec![
+            "network".to_string(),
+            "create".to_string(),
+            "--ignore".to_string(),
+        ];
+
+        for directive in directives {
+            push_mapped_args(NETWORK_ARGS, &directive.key, &directive.value, &mut args);
+        }
+        args.push(name);
+
+        Self::push_oneshot_service(generated, &args);
+        Ok(())
+    }
+
+    fn push_oneshot_service(generated: &mut GeneratedUnit, args: &[String]) {
+        generated.service.extend([
+            format!("ExecStart={} {}", PODMAN, join_args(args)),
+            "Type=oneshot".to_string(),
+            "RemainAfterExit=yes".to_string(),
+            "SyslogIdentifier=%N".to_string(),
+        ]);
+    }
+}
+
+#[derive(Debug, Default)]
+struct GeneratedUnit {
+    unit: Vec<String>,
+    service: Vec<String>,
+}
+
+impl GeneratedUnit {
+    fn add_dependency(&mut self, service: &str) {
+        let requires = format!("Requires={}", service);
+        if !self.unit.contains(&requires) {
+            self.unit.push(requires);
+            self.unit.push(format!("After={}", service));
+        }
+    }
+
+    fn render(&self, source: &SystemdUnit) -> String {
+        let mut output =
+            String::from("# Automatically generated by systemd-lsp (quadlet preview)\n");
+
+        output.push_str("\n[Unit]\n");
+        push_passthrough(&mut output, source, "Unit");
+        for line in &self.unit {
+            output.push_str(line);
+            output.push('\n');
+        }
+
+        output.push_str("\n[Service]\n");
+        for line in &self.service {
+            output.push_str(line);
+            output.push('\n');
+        }
+        push_passthrough(&mut output, source, "Service");
+
+        if source.sections.contains_key("Install") {
+            output.push_str("\n[Install]\n");
+            push_passthrough(&mut output, source, "Install");
+        }
+
+        output
+    }
+}
+
+fn push_passthrough(output: &mut String, source: &SystemdUnit, section: &str) {
+    if let Some(section) = source.sections.get(section) {
+        for directive in &section.directives {
+            output.push_str(&format!("{}={}\n", directive.key, directive.value));
+        }
+    }
+}
+
+fn push_mapped_args(table: &[(&str, QuadletArg)], key: &str, value: &str, args: &mut Vec<String>) {
+    let Some((_, arg)) = table.iter().find(|(name, _)| *name == key) else {
+        return;
+    };
+
+    match arg {
+        QuadletArg::Value(flag) => {
+            args.push(flag.to_string());
+            args.push(value.to_string());
+        }
+        QuadletArg::Split(flag) => {
+            for word in split_words(value) {
+                args.push(flag.to_string());
+                args.push(word);
+            }
+        }
+        QuadletArg::Switch(flag) => {
+            if is_true(value) {
+                args.push(flag.to_string());
+            }
+        }
+        QuadletArg::Option(option) => {
+            args.push("--opt".to_string());
+            args.push(format!("{}={}", option, value));
+        }
+        QuadletArg::Raw => args.extend(split_words(value)),
+    }
+}
+
+/// Resolve `foo.network`, `foo.volume:/data`, ... references to the podman object
+/// name quadlet creates, recording the dependency on the generated service.
+fn resolve_reference(value: &str, kind: &str, generated: &mut GeneratedUnit) -> String {
+    let (reference, rest) = match value.find(':') {
+        Some(idx) => (&value[..idx], &value[idx..]),
+        None => (value, ""),
+    };
+
+    let suffix = format!(".{}", kind);
+    match reference.strip_suffix(&suffix) {
+        Some(stem) if !stem.is_empty() && !stem.contains('/') => {
+            generated.add_dependency(&format!("{}-{}.service", stem, kind));
+            format!("systemd-{}{}", stem, rest)
+        }
+        _ => value.to_string(),
+    }
+}
+
+fn last_value(directives: &[SystemdDirective], key: &str) -> Option<String> {
+    directives
+        .iter()
+        .rev()
+        .find(|directive| directive.key == key)
+        .map(|directive| directive.value.clone())
+}
+
+fn is_true(value: &str) -> bool {
+    matches!(
+        value.trim().to_lowercase().as_str(),
+        "1" | "yes" | "y" | "true" | "t" | "on"
+    )
+}
+
+/// Split a value into words, honouring single and double quotes.
+fn split_words(value: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+    let mut in_word = false;
+
+    for ch in value.chars() {
+        match quote {
+            Some(q) if ch == q => quote = None,
+            Some(_) => current.push(ch),
+            None if ch == '"' || ch == '\'' => {
+                quote = Some(ch);
+                in_word = true;
+            }
+            None if ch.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            None => {
+                current.push(ch);
+                in_word = true;
+            }
+        }
+    }
+
+    if in_word {
+        words.push(current);
+    }
+    words
+}
+
+fn join_args(args: &[String]) -> String {
+    args.iter()
+        .map(|arg| {
+            if arg.is_empty() || arg.contains(char::is_whitespace) || arg.contains('"') {
+                format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))
+            } else {
+                arg.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::SystemdParser;
+
+    fn generate(content: &str, file_name: &str) -> Result<String, QuadletError> {
+        let parser = SystemdParser::new();
+        let unit = parser.parse(content);
+        SystemdQuadletGenerator::new().generate(&unit, file_name)
+    }
+
+    #[test]
+    fn test_service_name() {
+        assert_eq!(
+            SystemdQuadletGenerator::service_name("web.container"),
+            Some("web.service".to_string())
+        );
+        assert_eq!(
+            SystemdQuadletGenerator::service_name("/etc/containers/systemd/data.volume"),
+            Some("data-volume.service".to_string())
+        );
+        assert_eq!(
+            SystemdQuadletGenerator::service_name("app.network"),
+            Some("app-network.service".to_string())
+        );
+        assert_eq!(SystemdQuadletGenerator::service_name("app.service"), None);
+    }
+
+    #[test]
+    fn test_container_exec_start() {
+        let content = "[Unit]\nDescription=Web\n\n[Container]\nImage=docker.io/nginx:latest\nPublishPort=8080:80\nEnvironment=A=1 \"B=two words\"\nReadOnly=true\nExec=nginx -g daemon\n\n[Service]\nRestart=always\n\n[Install]\nWantedBy=multi-user.target\n";
+        let output = generate(content, "web.container").unwrap();
+
+        assert!(output.contains("[Unit]\nDescription=Web\n"));
+        assert!(output.contains("SourcePath=web.container"));
+        assert!(output.contains(
+            "ExecStart=/usr/bin/podman run --name systemd-%N --cidfile=%t/%N.cid --replace --rm --cgroups=split --sdnotify=conmon -d --publish 8080:80 --env A=1 --env \"B=two words\" --read-only docker.io/nginx:latest nginx -g daemon"
+        ));
+        assert!(output.contains("Type=notify\n"));
+        assert!(output.contains("Restart=always\n"));
+        assert!(output.contains("[Install]\nWantedBy=multi-user.target\n"));
+    }
+
+    #[test]
+    fn test_container_network_and_volume_dependencies() {
+        let content = "[Container]\nImage=alpine\nNetwork=app.network\nVolume=data.volume:/data:Z\nVolume=/srv:/srv\n";
+        let output = generate(content, "app.container").unwrap();
+
+        assert!(output.contains("Requires=app-network.service\nAfter=app-network.service\n"));
+        assert!(output.contains("Requires=data-volume.service\nAfter=data-volume.service\n"));
+        assert!(output.contains("--network systemd-app"));
+        assert!(output.contains("-v systemd-data:/data:Z"));
+        assert!(output.contains("-v /srv:/srv"));
+        assert!(!output.contains("[Install]"));
+    }
+
+    #[test]
+    fn test_container_name_and_notify() {
+        let content = "[Container]\nImage=alpine\nContainerName=web\nNotify=true\n";
+        let output = generate(content, "app.container").unwrap();
+
+        assert!(output.contains("run --name web "));
+        assert!(output.contains("--sdnotify=container"));
+    }
+
+    #[test]
+    fn test_container_without_image() {
+        let content = "[Container]\nPublishPort=80:80\n";
+        assert_eq!(
+            generate(content, "app.container"),
+            Err(QuadletError::MissingImage)
+        );
+    }
+
+    #[test]
+    fn test_network_and_volume_units() {
+        let network = generate(
+            "[Network]\nNetworkName=myapp-net\nSubnet=10.88.0.0/16\nInternal=yes\n",
+            "myapp.network",
+        )
+        .unwrap();
+        assert!(network.contains(
+            "ExecStart=/usr/bin/podman network create --ignore --subnet 10.88.0.0/16 --internal myapp-net"
+        ));
+        assert!(network.contains("Type=oneshot\nRemainAfterExit=yes\n"));
+
+        let volume = generate("[Volume]\nType=tmpfs\nDevice=tmpfs\n", "data.volume").unwrap();
+        assert!(volume.contains(
+            "ExecStart=/usr/bin/podman volume create --ignore --opt type=tmpfs --opt device=tmpfs systemd-data"
+        ));
+    }
+
+    #[test]
+    fn test_unsupported_type() {
+        assert_eq!(
+            generate("[Kube]\nYaml=app.yaml\n", "app.kube"),
+            Err(QuadletError::UnsupportedType("kube".to_string()))
+        );
+    }
+}
@@ -0,0 +1,372 @@
+//! Names the sandboxing directives accept: system calls, their `@groups` and errno
+//! names for `SystemCallFilter=` and `SystemCallErrorNumber=`, address families for
+//! `RestrictAddressFamilies=` and capabilities for `CapabilityBoundingSet=` and
+//! `AmbientCapabilities=`. The groups come from `docs/syscalls.txt`.
+
+use std::collections::BTreeSet;
+use std::sync::OnceLock;
+
+/// Address families `af_from_name()` knows, without `AF_UNSPEC` and `AF_MAX`
+pub const ADDRESS_FAMILIES: &[&str] = &[
+    "AF_UNIX",
+    "AF_LOCAL",
+    "AF_FILE",
+    "AF_INET",
+    "AF_INET6",
+    "AF_NETLINK",
+    "AF_ROUTE",
+    "AF_PACKET",
+    "AF_AX25",
+    "AF_IPX",
+    "AF_APPLETALK",
+    "AF_NETROM",
+    "AF_BRIDGE",
+    "AF_ATMPVC",
+    "AF_X25",
+    "AF_ROSE",
+    "AF_DECnet",
+    "AF_NETBEUI",
+    "AF_SECURITY",
+    "AF_KEY",
+    "AF_ASH",
+    "AF_ECONET",
+    "AF_ATMSVC",
+    "AF_RDS",
+    "AF_SNA",
+    "AF_IRDA",
+    "AF_PPPOX",
+    "AF_WANPIPE",
+    "AF_LLC",
+    "AF_IB",
+    "AF_MPLS",
+    "AF_CAN",
+    "AF_TIPC",
+    "AF_BLUETOOTH",
+    "AF_IUCV",
+    "AF_RXRPC",
+    "AF_ISDN",
+    "AF_PHONET",
+    "AF_IEEE802154",
+    "AF_CAIF",
+    "AF_ALG",
+    "AF_NFC",
+    "AF_VSOCK",
+    "AF_KCM",
+    "AF_QIPCRTR",
+    "AF_SMC",
+    "AF_XDP",
+    "AF_MCTP",
+];
+
+/// Capabilities from capabilities(7), in the order of their numbers
+pub const CAPABILITIES: &[&str] = &[
+    "CAP_CHOWN",
+    "CAP_DAC_OVERRIDE",
+    "CAP_DAC_READ_SEARCH",
+    "CAP_FOWNER",
+    "CAP_FSETID",
+    "CAP_KILL",
+    "CAP_SETGID",
+    "CAP_SETUID",
+    "CAP_SETPCAP",
+    "CAP_LINUX_IMMUTABLE",
+    "CAP_NET_BIND_SERVICE",
+    "CAP_NET_BROADCAST",
+    "CAP_NET_ADMIN",
+    "CAP_NET_RAW",
+    "CAP_IPC_LOCK",
+    "CAP_IPC_OWNER",
+    "CAP_SYS_MODULE",
+    "CAP_SYS_RAWIO",
+    "CAP_SYS_CHROOT",
+    "CAP_SYS_PTRACE",
+    "CAP_SYS_PACCT",
+    "CAP_SYS_ADMIN",
+    "CAP_SYS_BOOT",
+    "CAP_SYS_NICE",
+    "CAP_SYS_RESOURCE",
+    "CAP_SYS_TIME",
+    "CAP_SYS_TTY_CONFIG",
+    "CAP_MKNOD",
+    "CAP_LEASE",
+    "CAP_AUDIT_WRITE",
+    "CAP_AUDIT_CONTROL",
+    "CAP_SETFCAP",
+    "CAP_MAC_OVERRIDE",
+    "CAP_MAC_ADMIN",
+    "CAP_SYSLOG",
+    "CAP_WAKE_ALARM",
+    "CAP_BLOCK_SUSPEND",
+    "CAP_AUDIT_READ",
+    "CAP_PERFMON",
+    "CAP_BPF",
+    "CAP_CHECKPOINT_RESTORE",
+];
+
+/// Highest capability number the kernel may define, as accepted by `capability_from_name()`
+const MAX_CAPABILITY: u32 = 62;
+
+/// Error names a `SystemCallFilter=` entry may return instead of running the call
+pub const ERRNO_NAMES: &[&str] = &[
+    "EPERM",
+    "ENOENT",
+    "ESRCH",
+    "EINTR",
+    "EIO",
+    "ENXIO",
+    "E2BIG",
+    "ENOEXEC",
+    "EBADF",
+    "ECHILD",
+    "EAGAIN",
+    "ENOMEM",
+    "EACCES",
+    "EFAULT",
+    "ENOTBLK",
+    "EBUSY",
+    "EEXIST",
+    "EXDEV",
+    "ENODEV",
+    "ENOTDIR",
+    "EISDIR",
+    "EINVAL",
+    "ENFILE",
+    "EMFILE",
+    "ENOTTY",
+    "ETXTBSY",
+    "EFBIG",
+    "ENOSPC",
+    "ESPIPE",
+    "EROFS",
+    "EMLINK",
+    "EPIPE",
+    "EDOM",
+    "ERANGE",
+    "EDEADLK",
+    "ENAMETOOLONG",
+    "ENOLCK",
+    "ENOSYS",
+    "ENOTEMPTY",
+    "ELOOP",
+    "EWOULDBLOCK",
+    "ENOMSG",
+    "EIDRM",
+    "ENOSTR",
+    "ENODATA",
+    "ETIME",
+    "ENOSR",
+    "ENOLINK",
+    "EPROTO",
+    "EBADMSG",
+    "EOVERFLOW",
+    "EILSEQ",
+    "EUSERS",
+    "ENOTSOCK",
+    "EDESTADDRREQ",
+    "EMSGSIZE",
+    "EPROTOTYPE",
+    "ENOPROTOOPT",
+    "EPROTONOSUPPORT",
+    "ESOCKTNOSUPPORT",
+    "EOPNOTSUPP",
+    "EAFNOSUPPORT",
+    "EADDRINUSE",
+    "EADDRNOTAVAIL",
+    "ENETDOWN",
+    "ENETUNREACH",
+    "ENETRESET",
+    "ECONNABORTED",
+    "ECONNRESET",
+    "ENOBUFS",
+    "EISCONN",
+    "ENOTCONN",
+    "ESHUTDOWN",
+    "ETIMEDOUT",
+    "ECONNREFUSED",
+    "EHOSTDOWN",
+    "EHOSTUNREACH",
+    "EALREADY",
+    "EINPROGRESS",
+    "ESTALE",
+    "EDQUOT",
+    "ECANCELED",
+    "ENOKEY",
+    "EKEYEXPIRED",
+    "EKEYREVOKED",
+    "EKEYREJECTED",
+    "EOWNERDEAD",
+    "ENOTRECOVERABLE",
+    "ERFKILL",
+];
+
+/// Highest error number `parse_errno()` accepts
+const MAX_ERRNO: u32 = 4095;
+
+/// A `@group` of `SystemCallFilter=`, whose members are system calls or other groups
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyscallGroup {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub members: Vec<&'static str>,
+}
+
+/// The groups of `docs/syscalls.txt`, in the order `systemd-analyze syscall-filter` lists them
+pub fn syscall_groups() -> &'static [SyscallGroup] {
+    static GROUPS: OnceLock<Vec<SyscallGroup>> = OnceLock::new();
+    GROUPS.get_or_init(|| {
+        let mut groups: Vec<SyscallGroup> = Vec::new();
+        for line in include_str!("../docs/syscalls.txt").lines() {
+            if let Some(name) = line.strip_prefix('@') {
+                groups.push(SyscallGroup {
+                    name,
+                    description: "",
+                    members: Vec::new(),
+                });
+            } else if let Some(group) = groups.last_mut().filter(|_| line.starts_with(' ')) {
+                let line = line.trim();
+                match line.strip_prefix('#') {
+                    Some(description) => group.description = description.trim(),
+                    None if !line.is_empty() => group.members.push(line),
+                    None => {}
+                }
+            }
+        }
+        groups
+    })
+}
+
+/// A group by its name without the `@`, e.g. `system-service`
+pub fn syscall_group(name: &str) -> Option<&'static SyscallGroup> {
+    syscall_groups().iter().find(|group| group.name == name)
+}
+
+/// Whether a group lists `name`; `@known` has the calls of the kernel headers and
+/// `@obsolete` the ones removed from them, such as `_sysctl`
+pub fn is_syscall(name: &str) -> bool {
+    !name.starts_with('@')
+        && syscall_groups()
+            .iter()
+            .any(|group| group.members.contains(&name))
+}
+
+/// The system calls a group stands for once the groups it includes are expanded, sorted
+pub fn expand_syscall_group(name: &str) -> Vec<&'static str> {
+    fn collect(name: &str, syscalls: &mut BTreeSet<&'static str>, seen: &mut Vec<String>) {
+        if seen.iter().any(|group| group == name) {
+            return;
+        }
+        seen.push(name.to_string());
+        for member in syscall_group(name).map_or(&[][..], |group| &group.members) {
+            match member.strip_prefix('@') {
+                Some(included) => collect(included, syscalls, seen),
+                None => {
+                    syscalls.insert(member);
+                }
+            }
+        }
+    }
+
+    let mut syscalls = BTreeSet::new();
+    collect(name, &mut syscalls, &mut Vec::new());
+    syscalls.into_iter().collect()
+}
+
+/// Hover text of a group: its description, the groups it includes and the system calls
+pub fn syscall_group_documentation(name: &str) -> Option<String> {
+    let group = syscall_group(name)?;
+    let syscalls = expand_syscall_group(name);
+    let included: Vec<String> = group
+        .members
+        .iter()
+        .filter(|member| member.starts_with('@'))
+        .map(|member| format!("`{}`", member))
+        .collect();
+
+    let mut text = format!("**@{}**: {}", group.name, group.description);
+    if !included.is_empty() {
+        text.push_str(&format!("\n\nIncludes {}.", included.join(", ")));
+    }
+    text.push_str(&format!(
+        "\n\nExpands to {} system calls: {}",
+        syscalls.len(),
+        syscalls
+            .iter()
+            .map(|syscall| format!("`{}`", syscall))
+            .collect::<Vec<_>>()
+            .join(", ")
+    ));
+    Some(text)
+}
+
+/// Whether `name` is a capability name, compared case-insensitively, or number
+pub fn is_capability(name: &str) -> bool {
+    match name.parse::<u32>() {
+        Ok(number) => number <= MAX_CAPABILITY,
+        Err(_) => CAPABILITIES
+            .iter()
+            .any(|capability| capability.eq_ignore_ascii_case(name)),
+    }
+}
+
+/// Whether `name` is what `seccomp_parse_errno_or_action()` accepts after the `:`
+pub fn is_errno(name: &str) -> bool {
+    match name.parse::<u32>() {
+        Ok(number) => number <= MAX_ERRNO,
+        Err(_) => name == "kill" || ERRNO_NAMES.contains(&name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_syscall_groups() {
+        let names: Vec<&str> = syscall_groups().iter().map(|group| group.name).collect();
+        for name in ["default", "system-service", "privileged", "raw-io", "known"] {
+            assert!(names.contains(&name), "{}", name);
+        }
+
+        let raw_io = syscall_group("raw-io").unwrap();
+        assert_eq!(raw_io.description, "Raw I/O port access");
+        assert!(raw_io.members.contains(&"ioperm"));
+
+        assert!(is_syscall("openat"));
+        assert!(is_syscall("_sysctl"));
+        assert!(!is_syscall("open_at"));
+        assert!(!is_syscall("@raw-io"));
+    }
+
+    #[test]
+    fn test_groups_expand_the_groups_they_include() {
+        let system_service = expand_syscall_group("system-service");
+        assert!(system_service.contains(&"io_setup"), "from @aio");
+        assert!(system_service.contains(&"execve"), "from @default");
+        assert!(system_service.contains(&"setuid"), "from @setuid");
+        assert!(!system_service.contains(&"reboot"));
+        assert!(system_service.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(expand_syscall_group("nonexistent").is_empty());
+
+        let documentation = syscall_group_documentation("privileged").unwrap();
+        assert!(documentation
+            .starts_with("**@privileged**: All system calls which need super-user capabilities"));
+        assert!(documentation.contains("Includes `@chown`, `@clock`"));
+        assert!(documentation.contains("`reboot`"));
+        assert!(syscall_group_documentation("nonexistent").is_none());
+    }
+
+    #[test]
+    fn test_capabilities_and_errno_names() {
+        assert!(is_capability("CAP_NET_BIND_SERVICE"));
+        assert!(is_capability("cap_sys_admin"));
+        assert!(is_capability("21"));
+        assert!(!is_capability("CAP_NET_BIND"));
+        assert!(!is_capability("63"));
+
+        assert!(is_errno("EPERM"));
+        assert!(is_errno("kill"));
+        assert!(is_errno("38"));
+        assert!(!is_errno("eperm"));
+        assert!(!is_errno("5000"));
+    }
+}
@@ -0,0 +1,334 @@
+//! Typed directive schema, compiled by `build.rs` from the directive lists and
+//! manual pages in `docs/` and the type data in `docs/schema/*.ini`.
+//!
+//! Every `(dialect, section, directive)` has exactly one entry. The keys of a
+//! `docs/schema/*.ini` block are:
+//!
+//! - `sections`: limit the block to these sections (default: every section listing the directive)
+//! - `type`: one of the [`ValueType`] names, e.g. `boolean`, `timespan` or `cpu-set` (default `string`)
+//! - `list`: `yes` when the directive takes a list or may be repeated
+//! - `values`: allowed values of an `enum`, or keywords accepted besides the type's own syntax
+//!   (e.g. `disconnected` for the boolean `PrivateTmp=`); values ending in `:` are prefixes
+//! - `since`: `value@version` for values added after the directive itself
+//! - `default`: the value systemd assumes when the directive is not set
+//! - `deprecated` / `removed`: the replacement directive, or `yes` when there is none
+//! - `replacement-section`, `conversion`, `hint`: how to migrate a deprecated directive
+
+use crate::dialect::UnitDialect;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValueType {
+    String,
+    Boolean,
+    Enum,
+    Integer,
+    Percent,
+    Size,
+    TimeSpan,
+    Calendar,
+    Path,
+    Units,
+    Command,
+    Environment,
+    Signal,
+    Mode,
+    CpuSet,
+    ExitStatus,
+    Syscalls,
+    AddressFamilies,
+    Capabilities,
+    SocketAddress,
+    MountOptions,
+}
+
+impl ValueType {
+    pub fn description(&self) -> &'static str {
+        match self {
+            ValueType::String => "string",
+            ValueType::Boolean => "boolean",
+            ValueType::Enum => "one of a fixed set of values",
+            ValueType::Integer => "integer",
+            ValueType::Percent => "percentage",
+            ValueType::Size => "size in bytes (K, M, G, T suffixes)",
+            ValueType::TimeSpan => "time span",
+            ValueType::Calendar => "calendar event",
+            ValueType::Path => "absolute path",
+            ValueType::Units => "unit names",
+            ValueType::Command => "command line",
+            ValueType::Environment => "environment assignments",
+            ValueType::Signal => "signal",
+            ValueType::Mode => "octal file mode",
+            ValueType::CpuSet => "CPU or NUMA node set",
+            ValueType::ExitStatus => "exit statuses and signals",
+            ValueType::Syscalls => "system calls and @groups",
+            ValueType::AddressFamilies => "address families",
+            ValueType::Capabilities => "capabilities",
+            ValueType::SocketAddress => "socket address",
+            ValueType::MountOptions => "mount options",
+        }
+    }
+}
+
+/// How the value of a deprecated directive is carried over to its replacement
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueConversion {
+    /// The replacement accepts the same value
+    Keep,
+    /// `CPUShares=` (2…262144, default 1024) to `CPUWeight=` (1…10000, default 100)
+    CpuSharesToWeight,
+    /// `BlockIOWeight=` (10…1000, default 500) to `IOWeight=` (1…10000, default 100)
+    BlockIoToIoWeight,
+    /// Like `BlockIoToIoWeight`, for the weight following the device path
+    BlockIoDeviceToIoDeviceWeight,
+    /// A boolean `OnFailureIsolate=` to an `OnFailureJobMode=` job mode
+    IsolateToJobMode,
+    /// `PermissionsStartOnly=yes` becomes a `+` prefix on the auxiliary `Exec*=` commands
+    PrivilegedExecPrefix,
+}
+
+/// What to write instead of a directive systemd deprecated or dropped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Deprecation {
+    pub replacement: Option<&'static str>,
+    /// Section the replacement belongs in, when it differs from the original one
+    pub replacement_section: Option<&'static str>,
+    pub conversion: ValueConversion,
+    /// Removed directives are ignored by systemd, deprecated ones are still honoured
+    pub removed: bool,
+    /// Migration advice for directives without a one-to-one replacement
+    pub hint: Option<&'static str>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DirectiveSchema {
+    pub dialect: UnitDialect,
+    pub section: &'static str,
+    pub name: &'static str,
+    pub value_type: ValueType,
+    /// Takes a list of values or may be given more than once
+    pub list: bool,
+    pub values: &'static [&'static str],
+    /// Values that were added after the directive itself, as `(value, version)`
+    pub value_since: &'static [(&'static str, u32)],
+    pub default: Option<&'static str>,
+    /// systemd version that added the directive, if the manual page records it
+    pub since: Option<u32>,
+    /// Manual page documenting the directive, e.g. `systemd.service.5`
+    pub man_page: &'static str,
+    pub documentation: Option<&'static str>,
+    pub deprecation: Option<Deprecation>,
+}
+
+include!(concat!(env!("OUT_DIR"), "/directive_schema.rs"));
+
+type SchemaIndex = HashMap<UnitDialect, HashMap<&'static str, HashMap<&'static str, usize>>>;
+
+impl DirectiveSchema {
+    /// Values to complete; for an `Enum` also the only valid ones. Booleans list their
+    /// canonical spellings, other types the keywords they accept besides their syntax.
+    pub fn allowed_values(&self) -> Option<&'static [&'static str]> {
+        (!self.values.is_empty()).then_some(self.values)
+    }
+
+    /// Whether `value` is one of the listed values, honouring `prefix:` values
+    pub fn is_listed_value(&self, value: &str) -> bool {
+        self.values.iter().any(|known| matches_value(known, value))
+    }
+
+    /// Minimum systemd version of a value, when it is newer than the directive
+    pub fn value_version(&self, value: &str) -> Option<u32> {
+        self.value_since
+            .iter()
+            .find(|(known, _)| matches_value(known, value))
+            .map(|(_, version)| *version)
+    }
+
+    /// Diagnostic message naming the replacement, e.g. "CPUShares= is deprecated, use CPUWeight= instead"
+    pub fn deprecation_message(&self) -> Option<String> {
+        let deprecation = self.deprecation?;
+        let status = if deprecation.removed {
+            "has been removed"
+        } else {
+            "is deprecated"
+        };
+
+        Some(
+            match (
+                deprecation.replacement,
+                deprecation.replacement_section,
+                deprecation.hint,
+            ) {
+                (Some(replacement), Some(target), _) => format!(
+                    "{}= in [{}] {}, use {}= in [{}] instead",
+                    self.name, self.section, status, replacement, target
+                ),
+                (Some(replacement), None, _) => {
+                    format!("{}= {}, use {}= instead", self.name, status, replacement)
+                }
+                (None, _, Some(hint)) => format!("{}= {}, {}", self.name, status, hint),
+                (None, _, None) => format!("{}= {} and is ignored by systemd", self.name, status),
+            },
+        )
+    }
+
+    /// Online version of the manual page, pointing at the directive where the page has anchors
+    pub fn man_page_url(&self) -> Option<String> {
+        let (page, _) = self.man_page.rsplit_once('.')?;
+        if page.starts_with("podman") {
+            return Some(format!(
+                "https://docs.podman.io/en/latest/markdown/{}.html",
+                self.man_page
+            ));
+        }
+        Some(format!(
+            "https://www.freedesktop.org/software/systemd/man/latest/{}.html#{}=",
+            page, self.name
+        ))
+    }
+}
+
+fn matches_value(known: &str, value: &str) -> bool {
+    if known.ends_with(':') {
+        value.starts_with(known)
+    } else {
+        value == known
+    }
+}
+
+fn index() -> &'static SchemaIndex {
+    static INDEX: OnceLock<SchemaIndex> = OnceLock::new();
+    INDEX.get_or_init(|| {
+        let mut index: SchemaIndex = HashMap::new();
+        for (position, entry) in DIRECTIVES.iter().enumerate() {
+            index
+                .entry(entry.dialect)
+                .or_default()
+                .entry(entry.section)
+                .or_default()
+                .insert(entry.name, position);
+        }
+        index
+    })
+}
+
+/// All entries of a dialect, in the order of the upstream directive lists
+pub fn directives(dialect: UnitDialect) -> impl Iterator<Item = &'static DirectiveSchema> {
+    DIRECTIVES
+        .iter()
+        .filter(move |entry| entry.dialect == dialect)
+}
+
+/// The entry for a directive in a section, matching names exactly
+pub fn lookup(
+    dialect: UnitDialect,
+    section: &str,
+    directive: &str,
+) -> Option<&'static DirectiveSchema> {
+    let position = index().get(&dialect)?.get(section)?.get(directive)?;
+    Some(&DIRECTIVES[*position])
+}
+
+/// Like [`lookup`], falling back to a case-insensitive match of the section and directive
+pub fn find(
+    dialect: UnitDialect,
+    section: &str,
+    directive: &str,
+) -> Option<&'static DirectiveSchema> {
+    lookup(dialect, section, directive).or_else(|| {
+        directives(dialect).find(|entry| {
+            entry.section.eq_ignore_ascii_case(section)
+                && entry.name.eq_ignore_ascii_case(directive)
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entries_are_unique() {
+        let mut seen = std::collections::HashSet::new();
+        for entry in DIRECTIVES {
+            assert!(
+                seen.insert((entry.dialect, entry.section, entry.name)),
+                "duplicate schema entry for [{}] {}=",
+                entry.section,
+                entry.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_typed_entries() {
+        let restart_sec = lookup(UnitDialect::Systemd, "Service", "RestartSec").unwrap();
+        assert_eq!(restart_sec.value_type, ValueType::TimeSpan);
+        assert_eq!(restart_sec.man_page, "systemd.service.5");
+        assert!(restart_sec.documentation.is_some());
+
+        let wants = lookup(UnitDialect::Systemd, "Unit", "Wants").unwrap();
+        assert_eq!(wants.value_type, ValueType::Units);
+        assert!(wants.list);
+
+        let service_type = lookup(UnitDialect::Systemd, "Service", "Type").unwrap();
+        assert_eq!(service_type.value_type, ValueType::Enum);
+        assert_eq!(service_type.default, Some("simple"));
+        assert_eq!(service_type.value_version("notify-reload"), Some(253));
+        let mount_type = lookup(UnitDialect::Systemd, "Mount", "Type").unwrap();
+        assert!(mount_type.values.contains(&"ext4"));
+        assert_eq!(mount_type.value_version("exec"), None);
+
+        // Shared directives are documented in systemd.exec(5) for every section accepting them
+        let user = lookup(UnitDialect::Systemd, "Socket", "User").unwrap();
+        assert_eq!(user.man_page, "systemd.exec.5");
+        assert_eq!(
+            user.man_page_url().unwrap(),
+            "https://www.freedesktop.org/software/systemd/man/latest/systemd.exec.html#User="
+        );
+
+        let no_new_privileges = lookup(UnitDialect::Systemd, "Service", "NoNewPrivileges").unwrap();
+        assert_eq!(
+            no_new_privileges.allowed_values(),
+            Some(&["true", "false", "yes", "no", "1", "0"][..])
+        );
+        let private_tmp = lookup(UnitDialect::Systemd, "Service", "PrivateTmp").unwrap();
+        assert_eq!(private_tmp.value_type, ValueType::Boolean);
+        assert!(private_tmp.is_listed_value("disconnected"));
+        let standard_output = lookup(UnitDialect::Systemd, "Service", "StandardOutput").unwrap();
+        assert!(standard_output.is_listed_value("append:/var/log/app.log"));
+
+        let storage = lookup(UnitDialect::Journald, "Journal", "Storage").unwrap();
+        assert_eq!(
+            storage.man_page_url().unwrap(),
+            "https://www.freedesktop.org/software/systemd/man/latest/journald.conf.html#Storage="
+        );
+        assert!(lookup(UnitDialect::Journald, "Service", "Storage").is_none());
+    }
+
+    #[test]
+    fn test_deprecated_entries() {
+        let cpu_shares = lookup(UnitDialect::Systemd, "Service", "CPUShares").unwrap();
+        let deprecation = cpu_shares.deprecation.unwrap();
+        assert_eq!(deprecation.replacement, Some("CPUWeight"));
+        assert_eq!(deprecation.conversion, ValueConversion::CpuSharesToWeight);
+        assert!(cpu_shares.documentation.is_none());
+        assert_eq!(
+            cpu_shares.deprecation_message().unwrap(),
+            "CPUShares= is deprecated, use CPUWeight= instead"
+        );
+
+        assert!(lookup(UnitDialect::Systemd, "Service", "CPUWeight")
+            .unwrap()
+            .deprecation
+            .is_none());
+    }
+
+    #[test]
+    fn test_find_ignores_case() {
+        let entry = find(UnitDialect::Systemd, "service", "execstart").unwrap();
+        assert_eq!((entry.section, entry.name), ("Service", "ExecStart"));
+        assert_eq!(entry.value_type, ValueType::Command);
+    }
+}
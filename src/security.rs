@@ -0,0 +1,726 @@
+//! Exposure scoring of a service's sandboxing, modelled on `systemd-analyze security`.
+//!
+//! Every check rates one aspect of the `[Service]` section from 0 (hardened) to 1
+//! (fully exposed). The overall exposure is the weighted average on systemd's scale
+//! from 0.0 to 10.0, where lower is safer.
+
+use crate::parser::{SystemdSection, SystemdUnit};
+use crate::validators::parse_boolean;
+use std::collections::HashMap;
+
+/// Capabilities that on their own allow taking over the system
+const DANGEROUS_CAPABILITIES: &[&str] = &[
+    "CAP_SYS_ADMIN",
+    "CAP_SYS_PTRACE",
+    "CAP_SYS_MODULE",
+    "CAP_SYS_RAWIO",
+    "CAP_SYS_BOOT",
+    "CAP_SYS_TIME",
+    "CAP_NET_ADMIN",
+    "CAP_DAC_OVERRIDE",
+    "CAP_SETUID",
+    "CAP_SETGID",
+    "CAP_MKNOD",
+    "CAP_BPF",
+];
+
+/// Address families other than the ones network services commonly need
+const UNCOMMON_ADDRESS_FAMILIES: &[&str] = &["AF_PACKET", "AF_NETLINK", "AF_BLUETOOTH", "AF_ALG"];
+
+/// systemd's ratings, as (lowest exposure, name)
+const RATINGS: &[(f64, &str)] = &[
+    (10.0, "DANGEROUS"),
+    (9.0, "UNSAFE"),
+    (7.5, "EXPOSED"),
+    (5.0, "MEDIUM"),
+    (1.0, "OK"),
+    (0.1, "SAFE"),
+    (0.0, "PERFECT"),
+];
+
+/// How a check rates the settings of a service
+#[derive(Debug, Clone, Copy)]
+enum Rule {
+    /// Hardened when the boolean directive is enabled
+    Enabled,
+    /// Hardened when the directive is enabled or set to one of its other modes
+    NotDisabled,
+    /// Hardened when the directive is set at all
+    Set,
+    /// Hardened when the directive is set to one of these values
+    Values(&'static [&'static str]),
+    Custom(fn(&ServiceSettings) -> f64),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SecurityCheck {
+    /// Directive the check looks at
+    pub directive: &'static str,
+    /// What the service is exposed to while the check fails
+    pub exposure: &'static str,
+    /// Share of the check in the overall exposure
+    pub weight: u32,
+    /// Setting that passes the check, offered as a code action
+    pub recommendation: &'static str,
+    rule: Rule,
+}
+
+const CHECKS: &[SecurityCheck] = &[
+    SecurityCheck {
+        directive: "User",
+        exposure: "Service runs as root",
+        weight: 2000,
+        recommendation: "DynamicUser=yes",
+        rule: Rule::Custom(runs_as_root),
+    },
+    SecurityCheck {
+        directive: "NoNewPrivileges",
+        exposure: "Service processes may acquire new privileges",
+        weight: 1000,
+        recommendation: "NoNewPrivileges=yes",
+        rule: Rule::Enabled,
+    },
+    SecurityCheck {
+        directive: "CapabilityBoundingSet",
+        exposure: "Service may use capabilities such as CAP_SYS_ADMIN",
+        weight: 1500,
+        recommendation: "CapabilityBoundingSet=~CAP_SYS_ADMIN CAP_SYS_PTRACE CAP_SYS_MODULE CAP_SYS_RAWIO CAP_SYS_BOOT CAP_SYS_TIME CAP_NET_ADMIN CAP_DAC_OVERRIDE CAP_SETUID CAP_SETGID CAP_MKNOD CAP_BPF",
+        rule: Rule::Custom(capability_bounding_set),
+    },
+    SecurityCheck {
+        directive: "AmbientCapabilities",
+        exposure: "Service processes are granted ambient capabilities",
+        weight: 500,
+        recommendation: "AmbientCapabilities=",
+        rule: Rule::Custom(ambient_capabilities),
+    },
+    SecurityCheck {
+        directive: "PrivateTmp",
+        exposure: "Service has access to other software's temporary files",
+        weight: 1000,
+        recommendation: "PrivateTmp=yes",
+        rule: Rule::Custom(private_tmp),
+    },
+    SecurityCheck {
+        directive: "PrivateDevices",
+        exposure: "Service has access to hardware devices",
+        weight: 1000,
+        recommendation: "PrivateDevices=yes",
+        rule: Rule::Enabled,
+    },
+    SecurityCheck {
+        directive: "DevicePolicy",
+        exposure: "Service may create or open any device node",
+        weight: 1000,
+        recommendation: "DevicePolicy=closed",
+        rule: Rule::Custom(device_policy),
+    },
+    SecurityCheck {
+        directive: "PrivateNetwork",
+        exposure: "Service has access to the host's network",
+        weight: 2500,
+        recommendation: "PrivateNetwork=yes",
+        rule: Rule::Enabled,
+    },
+    SecurityCheck {
+        directive: "IPAddressDeny",
+        exposure: "Service may communicate with any IP address",
+        weight: 1000,
+        recommendation: "IPAddressDeny=any",
+        rule: Rule::Custom(ip_address_deny),
+    },
+    SecurityCheck {
+        directive: "PrivateUsers",
+        exposure: "Service has access to other users",
+        weight: 1500,
+        recommendation: "PrivateUsers=yes",
+        rule: Rule::NotDisabled,
+    },
+    SecurityCheck {
+        directive: "ProtectSystem",
+        exposure: "Service has full access to the OS file hierarchy",
+        weight: 1000,
+        recommendation: "ProtectSystem=strict",
+        rule: Rule::Custom(protect_system),
+    },
+    SecurityCheck {
+        directive: "ProtectHome",
+        exposure: "Service has full access to home directories",
+        weight: 1000,
+        recommendation: "ProtectHome=yes",
+        rule: Rule::Custom(protect_home),
+    },
+    SecurityCheck {
+        directive: "ProtectKernelTunables",
+        exposure: "Service may alter kernel tunables",
+        weight: 1000,
+        recommendation: "ProtectKernelTunables=yes",
+        rule: Rule::Enabled,
+    },
+    SecurityCheck {
+        directive: "ProtectKernelModules",
+        exposure: "Service may load kernel modules",
+        weight: 1000,
+        recommendation: "ProtectKernelModules=yes",
+        rule: Rule::Enabled,
+    },
+    SecurityCheck {
+        directive: "ProtectKernelLogs",
+        exposure: "Service may read from or write to the kernel log ring buffer",
+        weight: 1000,
+        recommendation: "ProtectKernelLogs=yes",
+        rule: Rule::Enabled,
+    },
+    SecurityCheck {
+        directive: "ProtectControlGroups",
+        exposure: "Service may modify the control group file system",
+        weight: 1000,
+        recommendation: "ProtectControlGroups=yes",
+        rule: Rule::NotDisabled,
+    },
+    SecurityCheck {
+        directive: "ProtectClock",
+        exposure: "Service may write to the hardware clock or system clock",
+        weight: 1000,
+        recommendation: "ProtectClock=yes",
+        rule: Rule::Enabled,
+    },
+    SecurityCheck {
+        directive: "ProtectHostname",
+        exposure: "Service may change the system host name",
+        weight: 50,
+        recommendation: "ProtectHostname=yes",
+        rule: Rule::NotDisabled,
+    },
+    SecurityCheck {
+        directive: "ProtectProc",
+        exposure: "Service has full access to the process tree in /proc",
+        weight: 1000,
+        recommendation: "ProtectProc=invisible",
+        rule: Rule::Values(&["invisible", "noaccess", "ptraceable"]),
+    },
+    SecurityCheck {
+        directive: "ProcSubset",
+        exposure: "Service has full access to non-process /proc files",
+        weight: 10,
+        recommendation: "ProcSubset=pid",
+        rule: Rule::Values(&["pid"]),
+    },
+    SecurityCheck {
+        directive: "RestrictAddressFamilies",
+        exposure: "Service may allocate sockets of any address family",
+        weight: 1500,
+        recommendation: "RestrictAddressFamilies=AF_UNIX AF_INET AF_INET6",
+        rule: Rule::Custom(restrict_address_families),
+    },
+    SecurityCheck {
+        directive: "RestrictNamespaces",
+        exposure: "Service may create namespaces",
+        weight: 1500,
+        recommendation: "RestrictNamespaces=yes",
+        rule: Rule::Custom(restrict_namespaces),
+    },
+    SecurityCheck {
+        directive: "RestrictRealtime",
+        exposure: "Service may acquire realtime scheduling",
+        weight: 500,
+        recommendation: "RestrictRealtime=yes",
+        rule: Rule::Enabled,
+    },
+    SecurityCheck {
+        directive: "RestrictSUIDSGID",
+        exposure: "Service may create SUID/SGID files",
+        weight: 1000,
+        recommendation: "RestrictSUIDSGID=yes",
+        rule: Rule::Custom(restrict_suid_sgid),
+    },
+    SecurityCheck {
+        directive: "LockPersonality",
+        exposure: "Service may change its ABI personality",
+        weight: 100,
+        recommendation: "LockPersonality=yes",
+        rule: Rule::Enabled,
+    },
+    SecurityCheck {
+        directive: "MemoryDenyWriteExecute",
+        exposure: "Service may create writable executable memory mappings",
+        weight: 100,
+        recommendation: "MemoryDenyWriteExecute=yes",
+        rule: Rule::Enabled,
+    },
+    SecurityCheck {
+        directive: "SystemCallFilter",
+        exposure: "Service does not filter system calls",
+        weight: 1000,
+        recommendation: "SystemCallFilter=@system-service",
+        rule: Rule::Custom(system_call_filter),
+    },
+    SecurityCheck {
+        directive: "SystemCallArchitectures",
+        exposure: "Service may execute system calls for all ABIs",
+        weight: 1000,
+        recommendation: "SystemCallArchitectures=native",
+        rule: Rule::Set,
+    },
+    SecurityCheck {
+        directive: "RemoveIPC",
+        exposure: "Service user may leave SysV IPC objects around",
+        weight: 100,
+        recommendation: "RemoveIPC=yes",
+        rule: Rule::Custom(remove_ipc),
+    },
+    SecurityCheck {
+        directive: "UMask",
+        exposure: "Files created by the service are world-readable by default",
+        weight: 100,
+        recommendation: "UMask=0077",
+        rule: Rule::Custom(umask),
+    },
+    SecurityCheck {
+        directive: "NotifyAccess",
+        exposure: "Service child processes may alter the service state",
+        weight: 500,
+        recommendation: "NotifyAccess=main",
+        rule: Rule::Custom(notify_access),
+    },
+    SecurityCheck {
+        directive: "Delegate",
+        exposure: "Service maintains its own delegated control group subtree",
+        weight: 100,
+        recommendation: "Delegate=no",
+        rule: Rule::Custom(delegate),
+    },
+];
+
+/// The `[Service]` settings a check reads; an empty assignment resets a directive
+#[derive(Debug, Clone, Default)]
+pub struct ServiceSettings {
+    values: HashMap<String, Vec<String>>,
+}
+
+impl ServiceSettings {
+    pub fn new(section: &SystemdSection) -> Self {
+        let mut settings = Self::default();
+        for directive in &section.directives {
+            settings.assign(&directive.key, &directive.value);
+        }
+        settings
+    }
+
+    fn assign(&mut self, directive: &str, value: &str) {
+        let values = self.values.entry(directive.to_string()).or_default();
+        if value.trim().is_empty() {
+            values.clear();
+        } else {
+            values.push(value.trim().to_string());
+        }
+    }
+
+    /// Whether `directive` is set at all, an empty assignment included
+    fn is_assigned(&self, directive: &str) -> bool {
+        self.values.contains_key(directive)
+    }
+
+    /// The last value assigned to `directive`
+    fn value(&self, directive: &str) -> Option<&str> {
+        self.values.get(directive)?.last().map(String::as_str)
+    }
+
+    /// Every item assigned to a list directive such as `SystemCallFilter=`
+    fn items(&self, directive: &str) -> Vec<&str> {
+        self.values
+            .get(directive)
+            .into_iter()
+            .flatten()
+            .flat_map(|value| value.split_whitespace())
+            .collect()
+    }
+
+    fn enabled(&self, directive: &str) -> bool {
+        self.value(directive).and_then(parse_boolean) == Some(true)
+    }
+
+    /// The settings after appending an assignment such as `PrivateTmp=yes`
+    pub fn with(&self, assignment: &str) -> Self {
+        let mut settings = self.clone();
+        if let Some((directive, value)) = assignment.split_once('=') {
+            settings.assign(directive, value);
+        }
+        settings
+    }
+}
+
+impl SecurityCheck {
+    /// How exposed the service is, from 0.0 (hardened) to 1.0
+    pub fn assess(&self, settings: &ServiceSettings) -> f64 {
+        match self.rule {
+            Rule::Enabled => badness(settings.enabled(self.directive)),
+            Rule::NotDisabled => badness(
+                settings
+                    .value(self.directive)
+                    .is_some_and(|value| parse_boolean(value) != Some(false)),
+            ),
+            Rule::Set => badness(settings.value(self.directive).is_some()),
+            Rule::Values(hardened) => badness(
+                settings
+                    .value(self.directive)
+                    .is_some_and(|value| hardened.contains(&value)),
+            ),
+            Rule::Custom(assess) => assess(settings),
+        }
+    }
+
+    /// The directive the recommendation sets, e.g. `DynamicUser` for the `User=` check
+    pub fn recommended_directive(&self) -> &'static str {
+        self.recommendation
+            .split_once('=')
+            .map_or(self.recommendation, |(directive, _)| directive)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SecurityFinding {
+    pub check: &'static SecurityCheck,
+    /// From 0.0 (hardened) to 1.0
+    pub badness: f64,
+}
+
+impl SecurityFinding {
+    pub fn passed(&self) -> bool {
+        self.badness == 0.0
+    }
+
+    /// Share of the overall exposure
+    pub fn exposure(&self) -> f64 {
+        10.0 * self.badness * self.check.weight as f64 / total_weight()
+    }
+}
+
+fn total_weight() -> f64 {
+    CHECKS.iter().map(|check| check.weight as f64).sum()
+}
+
+/// The findings of every check for one service
+#[derive(Debug, Clone)]
+pub struct SecurityReport {
+    pub settings: ServiceSettings,
+    pub findings: Vec<SecurityFinding>,
+}
+
+impl SecurityReport {
+    pub fn new(settings: ServiceSettings) -> Self {
+        let findings = CHECKS
+            .iter()
+            .map(|check| SecurityFinding {
+                check,
+                badness: check.assess(&settings).clamp(0.0, 1.0),
+            })
+            .collect();
+        Self { settings, findings }
+    }
+
+    /// The report for the `[Service]` section of a unit
+    pub fn for_unit(unit: &SystemdUnit) -> Option<Self> {
+        let section = unit.sections.get("Service")?;
+        Some(Self::new(ServiceSettings::new(section)))
+    }
+
+    /// Overall exposure from 0.0 (hardened) to 10.0, rounded like systemd prints it
+    pub fn exposure(&self) -> f64 {
+        let exposure: f64 = self.findings.iter().map(SecurityFinding::exposure).sum();
+        (exposure * 10.0).round() / 10.0
+    }
+
+    /// systemd's name for the exposure, e.g. `MEDIUM`
+    pub fn rating(&self) -> &'static str {
+        let exposure = self.exposure();
+        RATINGS
+            .iter()
+            .find(|(lowest, _)| exposure >= *lowest)
+            .map_or("PERFECT", |(_, rating)| rating)
+    }
+
+    /// Failed checks, the most exposing first
+    pub fn failed(&self) -> Vec<SecurityFinding> {
+        let mut failed: Vec<SecurityFinding> = self
+            .findings
+            .iter()
+            .filter(|finding| !finding.passed())
+            .copied()
+            .collect();
+        failed.sort_by(|a, b| b.exposure().total_cmp(&a.exposure()));
+        failed
+    }
+
+    /// The overall exposure after applying the recommendation of `check`
+    pub fn exposure_with(&self, check: &SecurityCheck) -> f64 {
+        Self::new(self.settings.with(check.recommendation)).exposure()
+    }
+}
+
+fn badness(hardened: bool) -> f64 {
+    if hardened {
+        0.0
+    } else {
+        1.0
+    }
+}
+
+fn runs_as_root(settings: &ServiceSettings) -> f64 {
+    let user = settings.value("User");
+    badness(
+        settings.enabled("DynamicUser") || user.is_some_and(|user| user != "root" && user != "0"),
+    )
+}
+
+fn capability_bounding_set(settings: &ServiceSettings) -> f64 {
+    // An empty assignment leaves the bounding set empty, which is not the same as never setting it
+    if !settings.is_assigned("CapabilityBoundingSet") {
+        return 1.0;
+    }
+    let items = settings.items("CapabilityBoundingSet");
+    if items.is_empty() {
+        return 0.0;
+    }
+    let kept = if items[0].starts_with('~') {
+        let dropped: Vec<&str> = items
+            .iter()
+            .map(|item| item.trim_start_matches('~'))
+            .collect();
+        DANGEROUS_CAPABILITIES
+            .iter()
+            .filter(|capability| !dropped.contains(capability))
+            .count()
+    } else {
+        DANGEROUS_CAPABILITIES
+            .iter()
+            .filter(|capability| items.contains(capability))
+            .count()
+    };
+    kept as f64 / DANGEROUS_CAPABILITIES.len() as f64
+}
+
+fn ambient_capabilities(settings: &ServiceSettings) -> f64 {
+    badness(settings.items("AmbientCapabilities").is_empty())
+}
+
+fn private_tmp(settings: &ServiceSettings) -> f64 {
+    // DynamicUser= implies PrivateTmp=, RemoveIPC=, RestrictSUIDSGID= and ProtectSystem=strict
+    badness(
+        settings.enabled("DynamicUser")
+            || settings
+                .value("PrivateTmp")
+                .is_some_and(|value| parse_boolean(value) == Some(true) || value == "disconnected"),
+    )
+}
+
+fn device_policy(settings: &ServiceSettings) -> f64 {
+    badness(
+        settings.enabled("PrivateDevices")
+            || matches!(settings.value("DevicePolicy"), Some("closed" | "strict")),
+    )
+}
+
+fn ip_address_deny(settings: &ServiceSettings) -> f64 {
+    badness(settings.enabled("PrivateNetwork") || settings.items("IPAddressDeny").contains(&"any"))
+}
+
+fn protect_system(settings: &ServiceSettings) -> f64 {
+    if settings.enabled("DynamicUser") {
+        return 0.0;
+    }
+    match settings.value("ProtectSystem") {
+        Some("strict") => 0.0,
+        Some("full") => 0.25,
+        Some(value) if parse_boolean(value) == Some(true) => 0.5,
+        _ => 1.0,
+    }
+}
+
+fn protect_home(settings: &ServiceSettings) -> f64 {
+    match settings.value("ProtectHome") {
+        Some("tmpfs") => 0.0,
+        Some("read-only") => 0.5,
+        Some(value) if parse_boolean(value) == Some(true) => 0.0,
+        _ if settings.enabled("DynamicUser") => 0.5,
+        _ => 1.0,
+    }
+}
+
+fn restrict_address_families(settings: &ServiceSettings) -> f64 {
+    let items = settings.items("RestrictAddressFamilies");
+    if items.is_empty() {
+        return 1.0;
+    }
+    if items[0].starts_with('~') {
+        let denied: Vec<&str> = items
+            .iter()
+            .map(|item| item.trim_start_matches('~'))
+            .collect();
+        let allowed = UNCOMMON_ADDRESS_FAMILIES
+            .iter()
+            .filter(|family| !denied.contains(family))
+            .count();
+        return 0.5 * allowed as f64 / UNCOMMON_ADDRESS_FAMILIES.len() as f64 + 0.25;
+    }
+    if items == ["none"] {
+        return 0.0;
+    }
+    let allowed = UNCOMMON_ADDRESS_FAMILIES
+        .iter()
+        .filter(|family| items.contains(family))
+        .count();
+    allowed as f64 / UNCOMMON_ADDRESS_FAMILIES.len() as f64
+}
+
+fn restrict_namespaces(settings: &ServiceSettings) -> f64 {
+    match settings.value("RestrictNamespaces") {
+        None => 1.0,
+        Some(value) => match parse_boolean(value) {
+            Some(true) => 0.0,
+            Some(false) => 1.0,
+            // A list of namespace types allowed, or denied with a leading ~
+            None => 0.5,
+        },
+    }
+}
+
+fn restrict_suid_sgid(settings: &ServiceSettings) -> f64 {
+    badness(settings.enabled("DynamicUser") || settings.enabled("RestrictSUIDSGID"))
+}
+
+fn system_call_filter(settings: &ServiceSettings) -> f64 {
+    let items = settings.items("SystemCallFilter");
+    match items.first() {
+        None => 1.0,
+        // Only denying some system calls still allows everything else
+        Some(first) if first.starts_with('~') => 0.5,
+        Some(_) => 0.0,
+    }
+}
+
+fn remove_ipc(settings: &ServiceSettings) -> f64 {
+    badness(settings.enabled("DynamicUser") || settings.enabled("RemoveIPC"))
+}
+
+fn umask(settings: &ServiceSettings) -> f64 {
+    let umask = settings
+        .value("UMask")
+        .and_then(|value| u32::from_str_radix(value, 8).ok())
+        .unwrap_or(0o022);
+    badness(umask & 0o004 != 0)
+}
+
+fn notify_access(settings: &ServiceSettings) -> f64 {
+    badness(settings.value("NotifyAccess") != Some("all"))
+}
+
+fn delegate(settings: &ServiceSettings) -> f64 {
+    // Delegate= takes a boolean or the controllers to delegate
+    badness(
+        settings
+            .value("Delegate")
+            .is_none_or(|value| parse_boolean(value) == Some(false)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::SystemdParser;
+
+    fn report(text: &str) -> SecurityReport {
+        SecurityReport::for_unit(&SystemdParser::new().parse(text)).unwrap()
+    }
+
+    fn badness(report: &SecurityReport, directive: &str) -> f64 {
+        report
+            .findings
+            .iter()
+            .find(|finding| finding.check.directive == directive)
+            .unwrap()
+            .badness
+    }
+
+    #[test]
+    fn test_unhardened_service_is_exposed() {
+        let plain = report("[Service]\nExecStart=/usr/bin/app\n");
+        assert!(plain.exposure() >= 9.0, "{}", plain.exposure());
+        assert_eq!(plain.rating(), "UNSAFE");
+        assert_eq!(plain.failed()[0].check.directive, "PrivateNetwork");
+
+        // Checks that only fail on a risky setting pass by default
+        assert_eq!(badness(&plain, "NotifyAccess"), 0.0);
+        assert_eq!(badness(&plain, "Delegate"), 0.0);
+        assert_eq!(badness(&plain, "CapabilityBoundingSet"), 1.0);
+        assert!(SecurityReport::for_unit(&SystemdParser::new().parse("[Unit]\n")).is_none());
+    }
+
+    #[test]
+    fn test_hardened_service_scores_low() {
+        let hardened = report(
+            "[Service]\nExecStart=/usr/bin/app\nDynamicUser=yes\nNoNewPrivileges=yes\n\
+             CapabilityBoundingSet=\nPrivateDevices=yes\nPrivateNetwork=yes\nPrivateUsers=yes\nProtectHome=yes\n\
+             ProtectKernelTunables=yes\nProtectKernelModules=yes\nProtectKernelLogs=yes\n\
+             ProtectControlGroups=yes\nProtectClock=yes\nProtectHostname=yes\n\
+             ProtectProc=invisible\nProcSubset=pid\nRestrictAddressFamilies=AF_UNIX\n\
+             RestrictNamespaces=yes\nRestrictRealtime=yes\nLockPersonality=yes\n\
+             MemoryDenyWriteExecute=yes\nSystemCallFilter=@system-service\n\
+             SystemCallArchitectures=native\nUMask=0077\n",
+        );
+        assert_eq!(hardened.exposure(), 0.0, "{:?}", hardened.failed());
+        assert_eq!(hardened.rating(), "PERFECT");
+
+        // DynamicUser= implies a private /tmp, a read-only OS and no SUID/SGID files
+        assert_eq!(badness(&hardened, "PrivateTmp"), 0.0);
+        assert_eq!(badness(&hardened, "ProtectSystem"), 0.0);
+        assert_eq!(badness(&hardened, "RestrictSUIDSGID"), 0.0);
+    }
+
+    #[test]
+    fn test_every_boolean_spelling_counts() {
+        let short = report("[Service]\nNoNewPrivileges=y\nPrivateTmp=t\nProtectClock=On\n");
+        assert_eq!(badness(&short, "NoNewPrivileges"), 0.0);
+        assert_eq!(badness(&short, "PrivateTmp"), 0.0);
+        assert_eq!(badness(&short, "ProtectClock"), 0.0);
+
+        let off = report("[Service]\nNoNewPrivileges=n\nPrivateTmp=f\n");
+        assert_eq!(badness(&off, "NoNewPrivileges"), 1.0);
+        assert_eq!(badness(&off, "PrivateTmp"), 1.0);
+    }
+
+    #[test]
+    fn test_partial_settings() {
+        let partial = report(
+            "[Service]\nUser=root\nProtectSystem=yes\nProtectHome=read-only\n\
+             CapabilityBoundingSet=~CAP_SYS_ADMIN CAP_SYS_PTRACE CAP_SYS_MODULE\n\
+             SystemCallFilter=~@mount\nRestrictNamespaces=~user\nNotifyAccess=all\n",
+        );
+        assert_eq!(badness(&partial, "User"), 1.0);
+        assert_eq!(badness(&partial, "ProtectSystem"), 0.5);
+        assert_eq!(badness(&partial, "ProtectHome"), 0.5);
+        assert_eq!(badness(&partial, "CapabilityBoundingSet"), 0.75);
+        assert_eq!(badness(&partial, "SystemCallFilter"), 0.5);
+        assert_eq!(badness(&partial, "RestrictNamespaces"), 0.5);
+        assert_eq!(badness(&partial, "NotifyAccess"), 1.0);
+
+        let reset = report(
+            "[Service]\nCapabilityBoundingSet=\nCapabilityBoundingSet=CAP_NET_BIND_SERVICE\n",
+        );
+        assert_eq!(badness(&reset, "CapabilityBoundingSet"), 0.0);
+        let widened =
+            report("[Service]\nCapabilityBoundingSet=\nCapabilityBoundingSet=CAP_SYS_ADMIN\n");
+        assert!(badness(&widened, "CapabilityBoundingSet") > 0.0);
+    }
+
+    #[test]
+    fn test_recommendations_pass_their_check() {
+        let plain = report("[Service]\nExecStart=/usr/bin/app\n");
+        for finding in plain.failed() {
+            let check = finding.check;
+            let settings = plain.settings.with(check.recommendation);
+            assert_eq!(check.assess(&settings), 0.0, "{}", check.recommendation);
+            assert!(plain.exposure_with(check) <= plain.exposure());
+        }
+    }
+}
@@ -0,0 +1,264 @@
+use log::{debug, warn};
+use regex::Regex;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{OnceLock, RwLock};
+
+/// Whole-file skeletons offered when completing in an empty file, as
+/// (name, unit type, description, LSP snippet)
+const BUILTIN_TEMPLATES: &[(&str, &str, &str, &str)] = &[
+    (
+        "hardened-service",
+        "service",
+        "Simple service with sandboxing enabled",
+        include_str!("../docs/templates/hardened-service.service"),
+    ),
+    (
+        "oneshot-service",
+        "service",
+        "Oneshot service, started by a timer of the same name",
+        include_str!("../docs/templates/oneshot-service.service"),
+    ),
+    (
+        "socket-activated-service",
+        "service",
+        "Service started by a socket of the same name",
+        include_str!("../docs/templates/socket-activated-service.service"),
+    ),
+    (
+        "timer",
+        "timer",
+        "Timer starting the service of the same name",
+        include_str!("../docs/templates/timer.timer"),
+    ),
+    (
+        "socket",
+        "socket",
+        "Socket activating the service of the same name",
+        include_str!("../docs/templates/socket.socket"),
+    ),
+    (
+        "container",
+        "container",
+        "Podman container run by Quadlet",
+        include_str!("../docs/templates/container.container"),
+    ),
+];
+
+/// A unit file skeleton with `${1:placeholder}` tab stops
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnitTemplate {
+    pub name: String,
+    /// The file extension the template is offered for, e.g. `service`
+    pub unit_type: String,
+    pub description: String,
+    pub snippet: String,
+}
+
+impl UnitTemplate {
+    /// The snippet as it reads once every placeholder keeps its default
+    pub fn preview(&self) -> String {
+        static TAB_STOP: OnceLock<Regex> = OnceLock::new();
+        let tab_stop = TAB_STOP.get_or_init(|| Regex::new(r"\$\{\d+:([^}]*)\}|\$\d+").unwrap());
+        tab_stop
+            .replace_all(&self.snippet, "$1")
+            .trim_end()
+            .to_string()
+    }
+}
+
+/// Built-in templates plus the user's, which replace built-in ones of the same name and type
+#[derive(Debug)]
+pub struct SystemdTemplates {
+    templates: RwLock<Vec<UnitTemplate>>,
+}
+
+impl Default for SystemdTemplates {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SystemdTemplates {
+    pub fn new() -> Self {
+        Self {
+            templates: RwLock::new(Self::builtin_templates()),
+        }
+    }
+
+    fn builtin_templates() -> Vec<UnitTemplate> {
+        BUILTIN_TEMPLATES
+            .iter()
+            .map(|(name, unit_type, description, snippet)| UnitTemplate {
+                name: name.to_string(),
+                unit_type: unit_type.to_string(),
+                description: description.to_string(),
+                snippet: snippet.to_string(),
+            })
+            .collect()
+    }
+
+    /// `$XDG_CONFIG_HOME/systemd-lsp/templates`, falling back to `~/.config/systemd-lsp/templates`
+    pub fn default_directory() -> Option<PathBuf> {
+        let config_home = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(config_home.join("systemd-lsp").join("templates"))
+    }
+
+    /// Replace the user templates with the files in `directory`, each named
+    /// `<name>.<unit type>` (e.g. `webapp.service`) and holding an LSP snippet.
+    /// Returns the number of user templates loaded.
+    pub fn load_directory(&self, directory: Option<&Path>) -> usize {
+        let mut templates = Self::builtin_templates();
+        let user_templates = directory.map(Self::read_directory).unwrap_or_default();
+        let count = user_templates.len();
+
+        for template in user_templates {
+            templates.retain(|existing| {
+                existing.name != template.name || existing.unit_type != template.unit_type
+            });
+            templates.push(template);
+        }
+
+        *self.templates.write().unwrap() = templates;
+        count
+    }
+
+    fn read_directory(directory: &Path) -> Vec<UnitTemplate> {
+        let entries = match fs::read_dir(directory) {
+            Ok(entries) => entries,
+            Err(err) => {
+                debug!("No user templates in {}: {}", directory.display(), err);
+                return Vec::new();
+            }
+        };
+
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file())
+            .collect();
+        paths.sort();
+
+        paths
+            .into_iter()
+            .filter_map(|path| {
+                let name = path.file_stem()?.to_str()?.to_string();
+                let unit_type = path.extension()?.to_str()?.to_string();
+                match fs::read_to_string(&path) {
+                    Ok(snippet) => Some(UnitTemplate {
+                        description: format!("User template {}", path.display()),
+                        name,
+                        unit_type,
+                        snippet,
+                    }),
+                    Err(err) => {
+                        warn!("Skipping template {}: {}", path.display(), err);
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Templates offered for files with the extension `unit_type`
+    pub fn for_unit_type(&self, unit_type: &str) -> Vec<UnitTemplate> {
+        self.templates
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|template| template.unit_type == unit_type)
+            .cloned()
+            .collect()
+    }
+
+    pub fn get(&self, unit_type: &str, name: &str) -> Option<UnitTemplate> {
+        self.templates
+            .read()
+            .unwrap()
+            .iter()
+            .find(|template| template.unit_type == unit_type && template.name == name)
+            .cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::SystemdParser;
+
+    #[test]
+    fn test_builtin_templates_by_unit_type() {
+        let templates = SystemdTemplates::new();
+
+        let services: Vec<String> = templates
+            .for_unit_type("service")
+            .into_iter()
+            .map(|template| template.name)
+            .collect();
+        assert_eq!(
+            services,
+            [
+                "hardened-service",
+                "oneshot-service",
+                "socket-activated-service"
+            ]
+        );
+        assert_eq!(templates.for_unit_type("timer").len(), 1);
+        assert_eq!(templates.for_unit_type("socket").len(), 1);
+        assert_eq!(templates.for_unit_type("container").len(), 1);
+        assert!(templates.for_unit_type("mount").is_empty());
+    }
+
+    #[test]
+    fn test_preview_fills_in_placeholders() {
+        let timer = SystemdTemplates::new().get("timer", "timer").unwrap();
+        let preview = timer.preview();
+
+        assert!(preview.contains("OnCalendar=daily\n"));
+        assert!(!preview.contains('$'));
+
+        // Every built-in template previews as a unit with known sections
+        let parser = SystemdParser::new();
+        for (name, unit_type, _, _) in BUILTIN_TEMPLATES {
+            let template = SystemdTemplates::new().get(unit_type, name).unwrap();
+            let unit = parser.parse(&template.preview());
+            assert!(!unit.sections.is_empty(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_user_templates_override_builtin_ones() {
+        let temp = tempfile::tempdir().unwrap();
+        let directory = temp.path().to_path_buf();
+        fs::write(
+            directory.join("timer.timer"),
+            "[Timer]\nOnBootSec=${1:5min}\n",
+        )
+        .unwrap();
+        fs::write(
+            directory.join("webapp.service"),
+            "[Service]\nExecStart=${1:/usr/bin/webapp}\n",
+        )
+        .unwrap();
+
+        let templates = SystemdTemplates::new();
+        assert_eq!(templates.load_directory(Some(&directory)), 2);
+
+        let timers = templates.for_unit_type("timer");
+        assert_eq!(timers.len(), 1);
+        assert!(timers[0].snippet.contains("OnBootSec"));
+        assert!(templates.get("service", "webapp").is_some());
+        assert_eq!(templates.for_unit_type("service").len(), 4);
+
+        // Reloading without a directory leaves the built-in templates only
+        assert_eq!(templates.load_directory(None), 0);
+        assert!(templates.get("service", "webapp").is_none());
+        assert!(templates
+            .get("timer", "timer")
+            .unwrap()
+            .snippet
+            .contains("OnCalendar"));
+    }
+}
@@ -0,0 +1,539 @@
+//! Unit names as systemd splits them, `prefix@instance.type`: templates such as
+//! `getty@.service` leave the instance empty and instances such as `getty@tty1.service`
+//! are loaded from their template. Also checks names the way `unit_name_is_valid()`
+//! does, escapes text and paths into them like `systemd-escape`, finds the file defining
+//! a unit and expands the specifiers a template is written with for one of its instances.
+
+use crate::value_providers;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// Longest unit name systemd accepts, `UNIT_NAME_MAX`
+pub const UNIT_NAME_MAX: usize = 255;
+
+/// Directories systemd loads system units from, highest priority first
+pub const UNIT_SEARCH_PATHS: &[&str] = &[
+    "/etc/systemd/system",
+    "/run/systemd/system",
+    "/usr/local/lib/systemd/system",
+    "/usr/lib/systemd/system",
+    "/lib/systemd/system",
+];
+
+/// Why systemd would not take `name` as a unit name, like `unit_name_is_valid()`: names
+/// are at most 255 bytes of ASCII letters, digits and `:-_.\\`, with a stem, one `@`
+/// at most after it and a unit type suffix, e.g. `multi-user.target`,
+/// `getty@tty1.service` or `-.slice`
+pub fn unit_name_error(name: &str) -> Option<String> {
+    if !value_providers::is_unit_name(name) {
+        return Some(format!(
+            "'{}' is not a unit name with a type suffix such as .service or .target",
+            name
+        ));
+    }
+    if name.len() > UNIT_NAME_MAX {
+        return Some(format!(
+            "'{}' is {} bytes long, unit names have at most {}",
+            name,
+            name.len(),
+            UNIT_NAME_MAX
+        ));
+    }
+    if let Some(c) = name
+        .chars()
+        .find(|c| !c.is_ascii_alphanumeric() && !":-_.\\@".contains(*c))
+    {
+        return Some(format!(
+            "'{}' contains '{}', which unit names cannot, escape it with systemd-escape",
+            name, c
+        ));
+    }
+    if name.starts_with('@') {
+        return Some(format!("'{}' has nothing before the @", name));
+    }
+    if name.matches('@').count() > 1 {
+        return Some(format!("'{}' has more than one @", name));
+    }
+    None
+}
+
+/// Whether `name` is a unit name systemd accepts, see [`unit_name_error`]
+pub fn is_valid(name: &str) -> bool {
+    unit_name_error(name).is_none()
+}
+
+/// Whether `name` is a template such as `getty@.service`
+pub fn is_template(name: &str) -> bool {
+    name.contains("@.")
+}
+
+/// The instance of `getty@tty1.service`, `tty1`. Templates have none.
+pub fn instance_of(name: &str) -> Option<&str> {
+    let (_, rest) = name.split_once('@')?;
+    let (instance, _) = rest.rsplit_once('.')?;
+    (!instance.is_empty()).then_some(instance)
+}
+
+/// The template a name would be: `getty@.service` for `getty.service` or `getty@tty1.service`
+pub fn template_of(name: &str) -> String {
+    match name.rsplit_once('.') {
+        Some((stem, suffix)) => {
+            let prefix = stem.split_once('@').map_or(stem, |(prefix, _)| prefix);
+            format!("{}@.{}", prefix, suffix)
+        }
+        None => name.to_string(),
+    }
+}
+
+/// `getty@.service` with instance `tty1` is `getty@tty1.service`. Names that are not
+/// templates are returned as they are.
+pub fn instantiate(name: &str, instance: &str) -> String {
+    match name.split_once('@') {
+        Some((prefix, rest)) => match rest.rsplit_once('.') {
+            Some((_, suffix)) => format!("{}@{}.{}", prefix, instance, suffix),
+            None => name.to_string(),
+        },
+        None => name.to_string(),
+    }
+}
+
+/// Escape `text` for use in a unit name like `systemd-escape`: `/` becomes `-`, and `-`,
+/// `\`, a leading `.` and any byte other than ASCII letters, digits, `:`, `_` and `.`
+/// become `\xNN`
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for (index, byte) in text.bytes().enumerate() {
+        match byte {
+            b'/' => escaped.push('-'),
+            b'.' if index == 0 => escaped.push_str("\\x2e"),
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b':' | b'_' | b'.' => {
+                escaped.push(byte as char)
+            }
+            _ => escaped.push_str(&format!("\\x{:02x}", byte)),
+        }
+    }
+    escaped
+}
+
+/// Escape an absolute path like `systemd-escape --path`, which is how mount and
+/// automount units are named: `/var/lib/data` is `var-lib-data` and `/` is `-`. Repeated
+/// slashes and `.` components are dropped first; paths with `..` have no name.
+pub fn escape_path(path: &str) -> Option<String> {
+    let components: Vec<&str> = path
+        .split('/')
+        .filter(|component| !component.is_empty() && *component != ".")
+        .collect();
+    if components.contains(&"..") {
+        return None;
+    }
+    if components.is_empty() {
+        return Some("-".to_string());
+    }
+    Some(escape(&components.join("/")))
+}
+
+/// Turn `name` into a unit name like `systemd-escape --mangle` and systemctl do with the
+/// names they are given: valid names stay as they are, absolute paths become `.device`
+/// or `.mount` units, other characters are escaped and names without a type get
+/// `.service`
+pub fn mangle(name: &str) -> String {
+    if is_valid(name) {
+        return name.to_string();
+    }
+    if name.starts_with('/') {
+        if let Some(escaped) = escape_path(name) {
+            let suffix = if name.starts_with("/dev/") || name.starts_with("/sys/") {
+                "device"
+            } else {
+                "mount"
+            };
+            return format!("{}.{}", escaped, suffix);
+        }
+    }
+
+    let mut mangled = String::new();
+    for byte in name.bytes() {
+        match byte {
+            b'/' => mangled.push('-'),
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b':' | b'-' | b'_' | b'.' | b'\\' | b'@' => {
+                mangled.push(byte as char)
+            }
+            _ => mangled.push_str(&format!("\\x{:02x}", byte)),
+        }
+    }
+    if !value_providers::is_unit_name(&mangled) {
+        mangled.push_str(".service");
+    }
+    mangled
+}
+
+/// The modes of `systemd-escape`, as its command line options
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct EscapeOptions {
+    /// `--path`: the text is a path, simplified and with its leading `/` dropped
+    pub path: bool,
+    /// `--suffix=`: a unit type appended to the escaped text
+    pub suffix: Option<String>,
+    /// `--template=`: a template such as `foo@.service` to instantiate with the text
+    pub template: Option<String>,
+    /// `--unescape`: turn an escaped string back into the text
+    pub unescape: bool,
+    /// `--mangle`: make a unit name out of the text, as systemctl does
+    pub mangle: bool,
+}
+
+/// What `systemd-escape` prints for `text` with `options`, or why it refuses
+pub fn systemd_escape(text: &str, options: &EscapeOptions) -> Result<String, String> {
+    if options.mangle {
+        if options.path
+            || options.unescape
+            || options.suffix.is_some()
+            || options.template.is_some()
+        {
+            return Err("--mangle cannot be combined with other options".to_string());
+        }
+        return Ok(mangle(text));
+    }
+    if options.suffix.is_some() && options.template.is_some() {
+        return Err("--suffix= and --template= cannot be combined".to_string());
+    }
+
+    if options.unescape {
+        if options.suffix.is_some() || options.template.is_some() {
+            return Err("--unescape cannot be combined with --suffix= or --template=".to_string());
+        }
+        return Ok(if options.path {
+            unescape_path(text)
+        } else {
+            unescape(text)
+        });
+    }
+
+    let escaped = if options.path {
+        if !text.starts_with('/') {
+            return Err(format!("'{}' is not an absolute path", text));
+        }
+        escape_path(text).ok_or_else(|| format!("'{}' has a .. component", text))?
+    } else {
+        escape(text)
+    };
+    let name = match (&options.suffix, &options.template) {
+        (Some(suffix), _) => format!("{}.{}", escaped, suffix.trim_start_matches('.')),
+        (_, Some(template)) => {
+            if !is_template(template) || !is_valid(template) {
+                return Err(format!(
+                    "'{}' is not a template such as foo@.service",
+                    template
+                ));
+            }
+            instantiate(template, &escaped)
+        }
+        _ => return Ok(escaped),
+    };
+    match unit_name_error(&name) {
+        Some(error) => Err(error),
+        None => Ok(name),
+    }
+}
+
+/// The path a name escaped with `systemd-escape --path` stands for, `var-lib-data` is
+/// `/var/lib/data`
+pub fn unescape_path(text: &str) -> String {
+    if text == "-" {
+        return "/".to_string();
+    }
+    format!("/{}", unescape(text))
+}
+
+/// Undo the escaping of `systemd-escape`: `-` is `/` and `\xNN` the byte `NN`
+pub fn unescape(text: &str) -> String {
+    let mut bytes = Vec::new();
+    let mut rest = text.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        let escaped = tail
+            .strip_prefix(b"x")
+            .filter(|_| byte == b'\\')
+            .and_then(|hex| std::str::from_utf8(hex.get(..2)?).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(value) => {
+                bytes.push(value);
+                rest = &tail[3..];
+            }
+            None => {
+                bytes.push(if byte == b'-' { b'/' } else { byte });
+                rest = tail;
+            }
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// Expand the specifiers that only depend on the unit name `name` the way systemd does
+/// when it loads the unit: `%n`, `%N`, `%p`, `%P`, `%i`, `%I`, `%j`, `%J`, `%f` and `%%`.
+/// Others, such as `%h` or `%t`, depend on the machine and are kept as they are.
+pub fn expand_specifiers(value: &str, name: &str) -> String {
+    let stem = name.rsplit_once('.').map_or(name, |(stem, _)| stem);
+    let (prefix, instance) = stem.split_once('@').unwrap_or((stem, ""));
+    let last_component = prefix.rsplit('-').next().unwrap_or(prefix);
+
+    let mut expanded = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            expanded.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => expanded.push_str(name),
+            Some('N') => expanded.push_str(stem),
+            Some('p') => expanded.push_str(prefix),
+            Some('P') => expanded.push_str(&unescape(prefix)),
+            Some('i') => expanded.push_str(instance),
+            Some('I') => expanded.push_str(&unescape(instance)),
+            Some('j') => expanded.push_str(last_component),
+            Some('J') => expanded.push_str(&unescape(last_component)),
+            Some('f') => {
+                let path = unescape(if instance.is_empty() {
+                    prefix
+                } else {
+                    instance
+                });
+                if !path.starts_with('/') {
+                    expanded.push('/');
+                }
+                expanded.push_str(&path);
+            }
+            Some('%') => expanded.push('%'),
+            Some(other) => {
+                expanded.push('%');
+                expanded.push(other);
+            }
+            None => expanded.push('%'),
+        }
+    }
+    expanded
+}
+
+/// The file defining unit `name`, looked for next to `near` first and in the system unit
+/// directories second. Instances such as `getty@tty1.service` resolve to their template.
+pub fn find_unit_file(name: &str, near: Option<&Path>) -> Option<PathBuf> {
+    let template = name.contains('@').then(|| template_of(name));
+
+    let directories = near
+        .into_iter()
+        .map(Path::to_path_buf)
+        .chain(UNIT_SEARCH_PATHS.iter().map(PathBuf::from));
+    for directory in directories {
+        for candidate in std::iter::once(name).chain(template.as_deref()) {
+            let path = directory.join(candidate);
+            if path.is_file() {
+                return Some(path);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_templates_and_instances() {
+        assert!(is_template("getty@.service"));
+        assert!(!is_template("getty@tty1.service"));
+        assert_eq!(instance_of("getty@tty1.service"), Some("tty1"));
+        assert_eq!(instance_of("getty@.service"), None);
+        assert_eq!(instance_of("getty.service"), None);
+        assert_eq!(template_of("getty@tty1.service"), "getty@.service");
+        assert_eq!(template_of("console.service"), "console@.service");
+        assert_eq!(instantiate("getty@.service", "tty2"), "getty@tty2.service");
+        assert_eq!(instantiate("getty.service", "tty2"), "getty.service");
+    }
+
+    #[test]
+    fn test_escaping() {
+        assert_eq!(escape("foo bar/baz-qux"), "foo\\x20bar-baz\\x2dqux");
+        assert_eq!(escape(".hidden"), "\\x2ehidden");
+        assert_eq!(escape("ä"), "\\xc3\\xa4");
+        assert_eq!(
+            escape_path("/var/lib/data").as_deref(),
+            Some("var-lib-data")
+        );
+        assert_eq!(
+            escape_path("//var/./lib/data/").as_deref(),
+            Some("var-lib-data")
+        );
+        assert_eq!(escape_path("/").as_deref(), Some("-"));
+        assert_eq!(
+            escape_path("/mnt/my-disk").as_deref(),
+            Some("mnt-my\\x2ddisk")
+        );
+        assert_eq!(escape_path("/var/../etc"), None);
+        assert_eq!(unescape_path("mnt-my\\x2ddisk"), "/mnt/my-disk");
+        assert_eq!(unescape_path("-"), "/");
+        for text in ["/srv/a b/ü.d", "/home/user/.cache"] {
+            assert_eq!(unescape_path(&escape_path(text).unwrap()), text);
+        }
+    }
+
+    #[test]
+    fn test_unit_name_errors() {
+        for name in [
+            "multi-user.target",
+            "getty@tty1.service",
+            "getty@.service",
+            "-.slice",
+        ] {
+            assert!(is_valid(name), "{}", name);
+        }
+        assert_eq!(
+            unit_name_error("app"),
+            Some(
+                "'app' is not a unit name with a type suffix such as .service or .target"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            unit_name_error("my app.service"),
+            Some("'my app.service' contains ' ', which unit names cannot, escape it with systemd-escape".to_string())
+        );
+        assert_eq!(
+            unit_name_error("@tty1.service"),
+            Some("'@tty1.service' has nothing before the @".to_string())
+        );
+        assert_eq!(
+            unit_name_error("a@b@c.socket"),
+            Some("'a@b@c.socket' has more than one @".to_string())
+        );
+        let long = format!("{}.service", "a".repeat(250));
+        assert_eq!(
+            unit_name_error(&long),
+            Some(format!(
+                "'{}' is 258 bytes long, unit names have at most 255",
+                long
+            ))
+        );
+    }
+
+    #[test]
+    fn test_systemd_escape() {
+        let escape_with = |text: &str, options: EscapeOptions| systemd_escape(text, &options);
+        assert_eq!(
+            escape_with(
+                "/var/lib/data",
+                EscapeOptions {
+                    path: true,
+                    suffix: Some("mount".to_string()),
+                    ..Default::default()
+                }
+            ),
+            Ok("var-lib-data.mount".to_string())
+        );
+        assert_eq!(
+            escape_with(
+                "tty 1",
+                EscapeOptions {
+                    template: Some("getty@.service".to_string()),
+                    ..Default::default()
+                }
+            ),
+            Ok("getty@tty\\x201.service".to_string())
+        );
+        assert_eq!(
+            escape_with(
+                "/dev/sda",
+                EscapeOptions {
+                    path: true,
+                    template: Some("systemd-fsck@.service".to_string()),
+                    ..Default::default()
+                }
+            ),
+            Ok("systemd-fsck@dev-sda.service".to_string())
+        );
+        assert_eq!(
+            escape_with(
+                "var-lib-data",
+                EscapeOptions {
+                    path: true,
+                    unescape: true,
+                    ..Default::default()
+                }
+            ),
+            Ok("/var/lib/data".to_string())
+        );
+        assert_eq!(
+            escape_with(
+                "x",
+                EscapeOptions {
+                    template: Some("getty.service".to_string()),
+                    ..Default::default()
+                }
+            ),
+            Err("'getty.service' is not a template such as foo@.service".to_string())
+        );
+        assert!(escape_with(
+            "relative",
+            EscapeOptions {
+                path: true,
+                ..Default::default()
+            }
+        )
+        .is_err());
+        assert!(escape_with(
+            "x",
+            EscapeOptions {
+                mangle: true,
+                path: true,
+                ..Default::default()
+            }
+        )
+        .is_err());
+
+        assert_eq!(mangle("sshd"), "sshd.service");
+        assert_eq!(mangle("sshd.service"), "sshd.service");
+        assert_eq!(mangle("/home/user"), "home-user.mount");
+        assert_eq!(mangle("/dev/sda1"), "dev-sda1.device");
+        assert_eq!(mangle("my app"), "my\\x20app.service");
+    }
+
+    #[test]
+    fn test_specifiers() {
+        assert_eq!(
+            expand_specifiers("%n %N %p %i %j 100%% %t", "foo-bar@tty1.service"),
+            "foo-bar@tty1.service foo-bar@tty1 foo-bar tty1 bar 100% %t"
+        );
+        assert_eq!(
+            expand_specifiers(
+                "%i %I %f",
+                "systemd-fsck@dev-disk-by\\x2dlabel-root.service"
+            ),
+            "dev-disk-by\\x2dlabel-root dev/disk/by-label/root /dev/disk/by-label/root"
+        );
+        assert_eq!(
+            expand_specifiers("%P %f", "home-user.mount"),
+            "home/user /home/user"
+        );
+    }
+
+    #[test]
+    fn test_find_unit_file() {
+        let temp = tempfile::tempdir().unwrap();
+        let directory = temp.path().to_path_buf();
+        std::fs::write(directory.join("backend.service"), "[Unit]\n").unwrap();
+        std::fs::write(directory.join("worker@.service"), "[Unit]\n").unwrap();
+
+        assert_eq!(
+            find_unit_file("backend.service", Some(&directory)),
+            Some(directory.join("backend.service"))
+        );
+        assert_eq!(
+            find_unit_file("worker@1.service", Some(&directory)),
+            Some(directory.join("worker@.service"))
+        );
+        assert!(find_unit_file("no-such-unit-here.service", Some(&directory)).is_none());
+
+    }
+}
@@ -0,0 +1,1271 @@
+//! Value checks for each schema `ValueType`, following the parsers systemd itself uses
+//! (`parse_boolean()`, `parse_size()`, `parse_sec()`, `parse_mode()`, …). Types without
+//! a fixed syntax, such as strings and command lines, are accepted as is.
+//! The same parsers explain values on hover, e.g. `1h30m` as 5400 seconds.
+
+use crate::constants::SystemdConstants;
+use crate::sandboxing;
+use crate::schema::{DirectiveSchema, ValueType};
+use crate::unit_name;
+use crate::value_providers;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// Spellings `parse_boolean()` accepts, compared case-insensitively
+const BOOLEAN_SPELLINGS: &[&str] = &[
+    "1", "yes", "y", "true", "t", "on", "0", "no", "n", "false", "f", "off",
+];
+
+/// Suffixes of `parse_size()` with base 1024, as (suffix, bytes)
+const SIZE_SUFFIXES: &[(&str, f64)] = &[
+    ("E", 1152921504606846976.0),
+    ("P", 1125899906842624.0),
+    ("T", 1099511627776.0),
+    ("G", 1073741824.0),
+    ("M", 1048576.0),
+    ("K", 1024.0),
+    ("B", 1.0),
+    ("", 1.0),
+];
+
+const USEC_PER_SEC: f64 = 1_000_000.0;
+
+/// Units of `parse_sec()` and `parse_nsec()` in microseconds
+const TIME_UNITS: &[(&str, f64)] = &[
+    ("seconds", USEC_PER_SEC),
+    ("second", USEC_PER_SEC),
+    ("minutes", 60.0 * USEC_PER_SEC),
+    ("minute", 60.0 * USEC_PER_SEC),
+    ("months", 2_629_800.0 * USEC_PER_SEC),
+    ("month", 2_629_800.0 * USEC_PER_SEC),
+    ("hours", 3600.0 * USEC_PER_SEC),
+    ("hour", 3600.0 * USEC_PER_SEC),
+    ("days", 86400.0 * USEC_PER_SEC),
+    ("day", 86400.0 * USEC_PER_SEC),
+    ("weeks", 604_800.0 * USEC_PER_SEC),
+    ("week", 604_800.0 * USEC_PER_SEC),
+    ("years", 31_557_600.0 * USEC_PER_SEC),
+    ("year", 31_557_600.0 * USEC_PER_SEC),
+    ("msec", 1000.0),
+    ("usec", 1.0),
+    ("nsec", 0.001),
+    ("sec", USEC_PER_SEC),
+    ("min", 60.0 * USEC_PER_SEC),
+    ("hr", 3600.0 * USEC_PER_SEC),
+    ("ms", 1000.0),
+    ("us", 1.0),
+    ("µs", 1.0),
+    ("μs", 1.0),
+    ("ns", 0.001),
+    ("s", USEC_PER_SEC),
+    ("m", 60.0 * USEC_PER_SEC),
+    ("h", 3600.0 * USEC_PER_SEC),
+    ("d", 86400.0 * USEC_PER_SEC),
+    ("w", 604_800.0 * USEC_PER_SEC),
+    ("M", 2_629_800.0 * USEC_PER_SEC),
+    ("y", 31_557_600.0 * USEC_PER_SEC),
+];
+
+/// Units `format_timespan()` prints, largest first
+const TIMESPAN_FORMAT: &[(&str, f64)] = &[
+    ("y", 31_557_600.0 * USEC_PER_SEC),
+    ("month", 2_629_800.0 * USEC_PER_SEC),
+    ("w", 604_800.0 * USEC_PER_SEC),
+    ("d", 86400.0 * USEC_PER_SEC),
+    ("h", 3600.0 * USEC_PER_SEC),
+    ("min", 60.0 * USEC_PER_SEC),
+    ("s", USEC_PER_SEC),
+    ("ms", 1000.0),
+    ("us", 1.0),
+];
+
+/// Calendar shorthands and the normalized events systemd.time(7) expands them to
+const CALENDAR_SHORTHANDS: &[(&str, &str)] = &[
+    ("minutely", "*-*-* *:*:00"),
+    ("hourly", "*-*-* *:00:00"),
+    ("daily", "*-*-* 00:00:00"),
+    ("monthly", "*-*-01 00:00:00"),
+    ("weekly", "Mon *-*-* 00:00:00"),
+    ("yearly", "*-01-01 00:00:00"),
+    ("annually", "*-01-01 00:00:00"),
+    ("quarterly", "*-01,04,07,10-01 00:00:00"),
+    ("semiannually", "*-01,07-01 00:00:00"),
+];
+
+/// Mount flags of mount(8) that are given alone, never as `flag=value`
+const MOUNT_FLAGS: &[&str] = &[
+    "defaults",
+    "ro",
+    "rw",
+    "auto",
+    "noauto",
+    "exec",
+    "noexec",
+    "suid",
+    "nosuid",
+    "dev",
+    "nodev",
+    "atime",
+    "noatime",
+    "diratime",
+    "nodiratime",
+    "relatime",
+    "norelatime",
+    "strictatime",
+    "nostrictatime",
+    "lazytime",
+    "nolazytime",
+    "sync",
+    "async",
+    "dirsync",
+    "mand",
+    "nomand",
+    "iversion",
+    "noiversion",
+    "symfollow",
+    "nosymfollow",
+    "nofail",
+    "_netdev",
+    "user",
+    "nouser",
+    "users",
+    "owner",
+    "group",
+    "silent",
+    "loud",
+    "bind",
+    "rbind",
+];
+
+/// The `x-systemd.` and `x-initrd.` mount options of systemd.mount(5), and whether they
+/// take a value: `Some(true)` always, `Some(false)` never and `None` optionally
+const SYSTEMD_MOUNT_OPTIONS: &[(&str, Option<bool>)] = &[
+    ("x-systemd.requires", Some(true)),
+    ("x-systemd.before", Some(true)),
+    ("x-systemd.after", Some(true)),
+    ("x-systemd.wanted-by", Some(true)),
+    ("x-systemd.required-by", Some(true)),
+    ("x-systemd.requires-mounts-for", Some(true)),
+    ("x-systemd.wants-mounts-for", Some(true)),
+    ("x-systemd.device-bound", None),
+    ("x-systemd.automount", Some(false)),
+    ("x-systemd.idle-timeout", Some(true)),
+    ("x-systemd.device-timeout", Some(true)),
+    ("x-systemd.mount-timeout", Some(true)),
+    ("x-systemd.makefs", Some(false)),
+    ("x-systemd.growfs", Some(false)),
+    ("x-systemd.pcrfs", Some(false)),
+    ("x-systemd.rw-only", Some(false)),
+    ("x-systemd.validatefs", Some(false)),
+    ("x-initrd.mount", Some(false)),
+    ("x-initrd.attach", Some(false)),
+];
+
+/// Symbolic exit statuses understood by `exit_status_from_string()`
+const EXIT_STATUSES: &[&str] = &[
+    "SUCCESS",
+    "FAILURE",
+    "INVALIDARGUMENT",
+    "NOTIMPLEMENTED",
+    "NOPERMISSION",
+    "NOTINSTALLED",
+    "NOTCONFIGURED",
+    "NOTRUNNING",
+    "USAGE",
+    "DATAERR",
+    "NOINPUT",
+    "NOUSER",
+    "NOHOST",
+    "UNAVAILABLE",
+    "SOFTWARE",
+    "OSERR",
+    "OSFILE",
+    "CANTCREAT",
+    "IOERR",
+    "TEMPFAIL",
+    "PROTOCOL",
+    "NOPERM",
+    "CONFIG",
+    "CHDIR",
+    "NICE",
+    "FDS",
+    "EXEC",
+    "MEMORY",
+    "LIMITS",
+    "OOM_ADJUST",
+    "SIGNAL_MASK",
+    "STDIN",
+    "STDOUT",
+    "CHROOT",
+    "IOPRIO",
+    "TIMERSLACK",
+    "SECUREBITS",
+    "SETSCHEDULER",
+    "CPUAFFINITY",
+    "GROUP",
+    "USER",
+    "CAPABILITIES",
+    "CGROUP",
+    "SETSID",
+    "CONFIRM",
+    "STDERR",
+    "PAM",
+    "NETWORK",
+    "NAMESPACE",
+    "NO_NEW_PRIVILEGES",
+    "SECCOMP",
+    "SELINUX_CONTEXT",
+    "PERSONALITY",
+    "APPARMOR_PROFILE",
+    "ADDRESS_FAMILIES",
+    "RUNTIME_DIRECTORY",
+    "CHOWN",
+    "SMACK_PROCESS_LABEL",
+    "KEYRING",
+    "STATE_DIRECTORY",
+    "CACHE_DIRECTORY",
+    "LOGS_DIRECTORY",
+    "CONFIGURATION_DIRECTORY",
+    "NUMA_POLICY",
+    "CREDENTIALS",
+    "BPF",
+    "KSM",
+    "EXCEPTION",
+];
+
+/// Check a non-empty value against the type of its directive. Listed values (booleans'
+/// extra keywords such as `disconnected`) are accepted by the caller before this runs.
+pub fn validate(entry: &DirectiveSchema, value: &str) -> Result<(), String> {
+    // Specifiers like %h or %i are only known once the unit is loaded
+    if has_specifier(value) {
+        return Ok(());
+    }
+
+    match entry.value_type {
+        ValueType::Boolean => check(is_boolean(value), || {
+            "expected a boolean (yes/no, true/false, on/off, 1/0)".to_string()
+        }),
+        ValueType::Integer => check(is_integer(value), || "expected an integer".to_string()),
+        ValueType::Size => check(is_size(value), || {
+            "expected a size in bytes with an optional K, M, G, T, P or E suffix (base 1024), a percentage or 'infinity'"
+                .to_string()
+        }),
+        ValueType::Percent => check(is_percentage(value), || {
+            "expected a percentage such as 50%".to_string()
+        }),
+        ValueType::TimeSpan => check(is_time_span(value), || {
+            "expected a time span such as 90s, 5min 30s or 'infinity'".to_string()
+        }),
+        ValueType::Path => validate_paths(entry, value),
+        ValueType::Mode => check(is_mode(value), || {
+            "expected an octal file mode such as 0755".to_string()
+        }),
+        ValueType::CpuSet => validate_cpu_set(value),
+        ValueType::Signal => check(is_signal(value), || {
+            "expected a signal name such as SIGTERM or a signal number".to_string()
+        }),
+        ValueType::ExitStatus => validate_exit_statuses(value),
+        ValueType::Syscalls => validate_syscalls(value),
+        ValueType::AddressFamilies => validate_address_families(value),
+        ValueType::Capabilities => validate_capabilities(value),
+        ValueType::Units => validate_unit_names(value),
+        ValueType::SocketAddress => parse_socket_address(value).map(|_| ()),
+        ValueType::MountOptions => validate_mount_options(value),
+        ValueType::String
+        | ValueType::Enum
+        | ValueType::Calendar
+        | ValueType::Command
+        | ValueType::Environment => Ok(()),
+    }
+}
+
+fn check(valid: bool, reason: impl FnOnce() -> String) -> Result<(), String> {
+    if valid {
+        Ok(())
+    } else {
+        Err(reason())
+    }
+}
+
+fn has_specifier(value: &str) -> bool {
+    value
+        .as_bytes()
+        .windows(2)
+        .any(|pair| pair[0] == b'%' && pair[1].is_ascii_alphabetic())
+}
+
+fn is_boolean(value: &str) -> bool {
+    parse_boolean(value).is_some()
+}
+
+/// The boolean a value stands for, like `parse_boolean()`: the first half of the
+/// spellings are true
+pub fn parse_boolean(value: &str) -> Option<bool> {
+    BOOLEAN_SPELLINGS
+        .iter()
+        .position(|spelling| spelling.eq_ignore_ascii_case(value))
+        .map(|index| index < BOOLEAN_SPELLINGS.len() / 2)
+}
+
+fn is_integer(value: &str) -> bool {
+    let digits = value.strip_prefix(['+', '-']).unwrap_or(value);
+    match digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        Some(hex) => !hex.is_empty() && hex.chars().all(|c| c.is_ascii_hexdigit()),
+        None => !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()),
+    }
+}
+
+/// Split a leading decimal number (`12`, `1.5`) from the rest of the string
+fn split_number(value: &str) -> Option<(&str, &str)> {
+    let end = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let number = &value[..end];
+    let valid = !number.is_empty()
+        && !number.starts_with('.')
+        && !number.ends_with('.')
+        && number.matches('.').count() <= 1;
+    valid.then(|| (number, &value[end..]))
+}
+
+fn is_percentage(value: &str) -> bool {
+    ["%", "‰", "‱"].iter().any(|sign| {
+        value
+            .strip_suffix(sign)
+            .and_then(split_number)
+            .is_some_and(|(_, rest)| rest.is_empty())
+    })
+}
+
+fn is_size(value: &str) -> bool {
+    value == "infinity" || is_percentage(value) || size_bytes(value).is_some()
+}
+
+/// `parse_size()` accepts a sum of components like "1G 512M"
+fn size_bytes(value: &str) -> Option<f64> {
+    sum_components(value, "", SIZE_SUFFIXES)
+}
+
+fn is_time_span(value: &str) -> bool {
+    value == "infinity" || time_span_usec(value).is_some()
+}
+
+/// `parse_sec()` accepts a sum of components like "1min 30s"; a bare number means seconds
+fn time_span_usec(value: &str) -> Option<f64> {
+    sum_components(value, "s", TIME_UNITS)
+}
+
+/// Add up components of a number and one of `units`, where a bare number is in `default_unit`
+fn sum_components(value: &str, default_unit: &str, units: &[(&str, f64)]) -> Option<f64> {
+    let mut rest = value.trim();
+    if rest.is_empty() {
+        return None;
+    }
+
+    let mut total = 0.0;
+    while !rest.is_empty() {
+        let (number, after) = split_number(rest)?;
+        let after = after.trim_start();
+        let unit_end = after
+            .find(|c: char| c.is_whitespace() || c.is_ascii_digit())
+            .unwrap_or(after.len());
+        let unit = match &after[..unit_end] {
+            "" => default_unit,
+            unit => unit,
+        };
+        let (_, factor) = units.iter().find(|(name, _)| *name == unit)?;
+        total += number.parse::<f64>().ok()? * factor;
+        rest = after[unit_end..].trim_start();
+    }
+    Some(total)
+}
+
+/// Like `parse_mode()`: any octal number up to 07777, so leading zeros are fine
+fn is_mode(value: &str) -> bool {
+    u32::from_str_radix(value, 8).is_ok_and(|mode| mode <= 0o7777) && !value.starts_with('+')
+}
+
+fn is_signal(value: &str) -> bool {
+    if let Ok(number) = value.parse::<u32>() {
+        return (1..=64).contains(&number);
+    }
+
+    let name = value.strip_prefix("SIG").unwrap_or(value);
+    if SystemdConstants::SIGNALS.contains(&name) {
+        return true;
+    }
+    // Real-time signals relative to the range, e.g. SIGRTMIN+3
+    ["RTMIN+", "RTMAX-"].iter().any(|prefix| {
+        name.strip_prefix(prefix)
+            .is_some_and(|offset| offset.parse::<u32>().is_ok())
+    })
+}
+
+/// A plain-language reading of a value, e.g. `1h 30min (5400 seconds)` for `90min`
+pub fn interpret(entry: &DirectiveSchema, value: &str) -> Option<String> {
+    let value = value.trim();
+    if value.is_empty() || has_specifier(value) || entry.is_listed_value(value) {
+        return None;
+    }
+
+    match entry.value_type {
+        ValueType::TimeSpan => {
+            let usec = time_span_usec(value)?;
+            let seconds = usec / USEC_PER_SEC;
+            Some(format!(
+                "Time span of {} ({} second{})",
+                format_timespan(usec),
+                format_number(seconds),
+                if seconds == 1.0 { "" } else { "s" }
+            ))
+        }
+        ValueType::Size if !is_percentage(value) => {
+            let bytes = size_bytes(value)?;
+            Some(format!(
+                "{} bytes ({})",
+                format_number(bytes.floor()),
+                format_bytes(bytes)
+            ))
+        }
+        ValueType::Calendar => {
+            let normalized = normalize_calendar(value)?;
+            Some(format!("Calendar event, normalized `{}`", normalized))
+        }
+        ValueType::Mode => {
+            let mode = u32::from_str_radix(value, 8).ok()?;
+            if entry.name == "UMask" {
+                Some(format!(
+                    "New files get mode {:04o} ({}), new directories {:04o} ({})",
+                    0o666 & !mode,
+                    permissions(0o666 & !mode),
+                    0o777 & !mode,
+                    permissions(0o777 & !mode)
+                ))
+            } else {
+                Some(format!("Mode {:04o} ({})", mode, permissions(mode)))
+            }
+        }
+        ValueType::SocketAddress => {
+            let protocol = match entry.name {
+                "ListenDatagram" => ("UDP", "datagram"),
+                "ListenSequentialPacket" => ("SCTP", "sequential packet"),
+                _ => ("TCP", "stream"),
+            };
+            Some(match parse_socket_address(value).ok()? {
+                SocketAddress::Port(port) => format!(
+                    "{} port {} on every IPv6 and IPv4 address, unless BindIPv6Only=ipv6-only",
+                    protocol.0, port
+                ),
+                SocketAddress::Inet {
+                    address,
+                    interface,
+                    port,
+                } => {
+                    let family = if address.is_ipv4() { "IPv4" } else { "IPv6" };
+                    let every = if address.is_unspecified() {
+                        format!(" (every {} address)", family)
+                    } else {
+                        String::new()
+                    };
+                    let interface = interface
+                        .map(|interface| format!(" on interface {}", interface))
+                        .unwrap_or_default();
+                    format!(
+                        "{} port {} on {} address {}{}{}",
+                        protocol.0, port, family, address, every, interface
+                    )
+                }
+                SocketAddress::Unix(path) => {
+                    format!("Unix {} socket at {}", protocol.1, path)
+                }
+                SocketAddress::Abstract(name) => {
+                    format!("Abstract Unix {} socket {}", protocol.1, name)
+                }
+                SocketAddress::Vsock { cid, port } => format!(
+                    "VSOCK {} port {} on {}",
+                    protocol.1,
+                    port,
+                    cid.map_or("any CID".to_string(), |cid| format!("CID {}", cid))
+                ),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Longest path that fits in `sun_path` of a Unix socket address, leaving room for the NUL
+const UNIX_PATH_MAX: usize = 107;
+
+/// An address of `ListenStream=`, `ListenDatagram=` or `ListenSequentialPacket=`
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SocketAddress {
+    /// A port alone, bound on every IPv6 and IPv4 address
+    Port(u16),
+    Inet {
+        address: IpAddr,
+        interface: Option<String>,
+        port: u16,
+    },
+    Unix(String),
+    Abstract(String),
+    Vsock {
+        cid: Option<u32>,
+        port: u32,
+    },
+}
+
+/// Like `socket_address_parse()`: a port, `IPv4:port`, `[IPv6]:port` with an optional
+/// `%interface`, an absolute path, an `@abstract` name or `vsock:CID:PORT`
+fn parse_socket_address(value: &str) -> Result<SocketAddress, String> {
+    let port = |text: &str| -> Result<u16, String> {
+        match text.parse::<u32>() {
+            Ok(port @ 1..=65535) => Ok(port as u16),
+            Ok(port) => Err(format!(
+                "port {} is out of range, ports go from 1 to 65535",
+                port
+            )),
+            Err(_) => Err(format!("'{}' is not a port number", text)),
+        }
+    };
+
+    if value.starts_with('/') || value.starts_with('@') {
+        if value.len() > UNIX_PATH_MAX {
+            return Err(format!(
+                "the socket path is {} bytes long, at most {} fit in a Unix socket address",
+                value.len(),
+                UNIX_PATH_MAX
+            ));
+        }
+        return Ok(match value.strip_prefix('@') {
+            Some(_) => SocketAddress::Abstract(value.to_string()),
+            None => SocketAddress::Unix(value.to_string()),
+        });
+    }
+
+    if let Some(rest) = value.strip_prefix("vsock:") {
+        let (cid, vsock_port) = rest.split_once(':').ok_or_else(|| {
+            format!(
+                "'{}' needs a port, as in vsock:CID:PORT or vsock::PORT",
+                value
+            )
+        })?;
+        let cid = match cid {
+            "" => None,
+            cid => Some(
+                cid.parse::<u32>()
+                    .map_err(|_| format!("'{}' is not a VSOCK context identifier", cid))?,
+            ),
+        };
+        let port = vsock_port
+            .parse::<u32>()
+            .map_err(|_| format!("'{}' is not a VSOCK port number", vsock_port))?;
+        return Ok(SocketAddress::Vsock { cid, port });
+    }
+
+    if let Some(rest) = value.strip_prefix('[') {
+        let (address, rest) = rest
+            .split_once(']')
+            .ok_or_else(|| format!("'{}' is missing the ] closing the IPv6 address", value))?;
+        let (address, interface) = match address.split_once('%') {
+            Some((address, interface)) => (address, Some(interface.to_string())),
+            None => (address, None),
+        };
+        let address = address
+            .parse::<Ipv6Addr>()
+            .map_err(|_| format!("'{}' is not an IPv6 address", address))?;
+        let Some(text) = rest.strip_prefix(':') else {
+            return Err(format!(
+                "the IPv6 address needs a port, e.g. [{}]:80",
+                address
+            ));
+        };
+        return Ok(SocketAddress::Inet {
+            address: IpAddr::V6(address),
+            interface,
+            port: port(text)?,
+        });
+    }
+
+    if value.bytes().all(|byte| byte.is_ascii_digit()) {
+        return port(value).map(SocketAddress::Port);
+    }
+
+    match value.rsplit_once(':') {
+        Some((address, text)) if !address.contains(':') => {
+            let address = address
+                .parse::<Ipv4Addr>()
+                .map_err(|_| format!("'{}' is not an IPv4 address", address))?;
+            Ok(SocketAddress::Inet {
+                address: IpAddr::V4(address),
+                interface: None,
+                port: port(text)?,
+            })
+        }
+        _ if value.parse::<Ipv4Addr>().is_ok() => Err(format!(
+            "the IPv4 address needs a port, e.g. {}:80",
+            value
+        )),
+        _ if value.parse::<Ipv6Addr>().is_ok() => Err(format!(
+            "IPv6 addresses go in brackets followed by a port, e.g. [{}]:80",
+            value
+        )),
+        _ if value.contains('/') || value.ends_with(".sock") => {
+            let path = value.trim_start_matches("./");
+            let example = if path.starts_with("run/") {
+                format!("/{}", path)
+            } else {
+                format!("/run/{}", path)
+            };
+            Err(format!("socket paths must be absolute, e.g. {}", example))
+        }
+        _ => Err(format!(
+            "'{}' is not a port, IPv4 or [IPv6] address with a port, absolute socket path, @abstract name or vsock:CID:PORT",
+            value
+        )),
+    }
+}
+
+/// Like `format_timespan()`: "1h 30min", "2min 500ms"
+fn format_timespan(usec: f64) -> String {
+    let mut rest = usec.round();
+    let mut parts = Vec::new();
+    for (unit, factor) in TIMESPAN_FORMAT {
+        let count = (rest / factor).floor();
+        if count >= 1.0 {
+            parts.push(format!("{}{}", count, unit));
+            rest -= count * factor;
+        }
+    }
+    if parts.is_empty() {
+        return "0".to_string();
+    }
+    parts.join(" ")
+}
+
+fn format_number(number: f64) -> String {
+    let text = format!("{:.3}", number);
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+fn format_bytes(bytes: f64) -> String {
+    let (suffix, factor) = SIZE_SUFFIXES
+        .iter()
+        .find(|(_, factor)| bytes >= *factor)
+        .unwrap_or(&("B", 1.0));
+    match *suffix {
+        "" | "B" => format!("{} B", format_number(bytes)),
+        suffix => format!("{:.1} {}iB", bytes / factor, suffix).replace(".0 ", " "),
+    }
+}
+
+/// `rwxr-xr-x` for 0755, with the setuid, setgid and sticky bits as `s` and `t`
+fn permissions(mode: u32) -> String {
+    let mut text = String::new();
+    for (shift, special, special_char) in [(6, 0o4000, 's'), (3, 0o2000, 's'), (0, 0o1000, 't')] {
+        let bits = (mode >> shift) & 0o7;
+        text.push(if bits & 4 != 0 { 'r' } else { '-' });
+        text.push(if bits & 2 != 0 { 'w' } else { '-' });
+        text.push(match (bits & 1 != 0, mode & special != 0) {
+            (true, true) => special_char,
+            (false, true) => special_char.to_ascii_uppercase(),
+            (true, false) => 'x',
+            (false, false) => '-',
+        });
+    }
+    text
+}
+
+/// Expand shorthands and fill in the parts an event leaves out, e.g. `Mon..Fri 9:00` to
+/// `Mon..Fri *-*-* 09:00:00`, the way `systemd-analyze calendar` prints it
+fn normalize_calendar(value: &str) -> Option<String> {
+    if let Some((_, normalized)) = CALENDAR_SHORTHANDS
+        .iter()
+        .find(|(shorthand, _)| shorthand.eq_ignore_ascii_case(value))
+    {
+        return Some(normalized.to_string());
+    }
+
+    let mut weekdays = None;
+    let mut date = None;
+    let mut time = None;
+    let mut timezone = None;
+    for token in value.split_whitespace() {
+        if token.contains(':') && time.is_none() {
+            time = Some(token);
+        } else if token.contains('-') && date.is_none() && time.is_none() {
+            date = Some(token);
+        } else if token.starts_with(|c: char| c.is_ascii_alphabetic())
+            && weekdays.is_none()
+            && date.is_none()
+            && time.is_none()
+        {
+            weekdays = Some(token);
+        } else if time.is_some() && timezone.is_none() {
+            timezone = Some(token);
+        } else {
+            return None;
+        }
+    }
+
+    let date = match date {
+        Some(date) if date.matches('-').count() == 1 => format!("*-{}", date),
+        Some(date) => date.to_string(),
+        None => "*-*-*".to_string(),
+    };
+    let time = match time {
+        Some(time) => {
+            let mut fields: Vec<String> = time
+                .split(':')
+                .map(|field| match field.parse::<u32>() {
+                    Ok(number) if field.len() == 1 => format!("{:02}", number),
+                    _ => field.to_string(),
+                })
+                .collect();
+            if fields.len() == 2 {
+                fields.push("00".to_string());
+            }
+            fields.join(":")
+        }
+        None => "00:00:00".to_string(),
+    };
+
+    let parts: Vec<String> = [weekdays.map(str::to_string), Some(date), Some(time)]
+        .into_iter()
+        .flatten()
+        .chain(timezone.map(str::to_string))
+        .collect();
+    Some(parts.join(" "))
+}
+
+fn validate_paths(entry: &DirectiveSchema, value: &str) -> Result<(), String> {
+    let paths: Vec<&str> = if entry.list {
+        value.split_whitespace().collect()
+    } else {
+        vec![value]
+    };
+
+    for path in paths {
+        let path = path.trim_matches(|c| c == '"' || c == '\'');
+        // '-' ignores missing paths, '+' resolves relative to RootDirectory=, '!' and '|'
+        // negate and trigger conditions; WorkingDirectory= also accepts the home directory
+        let stripped = path.trim_start_matches(['-', '+', '!', '|']);
+        let home = stripped == "~" && entry.name == "WorkingDirectory";
+        if home || stripped.starts_with('/') {
+            continue;
+        }
+        if stripped.starts_with('~') {
+            return Err(format!(
+                "'{}' is not an absolute path, only WorkingDirectory=~ stands for the home directory",
+                path
+            ));
+        }
+        return Err(format!("'{}' is not an absolute path", path));
+    }
+    Ok(())
+}
+
+/// CPU and NUMA node sets: indices and ranges separated by commas or whitespace
+fn validate_cpu_set(value: &str) -> Result<(), String> {
+    for item in value.split(|c: char| c == ',' || c.is_whitespace()) {
+        if item.is_empty() {
+            continue;
+        }
+        let (start, end) = item.split_once('-').unwrap_or((item, item));
+        match (start.parse::<u32>(), end.parse::<u32>()) {
+            (Ok(start), Ok(end)) if start <= end => {}
+            (Ok(_), Ok(_)) => return Err(format!("range '{}' is reversed", item)),
+            _ => {
+                return Err(format!(
+                    "'{}' is not a CPU index or range such as 0-3",
+                    item
+                ))
+            }
+        }
+    }
+    Ok(())
+}
+
+fn validate_exit_statuses(value: &str) -> Result<(), String> {
+    for item in value.split_whitespace() {
+        let valid = match item.parse::<u32>() {
+            Ok(status) => status <= 255,
+            Err(_) => EXIT_STATUSES.contains(&item) || (item.starts_with("SIG") && is_signal(item)),
+        };
+        if !valid {
+            return Err(format!(
+                "'{}' is not an exit status (0-255), exit status name or signal",
+                item
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn validate_unit_names(value: &str) -> Result<(), String> {
+    for name in value.split_whitespace() {
+        let Some(error) = unit_name::unit_name_error(name) else {
+            continue;
+        };
+        let suggestion = value_providers::STANDARD_UNITS
+            .iter()
+            .find(|unit| unit.rsplit_once('.').is_some_and(|(stem, _)| stem == name));
+        return Err(match suggestion {
+            Some(unit) => format!("'{}' is not a unit name, did you mean {}?", name, unit),
+            None => error,
+        });
+    }
+    Ok(())
+}
+
+/// The comma separated options of `Options=`. Options of the file system itself, such as
+/// `uid=` or `compress=`, are not known here and pass.
+fn validate_mount_options(value: &str) -> Result<(), String> {
+    for option in value.split(',').map(str::trim) {
+        let (name, argument) = match option.split_once('=') {
+            Some((name, argument)) => (name, Some(argument)),
+            None => (option, None),
+        };
+        if argument.is_some() && MOUNT_FLAGS.contains(&name) {
+            return Err(format!("the mount flag {} takes no value", name));
+        }
+        if !name.starts_with("x-systemd.") && !name.starts_with("x-initrd.") {
+            continue;
+        }
+        match SYSTEMD_MOUNT_OPTIONS
+            .iter()
+            .find(|(known, _)| *known == name)
+        {
+            None => {
+                return Err(format!(
+                    "'{}' is not one of the x-systemd. options of systemd.mount(5)",
+                    name
+                ))
+            }
+            Some((_, Some(true))) if argument.is_none_or(str::is_empty) => {
+                return Err(format!("{} needs a value, as in {}=…", name, name))
+            }
+            Some((_, Some(false))) if argument.is_some() => {
+                return Err(format!("{} takes no value", name))
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// The entries of a list that `~` as its first character turns into a deny list
+fn negatable_items(value: &str) -> (bool, std::str::SplitWhitespace<'_>) {
+    match value.strip_prefix('~') {
+        Some(rest) => (true, rest.split_whitespace()),
+        None => (false, value.split_whitespace()),
+    }
+}
+
+/// System calls and `@groups`, each optionally followed by `:errno` in a `~` deny list
+fn validate_syscalls(value: &str) -> Result<(), String> {
+    let (deny, items) = negatable_items(value);
+    for item in items {
+        let (name, errno) = match item.split_once(':') {
+            Some((name, errno)) => (name, Some(errno)),
+            None => (item, None),
+        };
+        if let Some(group) = name.strip_prefix('@') {
+            if sandboxing::syscall_group(group).is_none() {
+                return Err(format!("'{}' is not a system call group", name));
+            }
+        } else if name.starts_with('~') {
+            return Err(format!(
+                "only the first entry may start with '~', which negates the whole list; '{}' does not",
+                item
+            ));
+        } else if !sandboxing::is_syscall(name) {
+            return Err(format!("'{}' is not a known system call", name));
+        }
+
+        match errno {
+            Some(_) if !deny => {
+                return Err(format!(
+                    "'{}' returns an error but only a '~' deny list can, allowed system calls always run",
+                    item
+                ))
+            }
+            Some(errno) if !sandboxing::is_errno(errno) => {
+                return Err(format!(
+                    "'{}' is not an error name such as EPERM or a number up to 4095",
+                    errno
+                ))
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn validate_address_families(value: &str) -> Result<(), String> {
+    let (_, items) = negatable_items(value);
+    for item in items {
+        if !sandboxing::ADDRESS_FAMILIES.contains(&item) {
+            return Err(format!(
+                "'{}' is not an address family such as AF_UNIX, AF_INET or AF_INET6",
+                item
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn validate_capabilities(value: &str) -> Result<(), String> {
+    let (_, items) = negatable_items(value);
+    for item in items {
+        if !sandboxing::is_capability(item) {
+            return Err(format!(
+                "'{}' is not a capability such as CAP_NET_BIND_SERVICE",
+                item
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialect::UnitDialect;
+    use crate::schema;
+
+    fn check_value(section: &str, directive: &str, value: &str) -> Result<(), String> {
+        validate(
+            schema::lookup(UnitDialect::Systemd, section, directive).unwrap(),
+            value,
+        )
+    }
+
+    #[test]
+    fn test_booleans_accept_every_spelling() {
+        for value in ["on", "off", "y", "n", "Yes", "TRUE", "t", "f", "1", "0"] {
+            assert!(
+                check_value("Service", "NoNewPrivileges", value).is_ok(),
+                "{}",
+                value
+            );
+        }
+        assert!(check_value("Service", "NoNewPrivileges", "enabled").is_err());
+    }
+
+    #[test]
+    fn test_sizes() {
+        for value in [
+            "512M", "1G", "1.5G", "1G 512M", "1024", "512B", "infinity", "50%",
+        ] {
+            assert!(
+                check_value("Service", "MemoryMax", value).is_ok(),
+                "{}",
+                value
+            );
+        }
+        let error = check_value("Service", "MemoryMax", "512MB").unwrap_err();
+        assert!(error.contains("suffix"), "{}", error);
+        assert!(check_value("Service", "MemoryMax", "lots").is_err());
+    }
+
+    #[test]
+    fn test_percentages_and_time_spans() {
+        assert!(check_value("Service", "CPUQuota", "200%").is_ok());
+        assert!(check_value("Service", "CPUQuota", "200").is_err());
+
+        for value in [
+            "90", "90s", "5min 30s", "1h30m", "100ms", "2.5s", "infinity",
+        ] {
+            assert!(
+                check_value("Service", "TimeoutStartSec", value).is_ok(),
+                "{}",
+                value
+            );
+        }
+        assert!(check_value("Service", "TimeoutStartSec", "5 minutez").is_err());
+    }
+
+    #[test]
+    fn test_paths_and_modes() {
+        assert!(check_value("Service", "WorkingDirectory", "/srv/app").is_ok());
+        assert!(check_value("Service", "WorkingDirectory", "-~").is_ok());
+        assert!(check_value("Service", "WorkingDirectory", "%h/app").is_ok());
+        assert!(check_value("Service", "WorkingDirectory", "srv/app").is_err());
+        assert_eq!(
+            check_value("Service", "RootDirectory", "~"),
+            Err("'~' is not an absolute path, only WorkingDirectory=~ stands for the home directory".to_string())
+        );
+        assert!(check_value("Service", "RootDirectory", "-~/app").is_err());
+        assert!(check_value("Service", "ReadWritePaths", "/srv ~/cache").is_err());
+        assert!(check_value("Service", "ReadWritePaths", "/var/lib/app -/run/app").is_ok());
+        assert!(check_value("Service", "ReadWritePaths", "/var/lib/app run/app").is_err());
+
+        assert!(check_value("Service", "UMask", "0027").is_ok());
+        assert!(check_value("Service", "LogsDirectoryMode", "02755").is_ok());
+        assert!(check_value("Service", "LogsDirectoryMode", "17777").is_err());
+        assert!(check_value("Service", "UMask", "0829").is_err());
+    }
+
+    #[test]
+    fn test_interpretations() {
+        let interpret_value = |section, directive, value| {
+            interpret(
+                schema::lookup(UnitDialect::Systemd, section, directive).unwrap(),
+                value,
+            )
+        };
+
+        assert_eq!(
+            interpret_value("Service", "TimeoutStartSec", "90min").unwrap(),
+            "Time span of 1h 30min (5400 seconds)"
+        );
+        assert_eq!(
+            interpret_value("Service", "RestartSec", "1.5").unwrap(),
+            "Time span of 1s 500ms (1.5 seconds)"
+        );
+        assert_eq!(
+            interpret_value("Service", "MemoryMax", "1G 512M").unwrap(),
+            "1610612736 bytes (1.5 GiB)"
+        );
+        assert_eq!(
+            interpret_value("Timer", "OnCalendar", "daily").unwrap(),
+            "Calendar event, normalized `*-*-* 00:00:00`"
+        );
+        assert_eq!(
+            interpret_value("Timer", "OnCalendar", "Mon..Fri 9:00").unwrap(),
+            "Calendar event, normalized `Mon..Fri *-*-* 09:00:00`"
+        );
+        assert_eq!(
+            interpret_value("Service", "UMask", "0027").unwrap(),
+            "New files get mode 0640 (rw-r-----), new directories 0750 (rwxr-x---)"
+        );
+        assert_eq!(
+            interpret_value("Service", "LogsDirectoryMode", "02755").unwrap(),
+            "Mode 2755 (rwxr-sr-x)"
+        );
+        assert!(interpret_value("Service", "TimeoutStartSec", "infinity").is_none());
+        assert!(interpret_value("Service", "MemoryMax", "50%").is_none());
+        assert!(interpret_value("Service", "Restart", "on-failure").is_none());
+    }
+
+    #[test]
+    fn test_cpu_sets_signals_and_exit_statuses() {
+        assert!(check_value("Service", "CPUAffinity", "0-3,8").is_ok());
+        assert!(check_value("Service", "CPUAffinity", "0 2 4-7").is_ok());
+        assert!(check_value("Service", "CPUAffinity", "3-1").is_err());
+        assert!(check_value("Service", "CPUAffinity", "first").is_err());
+
+        for value in ["SIGTERM", "TERM", "9", "SIGRTMIN+3"] {
+            assert!(
+                check_value("Service", "KillSignal", value).is_ok(),
+                "{}",
+                value
+            );
+        }
+        assert!(check_value("Service", "KillSignal", "SIGTREM").is_err());
+
+        assert!(check_value("Service", "SuccessExitStatus", "0 143 SIGTERM TEMPFAIL").is_ok());
+        assert!(check_value("Service", "SuccessExitStatus", "256").is_err());
+        assert!(check_value("Service", "SuccessExitStatus", "SIGTREM").is_err());
+    }
+
+    #[test]
+    fn test_syscalls_address_families_and_capabilities() {
+        for value in [
+            "@system-service",
+            "@system-service @network-io",
+            "~@privileged @resources",
+            "~@mount:EPERM ptrace:EACCES reboot:kill",
+        ] {
+            assert!(
+                check_value("Service", "SystemCallFilter", value).is_ok(),
+                "{}",
+                value
+            );
+        }
+        for value in ["@system-service ~@privileged", "openat read:38"] {
+            assert!(
+                check_value("Service", "SystemCallFilter", value).is_err(),
+                "{}",
+                value
+            );
+        }
+        assert_eq!(
+            check_value("Service", "SystemCallFilter", "@sytem-service"),
+            Err("'@sytem-service' is not a system call group".to_string())
+        );
+        assert_eq!(
+            check_value("Service", "SystemCallLog", "~open_at"),
+            Err("'open_at' is not a known system call".to_string())
+        );
+        assert!(check_value("Service", "SystemCallFilter", "~ptrace:ENOPE").is_err());
+
+        assert!(check_value(
+            "Service",
+            "RestrictAddressFamilies",
+            "AF_UNIX AF_INET AF_INET6"
+        )
+        .is_ok());
+        assert!(check_value(
+            "Service",
+            "RestrictAddressFamilies",
+            "~AF_PACKET AF_NETLINK"
+        )
+        .is_ok());
+        assert!(check_value("Service", "RestrictAddressFamilies", "AF_INET4").is_err());
+        assert!(check_value("Service", "RestrictAddressFamilies", "af_unix").is_err());
+
+        assert!(check_value(
+            "Service",
+            "CapabilityBoundingSet",
+            "CAP_NET_BIND_SERVICE cap_chown"
+        )
+        .is_ok());
+        assert!(check_value("Service", "AmbientCapabilities", "~CAP_SYS_ADMIN").is_ok());
+        assert!(check_value("Service", "CapabilityBoundingSet", "CAP_NET_BIND").is_err());
+    }
+
+    #[test]
+    fn test_unit_names() {
+        for value in [
+            "multi-user.target",
+            "getty@tty1.service getty@.service",
+            "-.slice",
+            "dev-disk-by\\x2duuid-1234.device",
+        ] {
+            assert!(
+                check_value("Install", "WantedBy", value).is_ok(),
+                "{}",
+                value
+            );
+        }
+        assert_eq!(
+            check_value("Install", "WantedBy", "multi-user"),
+            Err("'multi-user' is not a unit name, did you mean multi-user.target?".to_string())
+        );
+        assert_eq!(
+            check_value("Unit", "After", "network.target app"),
+            Err(
+                "'app' is not a unit name with a type suffix such as .service or .target"
+                    .to_string()
+            )
+        );
+        assert!(check_value("Install", "Also", "app.conf").is_err());
+        assert!(check_value("Service", "Sockets", "a@b@c.socket").is_err());
+    }
+
+    #[test]
+    fn test_socket_addresses() {
+        for value in [
+            "8080",
+            "0.0.0.0:8080",
+            "[::1]:53",
+            "[fe80::1%eth0]:80",
+            "/run/app/app.sock",
+            "@app",
+            "vsock:2:1234",
+            "vsock::1234",
+        ] {
+            assert!(
+                check_value("Socket", "ListenStream", value).is_ok(),
+                "{}",
+                value
+            );
+        }
+
+        for (value, error) in [
+            ("0", "port 0 is out of range, ports go from 1 to 65535"),
+            (
+                "0.0.0.0:70000",
+                "port 70000 is out of range, ports go from 1 to 65535",
+            ),
+            ("1.2.3.400:80", "'1.2.3.400' is not an IPv4 address"),
+            (
+                "127.0.0.1",
+                "the IPv4 address needs a port, e.g. 127.0.0.1:80",
+            ),
+            ("[::1]", "the IPv6 address needs a port, e.g. [::1]:80"),
+            (
+                "::1",
+                "IPv6 addresses go in brackets followed by a port, e.g. [::1]:80",
+            ),
+            (
+                "run/app.sock",
+                "socket paths must be absolute, e.g. /run/app.sock",
+            ),
+            (
+                "./app.sock",
+                "socket paths must be absolute, e.g. /run/app.sock",
+            ),
+            (
+                "vsock:2",
+                "'vsock:2' needs a port, as in vsock:CID:PORT or vsock::PORT",
+            ),
+            ("vsock:host:80", "'host' is not a VSOCK context identifier"),
+        ] {
+            assert_eq!(
+                check_value("Socket", "ListenDatagram", value),
+                Err(error.to_string()),
+                "{}",
+                value
+            );
+        }
+        let long_path = format!("/run/{}.sock", "a".repeat(110));
+        assert!(check_value("Socket", "ListenStream", &long_path)
+            .unwrap_err()
+            .contains("at most 107 fit"));
+
+        let interpret_socket = |directive: &str, value: &str| {
+            let entry = schema::lookup(UnitDialect::Systemd, "Socket", directive).unwrap();
+            interpret(entry, value).unwrap()
+        };
+        assert_eq!(
+            interpret_socket("ListenStream", "8080"),
+            "TCP port 8080 on every IPv6 and IPv4 address, unless BindIPv6Only=ipv6-only"
+        );
+        assert_eq!(
+            interpret_socket("ListenDatagram", "0.0.0.0:53"),
+            "UDP port 53 on IPv4 address 0.0.0.0 (every IPv4 address)"
+        );
+        assert_eq!(
+            interpret_socket("ListenStream", "/run/app.sock"),
+            "Unix stream socket at /run/app.sock"
+        );
+        assert_eq!(
+            interpret_socket("ListenSequentialPacket", "vsock::1234"),
+            "VSOCK sequential packet port 1234 on any CID"
+        );
+    }
+
+    #[test]
+    fn test_mount_options() {
+        for value in [
+            "defaults",
+            "noatime,x-systemd.requires=network-online.target,uid=1000",
+            "ro,nofail,x-systemd.device-timeout=10s,x-systemd.device-bound",
+            "rw,noatime,ro",
+        ] {
+            assert!(check_value("Mount", "Options", value).is_ok(), "{}", value);
+        }
+
+        for (value, error) in [
+            ("ro=1", "the mount flag ro takes no value"),
+            (
+                "x-systemd.require=foo.mount",
+                "'x-systemd.require' is not one of the x-systemd. options of systemd.mount(5)",
+            ),
+            (
+                "x-systemd.after",
+                "x-systemd.after needs a value, as in x-systemd.after=…",
+            ),
+            ("x-systemd.growfs=yes", "x-systemd.growfs takes no value"),
+        ] {
+            assert_eq!(
+                check_value("Mount", "Options", value),
+                Err(error.to_string())
+            );
+        }
+    }
+}
@@ -0,0 +1,347 @@
+//! Values that name something on the system or in the workspace instead of coming from
+//! a fixed list: units, users, groups, paths and signals, and the system calls, address
+//! families and capabilities the sandboxing directives take.
+
+use crate::constants::SystemdConstants;
+use crate::dialect::UnitDialect;
+use crate::sandboxing;
+use crate::schema::{self, ValueType};
+use std::fs;
+use std::path::Path;
+
+/// Where the values of a directive come from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueProvider {
+    Units,
+    Users,
+    Groups,
+    Paths,
+    Signals,
+    Syscalls,
+    AddressFamilies,
+    Capabilities,
+}
+
+/// Providers for directives whose schema type does not tell, e.g. the plain-string `User=`
+const DIRECTIVE_PROVIDERS: &[(&str, ValueProvider)] = &[
+    ("User", ValueProvider::Users),
+    ("SocketUser", ValueProvider::Users),
+    ("Group", ValueProvider::Groups),
+    ("SocketGroup", ValueProvider::Groups),
+    ("SupplementaryGroups", ValueProvider::Groups),
+    ("PIDFile", ValueProvider::Paths),
+];
+
+/// Well-known units from systemd.special(7) that units commonly order themselves against
+pub const STANDARD_UNITS: &[&str] = &[
+    "basic.target",
+    "default.target",
+    "emergency.target",
+    "getty.target",
+    "graphical.target",
+    "local-fs.target",
+    "local-fs-pre.target",
+    "multi-user.target",
+    "network.target",
+    "network-online.target",
+    "network-pre.target",
+    "nss-lookup.target",
+    "nss-user-lookup.target",
+    "paths.target",
+    "remote-fs.target",
+    "rescue.target",
+    "shutdown.target",
+    "sockets.target",
+    "sysinit.target",
+    "time-set.target",
+    "time-sync.target",
+    "timers.target",
+    "dbus.socket",
+    "system.slice",
+    "user.slice",
+    "machine.slice",
+];
+
+/// Extensions of the files that are units in their own right
+const UNIT_TYPES: &[&str] = &[
+    "service",
+    "socket",
+    "timer",
+    "target",
+    "mount",
+    "automount",
+    "swap",
+    "path",
+    "slice",
+    "scope",
+    "device",
+];
+
+/// Upper bound on the entries listed for one directory
+const MAX_PATH_ENTRIES: usize = 200;
+
+impl ValueProvider {
+    /// The provider for a directive, by its name first and its value type second
+    pub fn for_directive(dialect: UnitDialect, section: &str, directive: &str) -> Option<Self> {
+        if !dialect.is_unit() {
+            return None;
+        }
+        let entry = schema::find(dialect, section, directive)?;
+        if let Some((_, provider)) = DIRECTIVE_PROVIDERS
+            .iter()
+            .find(|(name, _)| *name == entry.name)
+        {
+            return Some(*provider);
+        }
+
+        match entry.value_type {
+            ValueType::Units => Some(ValueProvider::Units),
+            ValueType::Path => Some(ValueProvider::Paths),
+            ValueType::Signal => Some(ValueProvider::Signals),
+            ValueType::Syscalls => Some(ValueProvider::Syscalls),
+            ValueType::AddressFamilies => Some(ValueProvider::AddressFamilies),
+            ValueType::Capabilities => Some(ValueProvider::Capabilities),
+            _ => None,
+        }
+    }
+
+    /// Completion detail shown next to the values of this provider
+    pub fn detail(&self) -> &'static str {
+        match self {
+            ValueProvider::Units => "unit",
+            ValueProvider::Users => "user",
+            ValueProvider::Groups => "group",
+            ValueProvider::Paths => "path",
+            ValueProvider::Signals => "signal",
+            ValueProvider::Syscalls => "system call",
+            ValueProvider::AddressFamilies => "address family",
+            ValueProvider::Capabilities => "capability",
+        }
+    }
+}
+
+/// Whether a file name such as `app.service` names a unit other units can refer to
+pub fn is_unit_name(name: &str) -> bool {
+    name.rsplit_once('.')
+        .is_some_and(|(stem, extension)| !stem.is_empty() && UNIT_TYPES.contains(&extension))
+}
+
+/// Account names from a passwd(5) or group(5) file
+pub fn account_names(text: &str) -> Vec<String> {
+    text.lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split(':').next())
+        .filter(|name| !name.is_empty() && !name.starts_with('+') && !name.starts_with('-'))
+        .map(str::to_string)
+        .collect()
+}
+
+pub fn system_users() -> Vec<String> {
+    fs::read_to_string("/etc/passwd")
+        .map(|text| account_names(&text))
+        .unwrap_or_default()
+}
+
+pub fn system_groups() -> Vec<String> {
+    fs::read_to_string("/etc/group")
+        .map(|text| account_names(&text))
+        .unwrap_or_default()
+}
+
+/// Signal names with their `SIG` prefix, e.g. `SIGTERM`
+pub fn signal_names() -> Vec<String> {
+    SystemdConstants::SIGNALS
+        .iter()
+        .map(|name| format!("SIG{}", name))
+        .collect()
+}
+
+/// Names completed after the `~` that turns a list into a deny list, when it was typed
+fn negatable(typed: &str, names: impl Iterator<Item = String>) -> Vec<String> {
+    let prefix = if typed.starts_with('~') { "~" } else { "" };
+    names.map(|name| format!("{}{}", prefix, name)).collect()
+}
+
+/// `@groups` and system calls, or the error names after `name:`, e.g. `ptrace:EPERM`
+pub fn syscall_completions(typed: &str) -> Vec<String> {
+    if let Some((name, _)) = typed.split_once(':') {
+        return sandboxing::ERRNO_NAMES
+            .iter()
+            .map(|errno| format!("{}:{}", name, errno))
+            .collect();
+    }
+
+    let groups = sandboxing::syscall_groups()
+        .iter()
+        .map(|group| format!("@{}", group.name));
+    let syscalls = sandboxing::syscall_group("known")
+        .map(|known| known.members.clone())
+        .unwrap_or_default();
+    negatable(
+        typed,
+        groups.chain(syscalls.into_iter().map(str::to_string)),
+    )
+}
+
+pub fn address_family_completions(typed: &str) -> Vec<String> {
+    negatable(
+        typed,
+        sandboxing::ADDRESS_FAMILIES
+            .iter()
+            .map(|name| name.to_string()),
+    )
+}
+
+pub fn capability_completions(typed: &str) -> Vec<String> {
+    negatable(
+        typed,
+        sandboxing::CAPABILITIES.iter().map(|name| name.to_string()),
+    )
+}
+
+/// Complete an absolute path typed so far, keeping prefixes such as `-` in
+/// `EnvironmentFile=-/etc/default/app`. Directories end in `/`.
+pub fn path_completions(typed: &str) -> Vec<String> {
+    let path = typed.trim_start_matches(['-', '+', '!', ':', '@']);
+    let prefix = &typed[..typed.len() - path.len()];
+    let path = if path.is_empty() { "/" } else { path };
+    if !path.starts_with('/') {
+        return Vec::new();
+    }
+
+    let (directory, partial) = path.rsplit_once('/').unwrap_or(("", path));
+    let directory = format!("{}/", directory);
+    let Ok(entries) = fs::read_dir(Path::new(&directory)) else {
+        return Vec::new();
+    };
+
+    let mut completions: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            if !name.starts_with(partial) || (name.starts_with('.') && !partial.starts_with('.')) {
+                return None;
+            }
+            let suffix = if entry.path().is_dir() { "/" } else { "" };
+            Some(format!("{}{}{}{}", prefix, directory, name, suffix))
+        })
+        .collect();
+    completions.sort();
+    completions.truncate(MAX_PATH_ENTRIES);
+    completions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_providers_by_directive() {
+        let provider = |section, directive| {
+            ValueProvider::for_directive(UnitDialect::Systemd, section, directive)
+        };
+
+        assert_eq!(provider("Unit", "After"), Some(ValueProvider::Units));
+        assert_eq!(provider("Install", "WantedBy"), Some(ValueProvider::Units));
+        assert_eq!(provider("Service", "User"), Some(ValueProvider::Users));
+        assert_eq!(
+            provider("Socket", "SocketGroup"),
+            Some(ValueProvider::Groups)
+        );
+        assert_eq!(
+            provider("Service", "WorkingDirectory"),
+            Some(ValueProvider::Paths)
+        );
+        assert_eq!(
+            provider("Service", "EnvironmentFile"),
+            Some(ValueProvider::Paths)
+        );
+        assert_eq!(
+            provider("Service", "KillSignal"),
+            Some(ValueProvider::Signals)
+        );
+        assert_eq!(
+            provider("Service", "SystemCallFilter"),
+            Some(ValueProvider::Syscalls)
+        );
+        assert_eq!(
+            provider("Service", "RestrictAddressFamilies"),
+            Some(ValueProvider::AddressFamilies)
+        );
+        assert_eq!(
+            provider("Service", "AmbientCapabilities"),
+            Some(ValueProvider::Capabilities)
+        );
+        assert_eq!(provider("Service", "Type"), None);
+        assert_eq!(
+            ValueProvider::for_directive(UnitDialect::Journald, "Journal", "Storage"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_account_names() {
+        let passwd = "root:x:0:0:root:/root:/bin/bash\n# comment\nwww-data:x:33:33::/var/www:/usr/sbin/nologin\n+@netgroup\n";
+        assert_eq!(account_names(passwd), ["root", "www-data"]);
+    }
+
+    #[test]
+    fn test_unit_names() {
+        assert!(is_unit_name("app.service"));
+        assert!(is_unit_name("getty@.service"));
+        assert!(!is_unit_name("app.container"));
+        assert!(!is_unit_name("journald.conf"));
+        assert!(!is_unit_name(".service"));
+    }
+
+    #[test]
+    fn test_path_completions() {
+        let temp = tempfile::tempdir().unwrap();
+        let directory = temp.path().to_path_buf();
+        fs::create_dir_all(directory.join("conf.d")).unwrap();
+        fs::write(directory.join("app.env"), "").unwrap();
+        fs::write(directory.join(".hidden"), "").unwrap();
+        let base = directory.display().to_string();
+
+        let completions = path_completions(&format!("{}/", base));
+        assert_eq!(
+            completions,
+            [format!("{}/app.env", base), format!("{}/conf.d/", base)]
+        );
+        assert_eq!(
+            path_completions(&format!("-{}/ap", base)),
+            [format!("-{}/app.env", base)]
+        );
+        assert_eq!(
+            path_completions(&format!("{}/.h", base)),
+            [format!("{}/.hidden", base)]
+        );
+        assert!(path_completions("relative/path").is_empty());
+        assert!(path_completions("")
+            .iter()
+            .all(|path| path.starts_with('/')));
+
+    }
+
+    #[test]
+    fn test_signal_names() {
+        let signals = signal_names();
+        assert!(signals.contains(&"SIGTERM".to_string()));
+        assert!(signals.iter().all(|signal| signal.starts_with("SIG")));
+    }
+
+    #[test]
+    fn test_sandboxing_completions() {
+        let syscalls = syscall_completions("");
+        assert_eq!(syscalls[0], "@default");
+        assert!(syscalls.contains(&"@system-service".to_string()));
+        assert!(syscalls.contains(&"openat".to_string()));
+        assert!(syscall_completions("~@p")
+            .iter()
+            .all(|name| name.starts_with('~')));
+        assert!(syscall_completions("ptrace:E").contains(&"ptrace:EPERM".to_string()));
+
+        assert!(address_family_completions("~AF").contains(&"~AF_PACKET".to_string()));
+        assert!(capability_completions("CAP").contains(&"CAP_NET_BIND_SERVICE".to_string()));
+    }
+}
@@ -7,7 +7,7 @@ static INIT: Once = Once::new();
 fn ensure_binary_built() {
     INIT.call_once(|| {
         let status = Command::new("cargo")
-            .args(&["build", "--release"])
+            .args(["build", "--release"])
             .status()
             .expect("Failed to build systemd-lsp");
         assert!(status.success(), "Failed to build binary");
@@ -215,20 +215,20 @@ fn test_expected_error_count() {
         .filter(|line| line.contains("error") || line.contains("warning") || line.contains("unknown"))
         .collect();
 
-    // We expect 9 diagnostics total (7 errors + 2 warnings)
+    // We expect 10 diagnostics total (8 errors + 2 warnings)
     // This is a regression test - if this number changes, it might indicate
     // a change in diagnostic behavior
     assert!(
-        diagnostic_lines.len() >= 9,
-        "Expected at least 9 diagnostics, found {}. Diagnostics:\n{}",
+        diagnostic_lines.len() >= 10,
+        "Expected at least 10 diagnostics, found {}. Diagnostics:\n{}",
         diagnostic_lines.len(),
         diagnostic_lines.join("\n")
     );
 
     // Verify the summary shows both errors and warnings
     assert!(
-        stdout.contains("7 error(s)") && stdout.contains("2 warning(s)"),
-        "Summary should show 7 errors and 2 warnings"
+        stdout.contains("8 error(s)") && stdout.contains("2 warning(s)"),
+        "Summary should show 8 errors and 2 warnings"
     );
 }
 
@@ -259,3 +259,295 @@ fn test_line_numbers_in_output() {
         );
     }
 }
+
+#[test]
+fn test_cli_quadlet_preview() {
+    let (stdout, _stderr, exit_code) =
+        run_systemd_lsp(&["quadlet", "examples/quadlet-example.container"]);
+
+    assert_eq!(exit_code, 0, "Expected exit code 0 for a valid Quadlet file");
+    assert!(
+        stdout.contains("ExecStart=/usr/bin/podman run"),
+        "Should render the podman run command"
+    );
+    assert!(
+        stdout.contains("docker.io/nginx:latest"),
+        "Should pass the image to podman run"
+    );
+    assert!(
+        stdout.contains("[Install]\nWantedBy=multi-user.target"),
+        "Should pass the [Install] section through"
+    );
+}
+
+#[test]
+fn test_cli_networkd_and_quadlet_network_files() {
+    let (stdout, _stderr, exit_code) =
+        run_systemd_lsp(&["examples/lan.network", "examples/mynetwork.network"]);
+
+    assert_eq!(
+        exit_code, 0,
+        "Both the networkd and the Quadlet .network example should be valid:\n{}",
+        stdout
+    );
+    assert!(stdout.contains("All 2 files are valid"));
+
+    // Quadlet keys are not valid in a file under a systemd-networkd search directory
+    let temp = tempfile::tempdir().unwrap();
+    let dir = temp.path().join("systemd/network");
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("10-bad.network");
+    std::fs::write(&file, "[Network]\nNetworkName=app\n").unwrap();
+
+    let (stdout, _stderr, _exit_code) = run_systemd_lsp(&[file.to_str().unwrap()]);
+    assert!(
+        stdout.contains("Unknown directive 'NetworkName' in [Network] section"),
+        "Should validate against systemd-networkd directives:\n{}",
+        stdout
+    );
+}
+
+#[test]
+fn test_cli_target_version() {
+    let temp = tempfile::tempdir().unwrap();
+    let dir = temp.path().to_path_buf();
+    let file = dir.join("reload.service");
+    std::fs::write(
+        &file,
+        "[Service]\nType=notify-reload\nExecStart=/usr/bin/app\n",
+    )
+    .unwrap();
+    let path = file.to_str().unwrap();
+
+    let (stdout, _stderr, exit_code) = run_systemd_lsp(&[path]);
+    assert_eq!(exit_code, 0, "Valid without a target version:\n{}", stdout);
+
+    let (stdout, _stderr, _exit_code) = run_systemd_lsp(&["--target-version", "250", path]);
+    assert!(
+        stdout.contains("Type=notify-reload requires systemd 253, but the target version is 250"),
+        "Should warn about values newer than the target:\n{}",
+        stdout
+    );
+}
+
+#[test]
+fn test_cli_security() {
+    let (stdout, _stderr, exit_code) = run_systemd_lsp(&["security", "examples/example.service"]);
+
+    assert_eq!(exit_code, 0, "Expected exit code 0:\n{}", stdout);
+    assert!(
+        stdout.contains("✗ PrivateNetwork="),
+        "Should list failed checks:\n{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("✓ NoNewPrivileges="),
+        "Should list passed checks:\n{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("→ Overall exposure level for example.service: 2.1 OK"),
+        "Should print the overall exposure:\n{}",
+        stdout
+    );
+
+    let (_stdout, stderr, exit_code) = run_systemd_lsp(&["security", "examples/lan.network"]);
+    assert_eq!(exit_code, 1);
+    assert!(stderr.contains("no [Service] section"));
+}
+
+#[test]
+fn test_cli_enable() {
+    let temp = tempfile::tempdir().unwrap();
+    let dir = temp.path().to_path_buf();
+    std::fs::write(
+        dir.join("app.service"),
+        "[Service]\nExecStart=/usr/bin/app\n\n[Install]\nWantedBy=multi-user.target\nAlias=application.service\nAlso=app.socket\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("app.socket"),
+        "[Socket]\nListenStream=8080\n\n[Install]\nWantedBy=sockets.target\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("worker@.service"),
+        "[Service]\nExecStart=/usr/bin/worker %i\n\n[Install]\nWantedBy=multi-user.target\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("helper.service"),
+        "[Service]\nExecStart=/usr/bin/helper\n",
+    )
+    .unwrap();
+    let file = |name: &str| dir.join(name).to_str().unwrap().to_string();
+
+    let (stdout, _stderr, exit_code) = run_systemd_lsp(&["enable", &file("app.service")]);
+    assert_eq!(exit_code, 0);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(
+        lines,
+        [
+            format!(
+                "Created symlink /etc/systemd/system/app.service → {}.",
+                file("app.service")
+            ),
+            format!(
+                "Created symlink /etc/systemd/system/multi-user.target.wants/app.service → {}.",
+                file("app.service")
+            ),
+            format!(
+                "Created symlink /etc/systemd/system/application.service → {}.",
+                file("app.service")
+            ),
+            format!(
+                "Created symlink /etc/systemd/system/app.socket → {}.",
+                file("app.socket")
+            ),
+            format!(
+                "Created symlink /etc/systemd/system/sockets.target.wants/app.socket → {}.",
+                file("app.socket")
+            ),
+        ]
+    );
+
+    let (stdout, stderr, exit_code) = run_systemd_lsp(&["enable", &file("worker@.service")]);
+    assert_eq!(exit_code, 0);
+    assert!(!stdout.contains(".wants/"), "{}", stdout);
+    assert!(stderr.contains("pass --instance"), "{}", stderr);
+
+    let (stdout, _stderr, exit_code) =
+        run_systemd_lsp(&["enable", "--instance", "blue", &file("worker@.service")]);
+    assert_eq!(exit_code, 0);
+    assert!(
+        stdout.contains(&format!(
+            "Created symlink /etc/systemd/system/multi-user.target.wants/worker@blue.service → {}.",
+            file("worker@.service")
+        )),
+        "{}",
+        stdout
+    );
+
+    let (stdout, _stderr, exit_code) = run_systemd_lsp(&["enable", &file("helper.service")]);
+    assert_eq!(exit_code, 0);
+    assert!(stdout.contains("is not meant to be enabled"), "{}", stdout);
+}
+
+#[test]
+fn test_cli_graph() {
+    let temp = tempfile::tempdir().unwrap();
+    let dir = temp.path().to_path_buf();
+    std::fs::create_dir_all(dir.join("web.service.d")).unwrap();
+    std::fs::write(
+        dir.join("web.service"),
+        "[Unit]\nRequires=db.service\nAfter=db.service\n\n[Install]\nWantedBy=multi-user.target\n",
+    )
+    .unwrap();
+    std::fs::write(dir.join("db.service"), "[Unit]\nBefore=web.service\n").unwrap();
+    std::fs::write(
+        dir.join("web.service.d/10-cache.conf"),
+        "[Unit]\nWants=cache.service\n",
+    )
+    .unwrap();
+    let path = dir.to_str().unwrap();
+
+    let (stdout, _stderr, exit_code) = run_systemd_lsp(&["graph", path]);
+    assert_eq!(exit_code, 0);
+    assert!(stdout.starts_with("digraph units {"), "{}", stdout);
+    assert!(
+        stdout.contains("\"web.service\" -> \"db.service\" [color=\"black\", label=\"requires\"];"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout
+            .contains("\"web.service\" -> \"cache.service\" [color=\"grey66\", label=\"wants\"];"),
+        "Drop-ins should add dependencies:\n{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("\"multi-user.target\" -> \"web.service\""),
+        "{}",
+        stdout
+    );
+
+    let (stdout, _stderr, exit_code) = run_systemd_lsp(&["graph", "--format", "mermaid", path]);
+    assert_eq!(exit_code, 0);
+    assert!(stdout.starts_with("flowchart LR"), "{}", stdout);
+
+    let (stdout, _stderr, exit_code) = run_systemd_lsp(&["graph", "-f", "json", path]);
+    assert_eq!(exit_code, 0);
+    assert_eq!(
+        stdout
+            .matches("\"from\": \"web.service\",\n      \"to\": \"db.service\"")
+            .count(),
+        2,
+        "Requires= and After= should be separate edges, Before= in db.service the same one:\n{}",
+        stdout
+    );
+
+    let (_stdout, stderr, exit_code) = run_systemd_lsp(&["graph", "examples/lan.network"]);
+    assert_eq!(exit_code, 1);
+    assert!(stderr.contains("No unit files found"));
+}
+
+#[test]
+fn test_cli_ordering_cycles() {
+    let temp = tempfile::tempdir().unwrap();
+    let dir = temp.path().to_path_buf();
+    std::fs::write(
+        dir.join("a.service"),
+        "[Unit]\nAfter=b.service\nBefore=c.service\n",
+    )
+    .unwrap();
+    std::fs::write(dir.join("b.service"), "[Unit]\nAfter=c.service\n").unwrap();
+    std::fs::write(dir.join("c.service"), "[Unit]\nDescription=C\n").unwrap();
+
+    let (stdout, _stderr, _exit_code) = run_systemd_lsp(&[dir.to_str().unwrap()]);
+    assert_eq!(
+        stdout.matches("Ordering cycle:").count(),
+        3,
+        "Every entry in the cycle should report it, whichever file is checked first:\n{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("Ordering cycle: a.service starts after b.service, which starts after c.service, which starts after a.service"),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn test_cli_escape() {
+    let (stdout, _stderr, exit_code) = run_systemd_lsp(&[
+        "escape",
+        "--path",
+        "--suffix",
+        "mount",
+        "/var/lib/data",
+        "/mnt/my-disk",
+    ]);
+    assert_eq!(exit_code, 0);
+    assert_eq!(stdout, "var-lib-data.mount\nmnt-my\\x2ddisk.mount\n");
+
+    let (stdout, _stderr, exit_code) =
+        run_systemd_lsp(&["escape", "--template", "getty@.service", "tty 1"]);
+    assert_eq!(exit_code, 0);
+    assert_eq!(stdout, "getty@tty\\x201.service\n");
+
+    let (stdout, _stderr, _exit_code) =
+        run_systemd_lsp(&["escape", "--unescape", "--path", "mnt-my\\x2ddisk"]);
+    assert_eq!(stdout, "/mnt/my-disk\n");
+
+    let (stdout, _stderr, _exit_code) = run_systemd_lsp(&["escape", "--mangle", "sshd", "/home"]);
+    assert_eq!(stdout, "sshd.service\nhome.mount\n");
+
+    let (_stdout, stderr, exit_code) =
+        run_systemd_lsp(&["escape", "--template", "getty.service", "tty1"]);
+    assert_eq!(exit_code, 1);
+    assert!(
+        stderr.contains("'getty.service' is not a template"),
+        "{}",
+        stderr
+    );
+}
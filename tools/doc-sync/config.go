@@ -36,6 +36,8 @@ func NewConfig(systemdBackend, podmanBackend Backend) *Config {
 			// Podman sections
 			pm("Container"), pm("Volume"), pm("Network"), pm("Kube"),
 			pm("Pod"), pm("Build"), pm("Image"),
+
+			// docs/networkd and docs/conf are maintained by hand, see the README in each
 		},
 		SharedPages: map[string]string{
 			"exec": "https://www.freedesktop.org/software/systemd/man/latest/systemd.exec.html",